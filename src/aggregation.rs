@@ -0,0 +1,620 @@
+use crate::{
+    r1cs_to_qap::R1CSToQAP, Groth16, PreparedVerifyingKey, Proof, Randomizer, VerifyingKey,
+};
+use ark_ec::{
+    pairing::{Pairing, PairingOutput},
+    scalar_mul::fixed_base::FixedBase,
+    AffineRepr, CurveGroup, Group, VariableBaseMSM,
+};
+use ark_ff::{
+    field_hashers::{DefaultFieldHasher, HashToField},
+    Field, PrimeField, UniformRand, Zero,
+};
+use ark_poly::EvaluationDomain;
+use ark_relations::r1cs::SynthesisError;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{cfg_iter, iter, rand::RngCore, vec, vec::Vec};
+use sha2::Sha256;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// The domain separation tag of the challenges of an [`AggregateProof`].
+const AGGREGATION_DOMAIN: &[u8] = b"polymorphic-groth16-aggregation";
+
+/// An error of aggregating proofs or verifying an aggregate proof.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AggregationError {
+    /// The number of proofs is not a power of two of at least two, or exceeds
+    /// the aggregation key.
+    InvalidProofCount(usize),
+    /// The verifying key commits to witnesses, whose commitments are not
+    /// aggregated.
+    CommittedWitnesses,
+    /// The public inputs do not match the verifying key, or a challenge was
+    /// zero.
+    Synthesis(SynthesisError),
+}
+
+impl From<SynthesisError> for AggregationError {
+    fn from(e: SynthesisError) -> Self {
+        Self::Synthesis(e)
+    }
+}
+
+impl core::fmt::Display for AggregationError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::InvalidProofCount(n) => write!(f, "cannot aggregate {} proofs", n),
+            Self::CommittedWitnesses => write!(f, "proofs with witness commitments"),
+            Self::Synthesis(e) => e.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AggregationError {}
+
+/// The commitment key of the inner pairing product arguments of an
+/// [`AggregateProof`], made of the powers of two secrets `a` and `b`.
+///
+/// `n` proofs are committed to under `v_i = a^i * H` and `w_i = a^(n + i) * G`,
+/// and likewise for `b`, and the keys folded by the arguments are opened as
+/// KZG commitments. Whoever knows `a` or `b` can forge aggregate proofs, so
+/// verifiers must trust the setup that sampled them.
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct AggregationKey<E: Pairing> {
+    /// `a^i * G` for `i < 2 * max_proofs`.
+    pub g_a_powers: Vec<E::G1Affine>,
+    /// `b^i * G` for `i < 2 * max_proofs`.
+    pub g_b_powers: Vec<E::G1Affine>,
+    /// `a^i * H` for `i < max_proofs`.
+    pub h_a_powers: Vec<E::G2Affine>,
+    /// `b^i * H` for `i < max_proofs`.
+    pub h_b_powers: Vec<E::G2Affine>,
+}
+
+/// The part of an [`AggregationKey`] needed to check the openings of the
+/// folded keys.
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct AggregationVerifyingKey<E: Pairing> {
+    /// The generator `G` of `E::G1`.
+    pub g: E::G1Affine,
+    /// The generator `H` of `E::G2`.
+    pub h: E::G2Affine,
+    /// `a * G`.
+    pub g_a: E::G1Affine,
+    /// `b * G`.
+    pub g_b: E::G1Affine,
+    /// `a * H`.
+    pub h_a: E::G2Affine,
+    /// `b * H`.
+    pub h_b: E::G2Affine,
+}
+
+impl<E: Pairing> AggregationKey<E> {
+    /// Sample a key aggregating up to `max_proofs` proofs, which must be a
+    /// power of two of at least two.
+    pub fn setup<R: RngCore>(max_proofs: usize, rng: &mut R) -> Result<Self, AggregationError> {
+        if max_proofs < 2 || !max_proofs.is_power_of_two() {
+            return Err(AggregationError::InvalidProofCount(max_proofs));
+        }
+        let a = E::ScalarField::rand(rng);
+        let b = E::ScalarField::rand(rng);
+
+        let scalar_bits = E::ScalarField::MODULUS_BIT_SIZE as usize;
+        let g1_window = FixedBase::get_mul_window_size(4 * max_proofs);
+        let g1_table = FixedBase::get_window_table(scalar_bits, g1_window, E::G1::generator());
+        let g2_window = FixedBase::get_mul_window_size(2 * max_proofs);
+        let g2_table = FixedBase::get_window_table(scalar_bits, g2_window, E::G2::generator());
+
+        let g1_powers = |x| {
+            let powers = FixedBase::msm::<E::G1>(
+                scalar_bits,
+                g1_window,
+                &g1_table,
+                &powers(x, 2 * max_proofs),
+            );
+            E::G1::normalize_batch(&powers)
+        };
+        let g2_powers = |x| {
+            let powers =
+                FixedBase::msm::<E::G2>(scalar_bits, g2_window, &g2_table, &powers(x, max_proofs));
+            E::G2::normalize_batch(&powers)
+        };
+
+        Ok(Self {
+            g_a_powers: g1_powers(a),
+            g_b_powers: g1_powers(b),
+            h_a_powers: g2_powers(a),
+            h_b_powers: g2_powers(b),
+        })
+    }
+
+    /// The largest number of proofs the key aggregates.
+    pub fn max_proofs(&self) -> usize {
+        self.h_a_powers.len()
+    }
+
+    /// The key checking the aggregate proofs made with this key.
+    pub fn verifying_key(&self) -> AggregationVerifyingKey<E> {
+        AggregationVerifyingKey {
+            g: self.g_a_powers[0],
+            h: self.h_a_powers[0],
+            g_a: self.g_a_powers[1],
+            g_b: self.g_b_powers[1],
+            h_a: self.h_a_powers[1],
+            h_b: self.h_b_powers[1],
+        }
+    }
+}
+
+/// A commitment in `E::TargetField` under the `a` (`t`) and `b` (`u`) halves
+/// of an [`AggregationKey`].
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PairCommitment<E: Pairing> {
+    /// The commitment under the powers of `a`.
+    pub t: PairingOutput<E>,
+    /// The commitment under the powers of `b`.
+    pub u: PairingOutput<E>,
+}
+
+impl<E: Pairing> PairCommitment<E> {
+    /// `sum_i e(a_i, v_i) + e(w_i, b_i)` under both halves of the key.
+    fn pair(
+        a: &[E::G1Affine],
+        b: &[E::G2Affine],
+        v: (&[E::G2Affine], &[E::G2Affine]),
+        w: (&[E::G1Affine], &[E::G1Affine]),
+    ) -> Self {
+        Self {
+            t: E::multi_pairing(a.iter().chain(w.0).copied(), v.0.iter().chain(b).copied()),
+            u: E::multi_pairing(a.iter().chain(w.1).copied(), v.1.iter().chain(b).copied()),
+        }
+    }
+
+    /// `sum_i e(c_i, v_i)` under both halves of the key.
+    fn single(c: &[E::G1Affine], v: (&[E::G2Affine], &[E::G2Affine])) -> Self {
+        Self {
+            t: E::multi_pairing(c.iter().copied(), v.0.iter().copied()),
+            u: E::multi_pairing(c.iter().copied(), v.1.iter().copied()),
+        }
+    }
+
+    /// The commitment to the folded vectors, given the commitments to the
+    /// cross terms of a round with challenge `x`.
+    fn fold(&self, left: &Self, right: &Self, x: E::ScalarField, x_inv: E::ScalarField) -> Self {
+        Self {
+            t: left.t * x + self.t + right.t * x_inv,
+            u: left.u * x + self.u + right.u * x_inv,
+        }
+    }
+}
+
+/// The cross terms of a round of the inner pairing product arguments, which
+/// halve `A`, `B` and `C` into their left and right parts `_L` and `_R`.
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct GipaRound<E: Pairing> {
+    /// The commitment to `A_R` and `B_L`.
+    pub com_ab_left: PairCommitment<E>,
+    /// The commitment to `A_L` and `B_R`.
+    pub com_ab_right: PairCommitment<E>,
+    /// The commitment to `C_R`.
+    pub com_c_left: PairCommitment<E>,
+    /// The commitment to `C_L`.
+    pub com_c_right: PairCommitment<E>,
+    /// `sum_i e(A_R[i], B_L[i])`.
+    pub z_ab_left: PairingOutput<E>,
+    /// `sum_i e(A_L[i], B_R[i])`.
+    pub z_ab_right: PairingOutput<E>,
+    /// `sum_i r_L[i] * C_R[i]`.
+    pub z_c_left: E::G1Affine,
+    /// `sum_i r_R[i] * C_L[i]`.
+    pub z_c_right: E::G1Affine,
+}
+
+/// A proof that `n` Groth16 proofs verify against the same verifying key and
+/// randomizer, of size logarithmic in `n`.
+///
+/// The proofs are checked as the random linear combination
+/// `sum_i r^i e(A_i, B_i) = s e(alpha, beta) + e(sum_i r^i L_i, gamma) +
+/// e(Z_C - s (P + Q), delta)`, with `s = sum_i r^i`, where the first term
+/// `Z_AB` and `Z_C = sum_i r^i C_i` are shown to be consistent with the
+/// commitments to `A`, `B` and `C` by the inner pairing product arguments of
+/// [SnarkPack](https://eprint.iacr.org/2021/529).
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct AggregateProof<E: Pairing> {
+    /// The commitment to the `A` and `B` elements of the proofs.
+    pub com_ab: PairCommitment<E>,
+    /// The commitment to the `C` elements of the proofs.
+    pub com_c: PairCommitment<E>,
+    /// `sum_i r^i e(A_i, B_i)`.
+    pub z_ab: PairingOutput<E>,
+    /// `sum_i r^i C_i`.
+    pub z_c: E::G1Affine,
+    /// The cross terms of every round of the arguments.
+    pub rounds: Vec<GipaRound<E>>,
+    /// The folded `A`.
+    pub final_a: E::G1Affine,
+    /// The folded `B`, rescaled by the powers of `r`.
+    pub final_b: E::G2Affine,
+    /// The folded `C`.
+    pub final_c: E::G1Affine,
+    /// The folded keys `v` under `a` and `b`.
+    pub final_v: (E::G2Affine, E::G2Affine),
+    /// The folded keys `w`, rescaled by the powers of `r^-1`, under `a` and
+    /// `b`.
+    pub final_w: (E::G1Affine, E::G1Affine),
+    /// The KZG openings of `final_v`.
+    pub v_opening: (E::G2Affine, E::G2Affine),
+    /// The KZG openings of `final_w`.
+    pub w_opening: (E::G1Affine, E::G1Affine),
+}
+
+/// A Fiat-Shamir transcript, hashing everything appended since the last
+/// challenge together with that challenge.
+struct Transcript {
+    state: Vec<u8>,
+}
+
+impl Transcript {
+    /// A transcript bound to the verifying key, the randomizer and the public
+    /// inputs of the aggregated proofs.
+    fn new<E: Pairing>(
+        vk: &VerifyingKey<E>,
+        rnd: &Randomizer<E>,
+        public_inputs: &[Vec<E::ScalarField>],
+    ) -> Self {
+        let mut transcript = Self { state: Vec::new() };
+        transcript.append(vk);
+        transcript.append(&rnd.p);
+        transcript.append(&rnd.q);
+        transcript.append(&(public_inputs.len() as u64));
+        for inputs in public_inputs {
+            transcript.append(inputs);
+        }
+        transcript
+    }
+
+    fn append<T: CanonicalSerialize + ?Sized>(&mut self, value: &T) {
+        value
+            .serialize_compressed(&mut self.state)
+            .expect("serializing into a vector cannot fail");
+    }
+
+    /// The next challenge and its inverse.
+    fn challenge<F: PrimeField>(&mut self) -> Result<(F, F), AggregationError> {
+        let hasher = <DefaultFieldHasher<Sha256> as HashToField<F>>::new(AGGREGATION_DOMAIN);
+        let challenge: F = hasher.hash_to_field(&self.state, 1)[0];
+        self.state.clear();
+        self.append(&challenge);
+        let inverse = challenge
+            .inverse()
+            .ok_or(SynthesisError::UnexpectedIdentity)?;
+        Ok((challenge, inverse))
+    }
+}
+
+/// `1, x, ..., x^(len - 1)`.
+fn powers<F: Field>(x: F, len: usize) -> Vec<F> {
+    iter::successors(Some(F::one()), |power| Some(*power * x))
+        .take(len)
+        .collect()
+}
+
+/// `scalars[i] * bases[i]`.
+fn scale<G: AffineRepr>(bases: &[G], scalars: &[G::ScalarField]) -> Vec<G> {
+    let scaled: Vec<G::Group> = cfg_iter!(bases)
+        .zip(scalars)
+        .map(|(base, scalar)| *base * scalar)
+        .collect();
+    G::Group::normalize_batch(&scaled)
+}
+
+/// `left + x * right` for the halves of `bases`.
+fn fold<G: AffineRepr>(bases: &[G], x: G::ScalarField) -> Vec<G> {
+    let (left, right) = bases.split_at(bases.len() / 2);
+    let folded: Vec<G::Group> = cfg_iter!(left)
+        .zip(right)
+        .map(|(l, r)| *r * x + l)
+        .collect();
+    G::Group::normalize_batch(&folded)
+}
+
+/// The coefficients of `prod_j (1 + factors[j] X^(n / 2^(j + 1)))`, with
+/// `n = 2^factors.len()`: the exponents of a key folded with `factors`.
+fn folding_polynomial<F: Field>(factors: &[F]) -> Vec<F> {
+    let mut coeffs = vec![F::one()];
+    for factor in factors.iter().rev() {
+        let high: Vec<F> = coeffs.iter().map(|c| *c * factor).collect();
+        coeffs.extend(high);
+    }
+    coeffs
+}
+
+/// The evaluation of [`folding_polynomial`] at `point`.
+fn evaluate_folding_polynomial<F: Field>(factors: &[F], point: F) -> F {
+    let mut power = point;
+    let mut result = F::one();
+    for factor in factors.iter().rev() {
+        result *= F::one() + *factor * power;
+        power.square_in_place();
+    }
+    result
+}
+
+/// The coefficients of `(f(X) - f(z)) / (X - z)`.
+fn kzg_quotient<F: Field>(coeffs: &[F], z: F) -> Vec<F> {
+    let mut quotient = vec![F::zero(); coeffs.len() - 1];
+    let mut acc = F::zero();
+    for i in (1..coeffs.len()).rev() {
+        acc = coeffs[i] + z * acc;
+        quotient[i - 1] = acc;
+    }
+    quotient
+}
+
+/// The factors of the folding polynomials of the keys `v`, of the keys `w`
+/// rescaled by the powers of `r^-1` and of the powers of `r`.
+fn folding_factors<F: Field>(challenges: &[(F, F)], r_inv: F) -> (Vec<F>, Vec<F>) {
+    let v_factors: Vec<F> = challenges.iter().map(|(_, x_inv)| *x_inv).collect();
+    let mut w_factors = vec![F::zero(); challenges.len()];
+    let mut power = r_inv;
+    for (factor, (x, _)) in w_factors.iter_mut().zip(challenges).rev() {
+        *factor = *x * power;
+        power.square_in_place();
+    }
+    (v_factors, w_factors)
+}
+
+impl<E: Pairing, QAP: R1CSToQAP, D: EvaluationDomain<E::ScalarField>> Groth16<E, QAP, D> {
+    /// Aggregate `proofs` of the statements `public_inputs`, made with the
+    /// proving key of `vk` under the same randomizer `rnd`, into a proof of
+    /// size logarithmic in their number, which must be a power of two.
+    ///
+    /// An aggregate proof does not attest that the proofs are pairwise
+    /// distinct: the same proof may be aggregated twice, and rescaling `A` by
+    /// some `t` and `B` by `1/t` yields another valid proof of the same
+    /// statement. Whoever needs to deduplicate submissions has to do so on
+    /// the proofs themselves, before they are aggregated.
+    pub fn aggregate_proofs(
+        key: &AggregationKey<E>,
+        vk: &VerifyingKey<E>,
+        rnd: &Randomizer<E>,
+        public_inputs: &[Vec<E::ScalarField>],
+        proofs: &[Proof<E>],
+    ) -> Result<AggregateProof<E>, AggregationError> {
+        let n = proofs.len();
+        if n < 2 || !n.is_power_of_two() || n > key.max_proofs() || public_inputs.len() != n {
+            return Err(AggregationError::InvalidProofCount(n));
+        }
+        if !vk.commitment_bases_g1.is_empty() {
+            return Err(AggregationError::CommittedWitnesses);
+        }
+
+        let mut transcript = Transcript::new(vk, rnd, public_inputs);
+        let mut a: Vec<_> = proofs.iter().map(|proof| proof.a).collect();
+        let mut b: Vec<_> = proofs.iter().map(|proof| proof.b).collect();
+        let mut c: Vec<_> = proofs.iter().map(|proof| proof.c).collect();
+        let mut v = (key.h_a_powers[..n].to_vec(), key.h_b_powers[..n].to_vec());
+        let mut w = (
+            key.g_a_powers[n..2 * n].to_vec(),
+            key.g_b_powers[n..2 * n].to_vec(),
+        );
+
+        let com_ab = PairCommitment::pair(&a, &b, (&v.0, &v.1), (&w.0, &w.1));
+        let com_c = PairCommitment::single(&c, (&v.0, &v.1));
+        transcript.append(&com_ab);
+        transcript.append(&com_c);
+        let (r, r_inv) = transcript.challenge::<E::ScalarField>()?;
+
+        // Rescaling `B` by the powers of `r` and `w` by those of `r^-1` leaves
+        // `com_ab` unchanged.
+        let mut r_powers = powers(r, n);
+        b = scale(&b, &r_powers);
+        let r_inv_powers = powers(r_inv, n);
+        w = (scale(&w.0, &r_inv_powers), scale(&w.1, &r_inv_powers));
+        let z_ab = E::multi_pairing(a.iter().copied(), b.iter().copied());
+        let z_c = E::G1::msm_unchecked(&c, &r_powers).into_affine();
+        transcript.append(&z_ab);
+        transcript.append(&z_c);
+
+        let mut rounds = Vec::new();
+        let mut challenges = Vec::new();
+        while a.len() > 1 {
+            let m = a.len() / 2;
+            let (a_l, a_r) = a.split_at(m);
+            let (b_l, b_r) = b.split_at(m);
+            let (c_l, c_r) = c.split_at(m);
+            let (r_l, r_r) = r_powers.split_at(m);
+            let (v_a_l, v_a_r) = v.0.split_at(m);
+            let (v_b_l, v_b_r) = v.1.split_at(m);
+            let (w_a_l, w_a_r) = w.0.split_at(m);
+            let (w_b_l, w_b_r) = w.1.split_at(m);
+
+            let round = GipaRound {
+                com_ab_left: PairCommitment::pair(a_r, b_l, (v_a_l, v_b_l), (w_a_r, w_b_r)),
+                com_ab_right: PairCommitment::pair(a_l, b_r, (v_a_r, v_b_r), (w_a_l, w_b_l)),
+                com_c_left: PairCommitment::single(c_r, (v_a_l, v_b_l)),
+                com_c_right: PairCommitment::single(c_l, (v_a_r, v_b_r)),
+                z_ab_left: E::multi_pairing(a_r.iter().copied(), b_l.iter().copied()),
+                z_ab_right: E::multi_pairing(a_l.iter().copied(), b_r.iter().copied()),
+                z_c_left: E::G1::msm_unchecked(c_r, r_l).into_affine(),
+                z_c_right: E::G1::msm_unchecked(c_l, r_r).into_affine(),
+            };
+            transcript.append(&round);
+            let (x, x_inv) = transcript.challenge::<E::ScalarField>()?;
+
+            a = fold(&a, x);
+            b = fold(&b, x_inv);
+            c = fold(&c, x);
+            r_powers = r_l.iter().zip(r_r).map(|(l, r)| *l + x_inv * r).collect();
+            v = (fold(&v.0, x_inv), fold(&v.1, x_inv));
+            w = (fold(&w.0, x), fold(&w.1, x));
+            rounds.push(round);
+            challenges.push((x, x_inv));
+        }
+
+        let mut proof = AggregateProof {
+            com_ab,
+            com_c,
+            z_ab,
+            z_c,
+            rounds,
+            final_a: a[0],
+            final_b: b[0],
+            final_c: c[0],
+            final_v: (v.0[0], v.1[0]),
+            final_w: (w.0[0], w.1[0]),
+            v_opening: Default::default(),
+            w_opening: Default::default(),
+        };
+        let z = Self::opening_point(&mut transcript, &proof)?;
+
+        // `final_v` commits to the folding polynomial of `v` and `final_w` to
+        // that of the rescaled `w`, shifted by `X^n`.
+        let (v_factors, w_factors) = folding_factors(&challenges, r_inv);
+        let v_quotient = kzg_quotient(&folding_polynomial(&v_factors), z);
+        let mut w_coeffs = vec![E::ScalarField::zero(); n];
+        w_coeffs.extend(folding_polynomial(&w_factors));
+        let w_quotient = kzg_quotient(&w_coeffs, z);
+        proof.v_opening = (
+            E::G2::msm_unchecked(&key.h_a_powers, &v_quotient).into_affine(),
+            E::G2::msm_unchecked(&key.h_b_powers, &v_quotient).into_affine(),
+        );
+        proof.w_opening = (
+            E::G1::msm_unchecked(&key.g_a_powers, &w_quotient).into_affine(),
+            E::G1::msm_unchecked(&key.g_b_powers, &w_quotient).into_affine(),
+        );
+
+        Ok(proof)
+    }
+
+    /// Verify that `proof` aggregates a proof of each of the statements
+    /// `public_inputs`, in order, under the randomizer `rnd`.
+    pub fn verify_aggregate_proof(
+        key: &AggregationVerifyingKey<E>,
+        pvk: &PreparedVerifyingKey<E>,
+        rnd: &Randomizer<E>,
+        public_inputs: &[Vec<E::ScalarField>],
+        proof: &AggregateProof<E>,
+    ) -> Result<bool, AggregationError> {
+        let n = public_inputs.len();
+        if n < 2 || !n.is_power_of_two() {
+            return Err(AggregationError::InvalidProofCount(n));
+        }
+        if !pvk.vk.commitment_bases_g1.is_empty() {
+            return Err(AggregationError::CommittedWitnesses);
+        }
+        if public_inputs
+            .iter()
+            .any(|inputs| inputs.len() + 1 != pvk.vk.gamma_abc_g1.len())
+        {
+            return Err(SynthesisError::MalformedVerifyingKey.into());
+        }
+        if proof.rounds.len() != ark_std::log2(n) as usize {
+            return Ok(false);
+        }
+
+        let mut transcript = Transcript::new(&pvk.vk, rnd, public_inputs);
+        transcript.append(&proof.com_ab);
+        transcript.append(&proof.com_c);
+        let (r, r_inv) = transcript.challenge::<E::ScalarField>()?;
+        transcript.append(&proof.z_ab);
+        transcript.append(&proof.z_c);
+
+        let mut com_ab = proof.com_ab.clone();
+        let mut com_c = proof.com_c.clone();
+        let mut z_ab = proof.z_ab;
+        let mut z_c = proof.z_c.into_group();
+        let mut challenges = Vec::new();
+        for round in &proof.rounds {
+            transcript.append(round);
+            let (x, x_inv) = transcript.challenge::<E::ScalarField>()?;
+            com_ab = com_ab.fold(&round.com_ab_left, &round.com_ab_right, x, x_inv);
+            com_c = com_c.fold(&round.com_c_left, &round.com_c_right, x, x_inv);
+            z_ab = round.z_ab_left * x + z_ab + round.z_ab_right * x_inv;
+            z_c += round.z_c_left * x + round.z_c_right * x_inv;
+            challenges.push((x, x_inv));
+        }
+        let z = Self::opening_point(&mut transcript, proof)?;
+        let (v_factors, w_factors) = folding_factors(&challenges, r_inv);
+
+        // The inner pairing product arguments over the folded elements
+        let final_r = evaluate_folding_polynomial(&v_factors, r);
+        let gipa = com_ab
+            == PairCommitment::pair(
+                &[proof.final_a],
+                &[proof.final_b],
+                (&[proof.final_v.0], &[proof.final_v.1]),
+                (&[proof.final_w.0], &[proof.final_w.1]),
+            )
+            && com_c
+                == PairCommitment::single(
+                    &[proof.final_c],
+                    (&[proof.final_v.0], &[proof.final_v.1]),
+                )
+            && z_ab == E::pairing(proof.final_a, proof.final_b)
+            && z_c == proof.final_c * final_r;
+
+        // The KZG openings of the folded keys
+        let v_eval = evaluate_folding_polynomial(&v_factors, z);
+        let w_eval = z.pow([n as u64]) * evaluate_folding_polynomial(&w_factors, z);
+        let open_v = |g_x: E::G1Affine, final_v: E::G2Affine, opening: E::G2Affine| {
+            E::multi_pairing(
+                [g_x.into_group() - key.g * z, -key.g.into_group()],
+                [opening.into_group(), final_v.into_group() - key.h * v_eval],
+            )
+            .is_zero()
+        };
+        let open_w = |h_x: E::G2Affine, final_w: E::G1Affine, opening: E::G1Affine| {
+            E::multi_pairing(
+                [opening.into_group(), key.g * w_eval - final_w],
+                [h_x.into_group() - key.h * z, key.h.into_group()],
+            )
+            .is_zero()
+        };
+        let kzg = open_v(key.g_a, proof.final_v.0, proof.v_opening.0)
+            && open_v(key.g_b, proof.final_v.1, proof.v_opening.1)
+            && open_w(key.h_a, proof.final_w.0, proof.w_opening.0)
+            && open_w(key.h_b, proof.final_w.1, proof.w_opening.1);
+
+        // The random linear combination of the verification equations, whose
+        // `e(P + Q, delta)` term is shared by all the proofs
+        let r_powers = powers(r, n);
+        let s: E::ScalarField = r_powers.iter().sum();
+        let input_scalars: Vec<E::ScalarField> = (0..pvk.vk.gamma_abc_g1.len() - 1)
+            .map(|j| {
+                r_powers
+                    .iter()
+                    .zip(public_inputs)
+                    .map(|(power, inputs)| *power * inputs[j])
+                    .sum()
+            })
+            .collect();
+        let inputs = pvk.vk.gamma_abc_g1[0] * s
+            + E::G1::msm_unchecked(&pvk.vk.gamma_abc_g1[1..], &input_scalars);
+        let rhs = PairingOutput::<E>(pvk.alpha_g1_beta_g2) * s
+            + E::multi_pairing(
+                [inputs, proof.z_c.into_group() - (rnd.p + rnd.q) * s],
+                [pvk.vk.gamma_g2, pvk.vk.delta_g2],
+            );
+
+        Ok(gipa && kzg && proof.z_ab == rhs)
+    }
+
+    /// The point at which the folded keys of `proof` are opened.
+    fn opening_point(
+        transcript: &mut Transcript,
+        proof: &AggregateProof<E>,
+    ) -> Result<E::ScalarField, AggregationError> {
+        transcript.append(&proof.final_a);
+        transcript.append(&proof.final_b);
+        transcript.append(&proof.final_c);
+        transcript.append(&proof.final_v.0);
+        transcript.append(&proof.final_v.1);
+        transcript.append(&proof.final_w.0);
+        transcript.append(&proof.final_w.1);
+        Ok(transcript.challenge::<E::ScalarField>()?.0)
+    }
+}
@@ -0,0 +1,297 @@
+use ark_ff::{
+    field_hashers::{DefaultFieldHasher, HashToField},
+    Field, PrimeField,
+};
+use ark_relations::r1cs::{
+    ConstraintSynthesizer, ConstraintSystem, ConstraintSystemRef, LinearCombination,
+    Result as R1CSResult, SynthesisError, SynthesisMode, Variable,
+};
+use ark_std::vec::Vec;
+use sha2::Sha256;
+
+/// The domain separation tag of [`context_input`].
+const CONTEXT_DOMAIN: &[u8] = b"polymorphic-groth16-context";
+
+/// A circuit proving `K` satisfying assignments of copies of a circuit `C`
+/// with the same public inputs, which pairwise differ on the witnesses at the
+/// indices `witnesses` of their witness assignments.
+///
+/// Each copy is synthesized on its own and its constraints are embedded in
+/// the outer constraint system, over the public inputs of the first copy and
+/// fresh witnesses. A single proof of this circuit thus attests to `K`
+/// different solutions, whatever the randomizers of the proofs.
+pub struct DistinctWitnesses<C, const K: usize> {
+    /// The copies of the circuit, one per solution.
+    pub circuits: [C; K],
+    /// The indices in the witness assignment of each copy of the witnesses
+    /// of which any two copies must differ in at least one.
+    pub witnesses: Vec<usize>,
+}
+
+impl<C, const K: usize> DistinctWitnesses<C, K> {
+    /// Wrap the `K` copies `circuits`, which must differ on `witnesses`.
+    pub fn new(circuits: [C; K], witnesses: Vec<usize>) -> Self {
+        Self {
+            circuits,
+            witnesses,
+        }
+    }
+}
+
+/// The constraints and assignment of a copy of the circuit, synthesized in a
+/// constraint system of its own.
+struct SynthesizedCopy<F: Field> {
+    num_instance_variables: usize,
+    num_witness_variables: usize,
+    a: Vec<Vec<(F, usize)>>,
+    b: Vec<Vec<(F, usize)>>,
+    c: Vec<Vec<(F, usize)>>,
+    instance_assignment: Vec<F>,
+    witness_assignment: Vec<F>,
+}
+
+impl<F: Field> SynthesizedCopy<F> {
+    fn synthesize<C: ConstraintSynthesizer<F>>(
+        circuit: C,
+        outer: &ConstraintSystemRef<F>,
+    ) -> R1CSResult<Self> {
+        let cs = ConstraintSystem::new_ref();
+        cs.set_optimization_goal(outer.optimization_goal());
+        cs.set_mode(if outer.is_in_setup_mode() {
+            SynthesisMode::Setup
+        } else {
+            SynthesisMode::Prove {
+                construct_matrices: true,
+            }
+        });
+        circuit.generate_constraints(cs.clone())?;
+        cs.finalize();
+
+        let matrices = cs.to_matrices().ok_or(SynthesisError::MissingCS)?;
+        let cs = cs.into_inner().ok_or(SynthesisError::MissingCS)?;
+        Ok(Self {
+            num_instance_variables: matrices.num_instance_variables,
+            num_witness_variables: matrices.num_witness_variables,
+            a: matrices.a,
+            b: matrices.b,
+            c: matrices.c,
+            instance_assignment: cs.instance_assignment,
+            witness_assignment: cs.witness_assignment,
+        })
+    }
+
+    /// Allocate the witnesses of the copy in `cs`.
+    fn allocate_witnesses(&self, cs: &ConstraintSystemRef<F>) -> R1CSResult<Vec<Variable>> {
+        (0..self.num_witness_variables)
+            .map(|i| {
+                cs.new_witness_variable(|| {
+                    self.witness_assignment
+                        .get(i)
+                        .copied()
+                        .ok_or(SynthesisError::AssignmentMissing)
+                })
+            })
+            .collect()
+    }
+
+    /// Enforce the constraints of the copy in `cs`, over the outer variables
+    /// `instance` and `witnesses`.
+    fn enforce(
+        &self,
+        cs: &ConstraintSystemRef<F>,
+        instance: &[Variable],
+        witnesses: &[Variable],
+    ) -> R1CSResult<()> {
+        let lc = |row: &[(F, usize)]| {
+            LinearCombination(
+                row.iter()
+                    .map(|&(coeff, index)| match index.checked_sub(instance.len()) {
+                        None => (coeff, instance[index]),
+                        Some(i) => (coeff, witnesses[i]),
+                    })
+                    .collect(),
+            )
+        };
+        for ((a, b), c) in self.a.iter().zip(&self.b).zip(&self.c) {
+            cs.enforce_constraint(lc(a), lc(b), lc(c))?;
+        }
+        Ok(())
+    }
+}
+
+/// Enforce that the values of `left` and `right` differ in at least one
+/// position: every difference is proven zero or not by an inverse, and the
+/// number of nonzero differences by its own inverse.
+fn enforce_not_equal<F: PrimeField>(
+    cs: &ConstraintSystemRef<F>,
+    left: &[Variable],
+    right: &[Variable],
+) -> R1CSResult<()> {
+    let mut num_different = LinearCombination::zero();
+    let mut num_different_value = Some(F::zero());
+    for (&l, &r) in left.iter().zip(right) {
+        let difference = cs
+            .assigned_value(l)
+            .zip(cs.assigned_value(r))
+            .map(|(l, r)| l - r);
+        let inverse = cs.new_witness_variable(|| {
+            difference
+                .map(|d| d.inverse().unwrap_or_default())
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let different = cs.new_witness_variable(|| {
+            difference
+                .map(|d| if d.is_zero() { F::zero() } else { F::one() })
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // (l - r) * inverse = different, (l - r) * (1 - different) = 0
+        let difference_lc = LinearCombination::from(l) - r;
+        cs.enforce_constraint(
+            difference_lc.clone(),
+            LinearCombination::from(inverse),
+            LinearCombination::from(different),
+        )?;
+        cs.enforce_constraint(
+            difference_lc,
+            LinearCombination::from(Variable::One) - different,
+            LinearCombination::zero(),
+        )?;
+
+        num_different = num_different + different;
+        num_different_value =
+            num_different_value
+                .zip(difference)
+                .map(|(n, d)| if d.is_zero() { n } else { n + F::one() });
+    }
+
+    let inverse = cs.new_witness_variable(|| {
+        num_different_value
+            .and_then(|n| n.inverse())
+            .ok_or(SynthesisError::Unsatisfiable)
+    })?;
+    cs.enforce_constraint(
+        num_different,
+        LinearCombination::from(inverse),
+        LinearCombination::from(Variable::One),
+    )
+}
+
+impl<F: PrimeField, C: ConstraintSynthesizer<F>, const K: usize> ConstraintSynthesizer<F>
+    for DistinctWitnesses<C, K>
+{
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> R1CSResult<()> {
+        let copies = self
+            .circuits
+            .into_iter()
+            .map(|circuit| SynthesizedCopy::synthesize(circuit, &cs))
+            .collect::<R1CSResult<Vec<_>>>()?;
+        let first = match copies.first() {
+            Some(first) => first,
+            None => return Ok(()),
+        };
+        if self.witnesses.is_empty()
+            || self
+                .witnesses
+                .iter()
+                .any(|&i| i >= first.num_witness_variables)
+        {
+            return Err(SynthesisError::AssignmentMissing);
+        }
+        for copy in &copies {
+            if copy.num_instance_variables != first.num_instance_variables
+                || copy.num_witness_variables != first.num_witness_variables
+            {
+                return Err(SynthesisError::Unsatisfiable);
+            }
+            if !cs.is_in_setup_mode() && copy.instance_assignment != first.instance_assignment {
+                return Err(SynthesisError::Unsatisfiable);
+            }
+        }
+
+        // The public inputs are shared by all the copies.
+        let mut instance = Vec::with_capacity(first.num_instance_variables);
+        instance.push(Variable::One);
+        for i in 1..first.num_instance_variables {
+            instance.push(cs.new_input_variable(|| {
+                first
+                    .instance_assignment
+                    .get(i)
+                    .copied()
+                    .ok_or(SynthesisError::AssignmentMissing)
+            })?);
+        }
+
+        let mut chosen = Vec::with_capacity(K);
+        for copy in &copies {
+            let witnesses = copy.allocate_witnesses(&cs)?;
+            copy.enforce(&cs, &instance, &witnesses)?;
+            chosen.push(
+                self.witnesses
+                    .iter()
+                    .map(|&i| witnesses[i])
+                    .collect::<Vec<_>>(),
+            );
+        }
+
+        for (i, left) in chosen.iter().enumerate() {
+            for right in &chosen[i + 1..] {
+                enforce_not_equal(&cs, left, right)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The public input to which [`ContextBound`] binds `context`.
+pub fn context_input<F: PrimeField>(context: &[u8]) -> F {
+    let hasher = <DefaultFieldHasher<Sha256> as HashToField<F>>::new(CONTEXT_DOMAIN);
+    hasher.hash_to_field(context, 1)[0]
+}
+
+/// A circuit `C` whose proofs are bound to `context`, e.g. an encoding of the
+/// id of the prover, a nonce of the verifier and an expiry, so that a proof
+/// seen by others cannot be submitted again under another context.
+///
+/// The context is hashed into an extra public input after those of `C`, which
+/// is constrained so that it cannot be changed in a proof. Proofs are checked
+/// by [`verify_with_context`](crate::data_structures::PolymorphicSNARK::verify_with_context)
+/// with the public inputs of `C`. The encoding of `context` must be
+/// unambiguous, e.g. with length prefixes.
+///
+/// As the context is a public input, the same witness proven under different
+/// contexts gives distinct proofs. Under one randomizer they still share `B`,
+/// which the context does not enter, so
+/// [`compare_proofs`](crate::Groth16::compare_proofs) and
+/// [`compare_all_proofs`](crate::data_structures::PolymorphicSNARK::compare_all_proofs)
+/// catch the reuse, but under different randomizers nothing is shared and
+/// they cannot tell it apart from proofs of distinct witnesses. Binding stops
+/// a proof from being replayed, not a witness from being reused: for that,
+/// the circuit must expose a nullifier computed from the witness, as in
+/// [`submission`](crate::submission) and the `poll` module.
+pub struct ContextBound<C> {
+    /// The wrapped circuit.
+    pub circuit: C,
+    /// The context bytes the proofs are bound to.
+    pub context: Vec<u8>,
+}
+
+impl<C> ContextBound<C> {
+    /// Bind the proofs of `circuit` to `context`.
+    pub fn new(circuit: C, context: Vec<u8>) -> Self {
+        Self { circuit, context }
+    }
+}
+
+impl<F: PrimeField, C: ConstraintSynthesizer<F>> ConstraintSynthesizer<F> for ContextBound<C> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> R1CSResult<()> {
+        self.circuit.generate_constraints(cs.clone())?;
+        let context = cs.new_input_variable(|| Ok(context_input(&self.context)))?;
+        // context * 1 = context, so that the input has a nonzero polynomial
+        cs.enforce_constraint(
+            LinearCombination::from(context),
+            LinearCombination::from(Variable::One),
+            LinearCombination::from(context),
+        )
+    }
+}
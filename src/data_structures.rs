@@ -1,16 +1,34 @@
 use ark_ec::pairing::Pairing;
-use ark_ff::PrimeField;
-use ark_relations::r1cs::ConstraintSynthesizer;
+use ark_ff::{
+    field_hashers::{DefaultFieldHasher, HashToField},
+    PrimeField, Zero,
+};
+use ark_poly::{EvaluationDomain, GeneralEvaluationDomain};
+use ark_relations::r1cs::{
+    ConstraintMatrices, ConstraintSynthesizer, Result as R1CSResult, SynthesisError,
+};
 use ark_serialize::*;
 use ark_std::{vec::Vec, rand::{RngCore, CryptoRng}};
 
-#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+use crate::r1cs_to_qap::evaluate_constraint;
+use crate::secret::{MasterSecret, SecretScalar};
+use crate::Groth16;
+use sha2::{Digest, Sha256};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// The domain separation tag of [`Randomizer::derive`].
+const RANDOMIZER_DOMAIN: &[u8] = b"polymorphic-groth16-randomizer";
+
 /// The randomzier
+///
+/// The scalars `r` and `s` are secret: they are zeroed on drop, redacted from
+/// `Debug` output, and only serialized by [`export`](Self::export).
+#[derive(Clone, Debug, PartialEq)]
 pub struct Randomizer<E: Pairing> {
     /// Randomizer `r` in scaler.
-    pub r: E::ScalarField,
+    pub r: SecretScalar<E::ScalarField>,
     /// Randomizer `s` in scaler.
-    pub s: E::ScalarField,
+    pub s: SecretScalar<E::ScalarField>,
     /// The `P` element in `G1`
     pub p: E::G1Affine,
     /// The `Q` element in `G1`
@@ -20,14 +38,280 @@ pub struct Randomizer<E: Pairing> {
 impl<E: Pairing> Default for Randomizer<E> {
     fn default() -> Self {
         Self {
-            r: E::ScalarField::default(),
-            s: E::ScalarField::default(),
+            r: SecretScalar::default(),
+            s: SecretScalar::default(),
             p: E::G1Affine::default(),
             q: E::G1Affine::default(),
         }
     }
 }
 
+impl<E: Pairing> Randomizer<E> {
+    /// Derive the randomizer of the key `pk` for `context`, e.g. a round id,
+    /// from `master_secret`, so that it can be recomputed instead of stored.
+    ///
+    /// `r` and `s` are hashed to the field from the secret, a digest of the
+    /// verifying key and `context`, so that the randomizers of distinct
+    /// circuits or contexts are independent. Secrets per epoch derive from a
+    /// master secret by [`MasterSecret::derive_child`].
+    ///
+    /// Fails on a zero `r` or `s`, or on `r == s`, which would make `P` or
+    /// `Q` the identity.
+    pub fn derive(
+        pk: &ProvingKey<E>,
+        master_secret: &MasterSecret,
+        context: &[u8],
+    ) -> R1CSResult<Self> {
+        let mut message = Vec::new();
+        message.extend_from_slice(master_secret.expose());
+        message.extend_from_slice(&pk.vk.digest());
+        message.extend_from_slice(&(context.len() as u64).to_le_bytes());
+        message.extend_from_slice(context);
+        let hasher =
+            <DefaultFieldHasher<Sha256> as HashToField<E::ScalarField>>::new(RANDOMIZER_DOMAIN);
+        let mut scalars: Vec<E::ScalarField> = hasher.hash_to_field(&message, 2);
+        message.zeroize();
+
+        let (r, s) = (SecretScalar::new(scalars[0]), SecretScalar::new(scalars[1]));
+        scalars.zeroize();
+        if r.expose().is_zero() || s.expose().is_zero() || r == s {
+            return Err(SynthesisError::UnexpectedIdentity);
+        }
+        Ok(Groth16::<E>::randomizer_from_scalars(pk, r, s))
+    }
+
+    /// Write the randomizer, secrets included, to `writer`.
+    pub fn export<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.r.export(&mut writer)?;
+        self.s.export(&mut writer)?;
+        self.p.serialize_compressed(&mut writer)?;
+        self.q.serialize_compressed(&mut writer)
+    }
+
+    /// Read a randomizer written by [`export`](Self::export).
+    pub fn import<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        Ok(Self {
+            r: SecretScalar::import(&mut reader)?,
+            s: SecretScalar::import(&mut reader)?,
+            p: E::G1Affine::deserialize_compressed(&mut reader)?,
+            q: E::G1Affine::deserialize_compressed(&mut reader)?,
+        })
+    }
+
+    /// The public part of the randomizer.
+    pub fn public(&self) -> PublicRandomizer<E> {
+        PublicRandomizer {
+            p: self.p,
+            q: self.q,
+        }
+    }
+}
+
+/// The public part of a randomizer, with which proofs are verified but not
+/// made.
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PublicRandomizer<E: Pairing> {
+    /// The `P` element in `G1`
+    pub p: E::G1Affine,
+    /// The `Q` element in `G1`
+    pub q: E::G1Affine,
+}
+
+/// A randomizer to verify proofs under, whose `r` and `s` are zero, so that
+/// [`prepare_randomizer`](Groth16::prepare_randomizer) rejects it.
+impl<E: Pairing> From<PublicRandomizer<E>> for Randomizer<E> {
+    fn from(other: PublicRandomizer<E>) -> Self {
+        Self {
+            p: other.p,
+            q: other.q,
+            ..Self::default()
+        }
+    }
+}
+
+/// A randomizer whose secrets `r` and `s` are non-zero, for what only the
+/// party holding them can compute, such as
+/// [`witness_fingerprint`](Groth16::witness_fingerprint).
+#[derive(Clone, Debug, PartialEq)]
+pub struct SecretRandomizer<E: Pairing>(Randomizer<E>);
+
+impl<E: Pairing> SecretRandomizer<E> {
+    /// The underlying randomizer.
+    pub fn randomizer(&self) -> &Randomizer<E> {
+        &self.0
+    }
+
+    /// The public part of the randomizer.
+    pub fn public(&self) -> PublicRandomizer<E> {
+        self.0.public()
+    }
+}
+
+/// Fails on a zero `r` or `s`, e.g. of a randomizer made from a
+/// [`PublicRandomizer`].
+impl<E: Pairing> TryFrom<Randomizer<E>> for SecretRandomizer<E> {
+    type Error = SynthesisError;
+
+    fn try_from(rnd: Randomizer<E>) -> R1CSResult<Self> {
+        if rnd.r.expose().is_zero() || rnd.s.expose().is_zero() {
+            return Err(SynthesisError::UnexpectedIdentity);
+        }
+        Ok(Self(rnd))
+    }
+}
+
+impl<E: Pairing> From<SecretRandomizer<E>> for Randomizer<E> {
+    fn from(other: SecretRandomizer<E>) -> Self {
+        other.0
+    }
+}
+
+/// A randomizer together with the group elements the prover derives from it,
+/// so that proving many witnesses under it skips those multiplications.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PreparedProverRandomizer<E: Pairing> {
+    /// The underlying randomizer. Its `q` is the element `(r - s) * alpha * G`
+    /// the prover adds to `C`.
+    pub rnd: Randomizer<E>,
+    /// The element `r * delta * G` in `E::G1`.
+    pub r_delta_g1: E::G1Affine,
+    /// The element `s * delta * G` in `E::G1`.
+    pub s_delta_g1: E::G1Affine,
+    /// The element `s * delta * H` in `E::G2`.
+    pub s_delta_g2: E::G2Affine,
+}
+
+impl<E: Pairing> From<PreparedProverRandomizer<E>> for Randomizer<E> {
+    fn from(other: PreparedProverRandomizer<E>) -> Self {
+        other.rnd
+    }
+}
+
+/// The MSMs of the `A`, `B` and `C` elements of a proof over the assignment,
+/// before the randomizer and the key elements are added.
+pub(crate) struct ProofAccumulators<E: Pairing> {
+    /// The sum over the `a_query`.
+    pub(crate) a: E::G1,
+    /// The sum over the `b_g1_query`, zero if it was skipped.
+    pub(crate) b_g1: E::G1,
+    /// The sum over the `b_g2_query`.
+    pub(crate) b_g2: E::G2,
+    /// The sums over the `l_query` and the `h_query`.
+    pub(crate) c: E::G1,
+}
+
+/// The toxic waste of a [`ProvingKey`], with which proofs of any statement
+/// can be simulated under any randomizer.
+///
+/// Like the scalars of a [`Randomizer`], its scalars are zeroed on drop,
+/// redacted from `Debug` output, and only serialized by
+/// [`export`](Self::export).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Trapdoor<E: Pairing> {
+    /// The `alpha` of the key.
+    pub alpha: SecretScalar<E::ScalarField>,
+    /// The `beta` of the key.
+    pub beta: SecretScalar<E::ScalarField>,
+    /// The `gamma` of the key.
+    pub gamma: SecretScalar<E::ScalarField>,
+    /// The `delta` of the key.
+    pub delta: SecretScalar<E::ScalarField>,
+    /// The point `t` at which the QAP polynomials are evaluated in the key.
+    pub t: SecretScalar<E::ScalarField>,
+}
+
+impl<E: Pairing> Trapdoor<E> {
+    /// Write the trapdoor to `writer`.
+    pub fn export<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.alpha.export(&mut writer)?;
+        self.beta.export(&mut writer)?;
+        self.gamma.export(&mut writer)?;
+        self.delta.export(&mut writer)?;
+        self.t.export(&mut writer)
+    }
+
+    /// Read a trapdoor written by [`export`](Self::export).
+    pub fn import<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        Ok(Self {
+            alpha: SecretScalar::import(&mut reader)?,
+            beta: SecretScalar::import(&mut reader)?,
+            gamma: SecretScalar::import(&mut reader)?,
+            delta: SecretScalar::import(&mut reader)?,
+            t: SecretScalar::import(&mut reader)?,
+        })
+    }
+}
+
+/// The assignment behind a proof together with its randomizer-independent
+/// accumulators, from which a proof of a slightly changed assignment is
+/// computed without repeating the MSMs over unchanged variables.
+///
+/// The cache holds the witness in plaintext, and the accumulators are
+/// unblinded commitments to it. Like a [`SecretScalar`], it is zeroed on
+/// drop, redacted from `Debug` output, and only serialized by
+/// [`export`](Self::export).
+#[derive(Clone, PartialEq)]
+pub struct ProofCache<E: Pairing> {
+    /// The full assignment, starting with the constant `one`, followed by the
+    /// public inputs and the witness.
+    pub assignment: Vec<E::ScalarField>,
+    /// The MSM of the `A` query with the assignment.
+    pub a_acc: E::G1,
+    /// The MSM of the `B` query in `E::G1` with the assignment.
+    pub b_g1_acc: E::G1,
+    /// The MSM of the `B` query in `E::G2` with the assignment.
+    pub b_g2_acc: E::G2,
+    /// The MSM of the `L` query with the witness.
+    pub l_acc: E::G1,
+}
+
+impl<E: Pairing> ProofCache<E> {
+    /// Write the cache to `writer`, in the compressed `CanonicalSerialize`
+    /// encoding of its fields.
+    pub fn export<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.assignment.serialize_compressed(&mut writer)?;
+        self.a_acc.serialize_compressed(&mut writer)?;
+        self.b_g1_acc.serialize_compressed(&mut writer)?;
+        self.b_g2_acc.serialize_compressed(&mut writer)?;
+        self.l_acc.serialize_compressed(&mut writer)
+    }
+
+    /// Read a cache written by [`export`](Self::export).
+    pub fn import<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        Ok(Self {
+            assignment: Vec::deserialize_compressed(&mut reader)?,
+            a_acc: E::G1::deserialize_compressed(&mut reader)?,
+            b_g1_acc: E::G1::deserialize_compressed(&mut reader)?,
+            b_g2_acc: E::G2::deserialize_compressed(&mut reader)?,
+            l_acc: E::G1::deserialize_compressed(&mut reader)?,
+        })
+    }
+}
+
+impl<E: Pairing> core::fmt::Debug for ProofCache<E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("ProofCache(<redacted>)")
+    }
+}
+
+impl<E: Pairing> Zeroize for ProofCache<E> {
+    fn zeroize(&mut self) {
+        self.assignment.zeroize();
+        self.a_acc.zeroize();
+        self.b_g1_acc.zeroize();
+        self.b_g2_acc.zeroize();
+        self.l_acc.zeroize();
+    }
+}
+
+impl<E: Pairing> Drop for ProofCache<E> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl<E: Pairing> ZeroizeOnDrop for ProofCache<E> {}
+
 /// A proof in the Groth16 SNARK.
 #[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
 pub struct Proof<E: Pairing> {
@@ -49,6 +333,67 @@ impl<E: Pairing> Default for Proof<E> {
     }
 }
 
+/// A proof under a key that commits to some witnesses, made by
+/// [`create_committed_proof`](Groth16::create_committed_proof): a [`Proof`]
+/// along with the commitment `D` to the committed witnesses and the proof of
+/// knowledge of its opening.
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct CommittedProof<E: Pairing> {
+    /// The `A`, `B` and `C` elements.
+    pub proof: Proof<E>,
+    /// The `D` commitment to the committed witnesses in `G1`.
+    pub d: E::G1Affine,
+    /// The proof of knowledge of the opening of `d`.
+    pub opening: CommitmentOpeningProof<E>,
+}
+
+/// The domain separation tag of the challenge of a [`CommitmentOpeningProof`].
+const OPENING_CHALLENGE_DOMAIN: &[u8] = b"polymorphic-groth16-commitment-opening";
+
+/// A Schnorr proof of knowledge of the committed witnesses `w_i` and the
+/// blinding `v` opening the commitment
+/// `D = sum_i w_i * commitment_bases_g1[i] + v * eta_gamma_inv_g1` of a
+/// [`CommittedProof`], made non-interactive with the Fiat-Shamir transform.
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct CommitmentOpeningProof<E: Pairing> {
+    /// The commitment to the nonces, under the same bases as `D`.
+    pub t: E::G1Affine,
+    /// The responses for the committed witnesses, in the order of
+    /// `VerifyingKey::commitment_bases_g1`.
+    pub responses: Vec<E::ScalarField>,
+    /// The response for the blinding.
+    pub blinding_response: E::ScalarField,
+}
+
+impl<E: Pairing> CommitmentOpeningProof<E> {
+    /// The challenge of the opening proof `t` of the commitment `d`, bound to
+    /// the commitment bases of `vk` and to the `a`, `b` and `c` elements of
+    /// the proof.
+    pub(crate) fn challenge(
+        vk: &VerifyingKey<E>,
+        a: &E::G1Affine,
+        b: &E::G2Affine,
+        c: &E::G1Affine,
+        d: &E::G1Affine,
+        t: &E::G1Affine,
+    ) -> E::ScalarField {
+        let mut transcript = Vec::new();
+        vk.commitment_bases_g1
+            .serialize_compressed(&mut transcript)
+            .and_then(|_| vk.eta_gamma_inv_g1.serialize_compressed(&mut transcript))
+            .and_then(|_| a.serialize_compressed(&mut transcript))
+            .and_then(|_| b.serialize_compressed(&mut transcript))
+            .and_then(|_| c.serialize_compressed(&mut transcript))
+            .and_then(|_| d.serialize_compressed(&mut transcript))
+            .and_then(|_| t.serialize_compressed(&mut transcript))
+            .expect("serializing into a vector cannot fail");
+        let hasher = <DefaultFieldHasher<Sha256> as HashToField<E::ScalarField>>::new(
+            OPENING_CHALLENGE_DOMAIN,
+        );
+        hasher.hash_to_field(&transcript, 1)[0]
+    }
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 ////////////////////////////////////////////////////////////////////////////////
 
@@ -65,6 +410,22 @@ pub struct VerifyingKey<E: Pairing> {
     pub delta_g2: E::G2Affine,
     /// The `gamma^{-1} * (beta * a_i + alpha * b_i + c_i) * H`, where `H` is the generator of `E::G1`.
     pub gamma_abc_g1: Vec<E::G1Affine>,
+    /// The `gamma^{-1} * (beta * a_i + alpha * b_i + c_i) * G` of the committed
+    /// witnesses, in the order of `ProvingKey::committed_witnesses`.
+    pub commitment_bases_g1: Vec<E::G1Affine>,
+    /// The `eta * gamma^{-1} * G` blinding base of the commitment `D`, the
+    /// identity if no witness is committed.
+    pub eta_gamma_inv_g1: E::G1Affine,
+}
+
+impl<E: Pairing> VerifyingKey<E> {
+    /// The SHA-256 digest of the compressed key, which identifies the circuit.
+    pub fn digest(&self) -> [u8; 32] {
+        let mut bytes = Vec::new();
+        self.serialize_compressed(&mut bytes)
+            .expect("serializing into a vector cannot fail");
+        Sha256::digest(&bytes).into()
+    }
 }
 
 impl<E: Pairing> Default for VerifyingKey<E> {
@@ -75,6 +436,8 @@ impl<E: Pairing> Default for VerifyingKey<E> {
             gamma_g2: E::G2Affine::default(),
             delta_g2: E::G2Affine::default(),
             gamma_abc_g1: Vec::new(),
+            commitment_bases_g1: Vec::new(),
+            eta_gamma_inv_g1: E::G1Affine::default(),
         }
     }
 }
@@ -91,6 +454,9 @@ pub struct PreparedVerifyingKey<E: Pairing> {
     pub gamma_g2_neg_pc: E::G2Prepared,
     /// The element `- delta * H` in `E::G2`, prepared for use in pairings.
     pub delta_g2_neg_pc: E::G2Prepared,
+    /// Window tables for the bases of the public inputs in `vk.gamma_abc_g1`.
+    /// Without them, the public inputs are prepared with a single MSM.
+    pub gamma_abc_g1_tables: Option<GammaAbcTables<E>>,
 }
 
 impl<E: Pairing> From<PreparedVerifyingKey<E>> for VerifyingKey<E> {
@@ -112,15 +478,201 @@ impl<E: Pairing> Default for PreparedVerifyingKey<E> {
             alpha_g1_beta_g2: E::TargetField::default(),
             gamma_g2_neg_pc: E::G2Prepared::default(),
             delta_g2_neg_pc: E::G2Prepared::default(),
+            gamma_abc_g1_tables: None,
         }
     }
 }
 
+/// Fixed-base window tables for the bases `gamma_abc_g1[1..]` of the public
+/// inputs, so that preparing the inputs only takes additions.
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct GammaAbcTables<E: Pairing> {
+    /// The window size in bits.
+    pub window: usize,
+    /// The table of each public input, as built by
+    /// `FixedBase::get_window_table`.
+    pub tables: Vec<Vec<Vec<E::G1Affine>>>,
+}
+
+impl<E: Pairing> GammaAbcTables<E> {
+    /// The size in bytes of the tables with windows of `window` bits for
+    /// `num_inputs` public inputs.
+    pub fn size_in_bytes(num_inputs: usize, window: usize) -> usize {
+        let scalar_bits = E::ScalarField::MODULUS_BIT_SIZE as usize;
+        let num_windows = scalar_bits.div_ceil(window);
+        num_inputs
+            .saturating_mul(num_windows << window)
+            .saturating_mul(core::mem::size_of::<E::G1Affine>())
+    }
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 ////////////////////////////////////////////////////////////////////////////////
 
+/// A bitmap over the variables of a circuit that records which of them have a
+/// non-zero query element, so that a proving key only stores the bases for
+/// those variables and the prover can skip the matching scalars.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct QueryDensity {
+    bits: Vec<u64>,
+    len: usize,
+}
+
+impl QueryDensity {
+    /// Returns the density of length `len` with no variable set.
+    pub fn new(len: usize) -> Self {
+        Self {
+            bits: vec![0; len.div_ceil(64)],
+            len,
+        }
+    }
+
+    /// The number of variables tracked.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether no variables are tracked.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Marks variable `i` as having a non-zero query element.
+    ///
+    /// # Panics
+    /// Panics if `i` is out of bounds.
+    pub fn set(&mut self, i: usize) {
+        assert!(i < self.len, "variable {} out of bounds", i);
+        self.bits[i / 64] |= 1 << (i % 64);
+    }
+
+    /// Whether variable `i` has a non-zero query element.
+    pub fn get(&self, i: usize) -> bool {
+        i < self.len && self.bits[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    /// The number of variables with a non-zero query element, i.e. the number
+    /// of bases stored for the query.
+    pub fn count_ones(&self) -> usize {
+        self.bits
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    /// The number of variables before `i` with a non-zero query element, i.e.
+    /// the position of the base of variable `i` in the query if it is set.
+    pub fn rank(&self, i: usize) -> usize {
+        let i = i.min(self.len);
+        let full_words = self.bits[..i / 64]
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum::<usize>();
+        let rest = self.bits.get(i / 64).map_or(0, |word| {
+            (word & ((1 << (i % 64)) - 1)).count_ones() as usize
+        });
+        full_words + rest
+    }
+
+    /// Iterates over the bits of all tracked variables, in order.
+    pub fn iter(&self) -> impl Iterator<Item = bool> + '_ {
+        (0..self.len).map(move |i| self.get(i))
+    }
+
+    /// Keeps the elements of `values` whose variable is set. `values` is
+    /// indexed from variable `offset` onwards.
+    pub fn filter<T: Copy>(&self, offset: usize, values: &[T]) -> Vec<T> {
+        values
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| self.get(offset + i))
+            .map(|(_, value)| *value)
+            .collect()
+    }
+
+    /// Keeps the items of `values` whose variable is set. `values` is indexed
+    /// from the first variable onwards.
+    pub fn select<'a, I>(&'a self, values: I) -> impl Iterator<Item = I::Item> + 'a
+    where
+        I: IntoIterator,
+        I::IntoIter: 'a,
+    {
+        values
+            .into_iter()
+            .zip(self.iter())
+            .filter_map(|(value, set)| set.then_some(value))
+    }
+}
+
+impl FromIterator<bool> for QueryDensity {
+    fn from_iter<I: IntoIterator<Item = bool>>(iter: I) -> Self {
+        let mut density = Self::default();
+        for bit in iter {
+            if density.len % 64 == 0 {
+                density.bits.push(0);
+            }
+            density.len += 1;
+            if bit {
+                density.set(density.len - 1);
+            }
+        }
+        density
+    }
+}
+
+impl CanonicalSerialize for QueryDensity {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        self.len.serialize_with_mode(&mut writer, compress)?;
+        for word in &self.bits {
+            word.serialize_with_mode(&mut writer, compress)?;
+        }
+        Ok(())
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        self.len.serialized_size(compress) + self.bits.len() * 8
+    }
+}
+
+impl Valid for QueryDensity {
+    fn check(&self) -> Result<(), SerializationError> {
+        if self.bits.len() != self.len.div_ceil(64) {
+            return Err(SerializationError::InvalidData);
+        }
+        // Bits past `len` must be unset, so that `count_ones` is exact.
+        let unused_bits = self.bits.len() * 64 - self.len;
+        let last = self.bits.last().copied().unwrap_or(0);
+        if unused_bits > 0 && last >> (64 - unused_bits) != 0 {
+            return Err(SerializationError::InvalidData);
+        }
+        Ok(())
+    }
+}
+
+impl CanonicalDeserialize for QueryDensity {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let len = usize::deserialize_with_mode(&mut reader, compress, validate)?;
+        let bits = (0..len.div_ceil(64))
+            .map(|_| u64::deserialize_with_mode(&mut reader, compress, validate))
+            .collect::<Result<Vec<_>, _>>()?;
+        let density = Self { bits, len };
+        if let Validate::Yes = validate {
+            density.check()?;
+        }
+        Ok(density)
+    }
+}
+
 /// The prover key for for the Groth16 zkSNARK.
-#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize)]
 pub struct ProvingKey<E: Pairing> {
     /// The underlying verification key.
     pub vk: VerifyingKey<E>,
@@ -130,16 +682,270 @@ pub struct ProvingKey<E: Pairing> {
     pub delta_g1: E::G1Affine,
     /// The element `delta * G` in `E::G2`.
     pub delta_g2: E::G2Affine,
-    /// The elements `a_i * G` in `E::G1`.
+    /// The elements `a_i * G` in `E::G1`, for the variables set in
+    /// `a_query_density`.
     pub a_query: Vec<E::G1Affine>,
-    /// The elements `b_i * G` in `E::G1`.
+    /// The variables `i` with a non-zero `a_i`.
+    pub a_query_density: QueryDensity,
+    /// The elements `b_i * G` in `E::G1`, for the variables set in
+    /// `b_query_density`.
     pub b_g1_query: Vec<E::G1Affine>,
-    /// The elements `b_i * H` in `E::G2`.
+    /// The elements `b_i * H` in `E::G2`, for the variables set in
+    /// `b_query_density`.
     pub b_g2_query: Vec<E::G2Affine>,
+    /// The variables `i` with a non-zero `b_i`.
+    pub b_query_density: QueryDensity,
     /// The elements `h_i * G` in `E::G1`.
     pub h_query: Vec<E::G1Affine>,
-    /// The elements `l_i * G` in `E::G1`.
+    /// The elements `l_i * G` in `E::G1`, the identity for the committed
+    /// witnesses.
     pub l_query: Vec<E::G1Affine>,
+    /// The witnesses committed in the `D` element of proofs, as indices into
+    /// the witness assignment.
+    pub committed_witnesses: Vec<usize>,
+    /// The element `eta * delta^{-1} * G` in `E::G1`, which cancels the
+    /// blinding of `D` in `C`.
+    pub eta_delta_inv_g1: E::G1Affine,
+}
+
+impl<E: Pairing> ProvingKey<E> {
+    /// Checks that the queries have one element per variable set in their
+    /// density, and that the densities, the `L` query and the committed
+    /// witnesses agree on the number of variables.
+    fn check_queries(&self) -> Result<(), SerializationError> {
+        let num_variables = self.a_query_density.len();
+        let num_witness_variables = self.l_query.len();
+        if self.a_query.len() != self.a_query_density.count_ones()
+            || self.b_g1_query.len() != self.b_query_density.count_ones()
+            || self.b_g2_query.len() != self.b_query_density.count_ones()
+            || self.b_query_density.len() != num_variables
+            || self.vk.gamma_abc_g1.len() + num_witness_variables != num_variables
+            || self
+                .committed_witnesses
+                .iter()
+                .any(|&i| i >= num_witness_variables)
+        {
+            return Err(SerializationError::InvalidData);
+        }
+        Ok(())
+    }
+}
+
+impl<E: Pairing> Valid for ProvingKey<E> {
+    fn check(&self) -> Result<(), SerializationError> {
+        self.vk.check()?;
+        self.beta_g1.check()?;
+        self.delta_g1.check()?;
+        self.delta_g2.check()?;
+        self.a_query.check()?;
+        self.a_query_density.check()?;
+        self.b_g1_query.check()?;
+        self.b_g2_query.check()?;
+        self.b_query_density.check()?;
+        self.h_query.check()?;
+        self.l_query.check()?;
+        self.eta_delta_inv_g1.check()?;
+        self.check_queries()
+    }
+}
+
+impl<E: Pairing> CanonicalDeserialize for ProvingKey<E> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let pk = Self {
+            vk: VerifyingKey::deserialize_with_mode(&mut reader, compress, validate)?,
+            beta_g1: E::G1Affine::deserialize_with_mode(&mut reader, compress, validate)?,
+            delta_g1: E::G1Affine::deserialize_with_mode(&mut reader, compress, validate)?,
+            delta_g2: E::G2Affine::deserialize_with_mode(&mut reader, compress, validate)?,
+            a_query: Vec::deserialize_with_mode(&mut reader, compress, validate)?,
+            a_query_density: QueryDensity::deserialize_with_mode(&mut reader, compress, validate)?,
+            b_g1_query: Vec::deserialize_with_mode(&mut reader, compress, validate)?,
+            b_g2_query: Vec::deserialize_with_mode(&mut reader, compress, validate)?,
+            b_query_density: QueryDensity::deserialize_with_mode(&mut reader, compress, validate)?,
+            h_query: Vec::deserialize_with_mode(&mut reader, compress, validate)?,
+            l_query: Vec::deserialize_with_mode(&mut reader, compress, validate)?,
+            committed_witnesses: Vec::deserialize_with_mode(&mut reader, compress, validate)?,
+            eta_delta_inv_g1: E::G1Affine::deserialize_with_mode(&mut reader, compress, validate)?,
+        };
+        if let Validate::Yes = validate {
+            pk.check_queries()?;
+        }
+        Ok(pk)
+    }
+}
+
+/// A proving key bundled with the constraint matrices of its circuit, so that
+/// the prover only has to supply witness values.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProverIndex<
+    E: Pairing,
+    D: EvaluationDomain<E::ScalarField> = GeneralEvaluationDomain<<E as Pairing>::ScalarField>,
+> {
+    /// The underlying proving key.
+    pub pk: ProvingKey<E>,
+    /// The R1CS matrices of the circuit, as produced during setup.
+    pub matrices: ConstraintMatrices<E::ScalarField>,
+    /// The evaluation domain of the QAP.
+    pub domain: D,
+}
+
+impl<E: Pairing, D: EvaluationDomain<E::ScalarField>> ProverIndex<E, D> {
+    /// The number of instance variables of the circuit, including the
+    /// constant `one`.
+    pub fn num_instance_variables(&self) -> usize {
+        self.matrices.num_instance_variables
+    }
+
+    /// The number of witness variables of the circuit.
+    pub fn num_witness_variables(&self) -> usize {
+        self.matrices.num_witness_variables
+    }
+
+    /// The number of constraints of the circuit.
+    pub fn num_constraints(&self) -> usize {
+        self.matrices.num_constraints
+    }
+
+    /// Returns the index of the first constraint that `full_assignment`, the
+    /// instance followed by the witness assignment, does not satisfy, or
+    /// `None` if it satisfies all of them.
+    ///
+    /// # Panics
+    /// Panics if `full_assignment` is shorter than the number of variables.
+    pub fn which_is_unsatisfied(&self, full_assignment: &[E::ScalarField]) -> Option<usize> {
+        let rows = self
+            .matrices
+            .a
+            .iter()
+            .zip(&self.matrices.b)
+            .zip(&self.matrices.c);
+        for (i, ((a, b), c)) in rows.enumerate() {
+            let a: E::ScalarField = evaluate_constraint(a, full_assignment);
+            let b: E::ScalarField = evaluate_constraint(b, full_assignment);
+            let c: E::ScalarField = evaluate_constraint(c, full_assignment);
+            if a * b != c {
+                return Some(i);
+            }
+        }
+        None
+    }
+
+    /// Checks that the matrices are consistent with the recorded sizes and
+    /// only reference existing variables.
+    fn check_matrices(&self) -> Result<(), SerializationError> {
+        let num_instance_variables = self.matrices.num_instance_variables;
+        let num_variables = num_instance_variables + self.matrices.num_witness_variables;
+        let num_constraints = self.matrices.num_constraints;
+        if self.matrices.b.len() != num_constraints
+            || self.matrices.c.len() != num_constraints
+            || self.domain.size() < num_constraints + num_instance_variables
+        {
+            return Err(SerializationError::InvalidData);
+        }
+        let in_range = |m: &[Vec<(E::ScalarField, usize)>]| {
+            m.iter().flatten().all(|(_, index)| *index < num_variables)
+        };
+        if !(in_range(&self.matrices.a) && in_range(&self.matrices.b) && in_range(&self.matrices.c))
+        {
+            return Err(SerializationError::InvalidData);
+        }
+        Ok(())
+    }
+}
+
+impl<E: Pairing, D: EvaluationDomain<E::ScalarField>> CanonicalSerialize for ProverIndex<E, D> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        self.pk.serialize_with_mode(&mut writer, compress)?;
+        self.matrices
+            .num_instance_variables
+            .serialize_with_mode(&mut writer, compress)?;
+        self.matrices
+            .num_witness_variables
+            .serialize_with_mode(&mut writer, compress)?;
+        self.matrices.a.serialize_with_mode(&mut writer, compress)?;
+        self.matrices.b.serialize_with_mode(&mut writer, compress)?;
+        self.matrices.c.serialize_with_mode(&mut writer, compress)?;
+        self.domain.serialize_with_mode(&mut writer, compress)
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        self.pk.serialized_size(compress)
+            + self
+                .matrices
+                .num_instance_variables
+                .serialized_size(compress)
+            + self
+                .matrices
+                .num_witness_variables
+                .serialized_size(compress)
+            + self.matrices.a.serialized_size(compress)
+            + self.matrices.b.serialized_size(compress)
+            + self.matrices.c.serialized_size(compress)
+            + self.domain.serialized_size(compress)
+    }
+}
+
+impl<E: Pairing, D: EvaluationDomain<E::ScalarField>> Valid for ProverIndex<E, D> {
+    fn check(&self) -> Result<(), SerializationError> {
+        self.pk.check()?;
+        self.check_matrices()
+    }
+}
+
+impl<E: Pairing, D: EvaluationDomain<E::ScalarField>> CanonicalDeserialize for ProverIndex<E, D> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let pk = ProvingKey::deserialize_with_mode(&mut reader, compress, validate)?;
+        let num_instance_variables = usize::deserialize_with_mode(&mut reader, compress, validate)?;
+        let num_witness_variables = usize::deserialize_with_mode(&mut reader, compress, validate)?;
+        let a = Vec::<Vec<(E::ScalarField, usize)>>::deserialize_with_mode(
+            &mut reader,
+            compress,
+            validate,
+        )?;
+        let b = Vec::<Vec<(E::ScalarField, usize)>>::deserialize_with_mode(
+            &mut reader,
+            compress,
+            validate,
+        )?;
+        let c = Vec::<Vec<(E::ScalarField, usize)>>::deserialize_with_mode(
+            &mut reader,
+            compress,
+            validate,
+        )?;
+        let domain = D::deserialize_with_mode(&mut reader, compress, validate)?;
+
+        let matrices = ConstraintMatrices {
+            num_instance_variables,
+            num_witness_variables,
+            num_constraints: a.len(),
+            a_num_non_zero: a.iter().map(Vec::len).sum(),
+            b_num_non_zero: b.iter().map(Vec::len).sum(),
+            c_num_non_zero: c.iter().map(Vec::len).sum(),
+            a,
+            b,
+            c,
+        };
+        let index = Self {
+            pk,
+            matrices,
+            domain,
+        };
+        if let Validate::Yes = validate {
+            index.check_matrices()?;
+        }
+        Ok(index)
+    }
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -155,8 +961,9 @@ pub trait PolymorphicSNARK<F: PrimeField> {
     /// circuit *C*.
     type VerifyingKey: Clone + CanonicalSerialize + CanonicalDeserialize;
 
-    /// The randomizer output by the prover
-    type Randomizer: Clone + CanonicalSerialize + CanonicalDeserialize;
+    /// The randomizer output by the prover. It holds secrets of the prover,
+    /// so it is not required to be serializable.
+    type Randomizer: Clone;
 
     /// The proof output by the prover.
     type Proof: Clone + CanonicalSerialize + CanonicalDeserialize;
@@ -186,6 +993,19 @@ pub trait PolymorphicSNARK<F: PrimeField> {
         rnd: &Self::Randomizer
     ) -> Result<Self::Proof, Self::Error>;
 
+    /// Generates one proof per circuit in `circuits`, all under the same
+    /// randomizer. By default, this just invokes `prove` for every circuit.
+    fn prove_many<C: ConstraintSynthesizer<F> + Send>(
+        circuit_pk: &Self::ProvingKey,
+        circuits: Vec<C>,
+        rnd: &Self::Randomizer,
+    ) -> Result<Vec<Self::Proof>, Self::Error> {
+        circuits
+            .into_iter()
+            .map(|circuit| Self::prove(circuit_pk, circuit, rnd))
+            .collect()
+    }
+
     /// Preprocesses `circuit_vk` to enable faster verification.
     fn process_vk(
         circuit_vk: &Self::VerifyingKey,
@@ -201,6 +1021,24 @@ pub trait PolymorphicSNARK<F: PrimeField> {
         rnd: &Self::Randomizer
     ) -> Result<bool, Self::Error>;
 
+    /// Checks that `proof` is a valid proof of the satisfaction of circuit
+    /// encoded in `circuit_pvk` under `context`, with respect to the public
+    /// input `public_input` of the circuit that
+    /// [`ContextBound`](crate::circuits::ContextBound) wraps. By default, this
+    /// appends the [`context_input`](crate::circuits::context_input) of
+    /// `context` to `public_input` and invokes `verify_with_processed_vk`.
+    fn verify_with_context(
+        circuit_pvk: &Self::ProcessedVerifyingKey,
+        public_input: &[F],
+        proof: &Self::Proof,
+        rnd: &Self::Randomizer,
+        context: &[u8],
+    ) -> Result<bool, Self::Error> {
+        let mut public_input = public_input.to_vec();
+        public_input.push(crate::circuits::context_input(context));
+        Self::verify_with_processed_vk(circuit_pvk, &public_input, proof, rnd)
+    }
+
     /// Checks that all `proofs` are all distinct 
     fn compare_all_proofs(
         proofs: &Vec<Self::Proof>
@@ -0,0 +1,124 @@
+//! Fingerprints of witnesses that do not depend on the randomizer, for the
+//! party that holds its secret `r`.
+//!
+//! Under a randomizer, `A = alpha * G + sum_i a_i(x) w_i * G + r * delta * G`,
+//! so `A - r * delta * G` is the same for a witness whatever the randomizer.
+//! [`compare_proofs`](crate::Groth16::compare_proofs) only catches a witness
+//! proven twice under one randomizer; its fingerprint catches it across
+//! rounds with fresh randomizers. Only the holder of `r` can compute it, and
+//! it must not be published: it is a deterministic function of the witness,
+//! by which guessed witnesses can be tested.
+//!
+//! The fingerprint is that of the proof as made by the prover, not of the
+//! witness alone: rescaling `A` by some `t` and `B` by `1/t` yields another
+//! valid proof of the same witness, with another fingerprint. The index thus
+//! catches a proof submitted again as it was made, but not a prover who
+//! rescales it to evade the index.
+
+use crate::{r1cs_to_qap::R1CSToQAP, Groth16, Proof, ProvingKey, SecretRandomizer};
+use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup};
+use ark_poly::EvaluationDomain;
+use ark_serialize::*;
+use ark_std::vec::Vec;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+/// The domain separation tag of witness fingerprints.
+const FINGERPRINT_DOMAIN: &[u8] = b"polymorphic-groth16-fingerprint";
+
+/// The fingerprint of the witness of a proof, the same under any randomizer
+/// of a proving key.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    CanonicalSerialize,
+    CanonicalDeserialize,
+)]
+pub struct WitnessFingerprint(pub [u8; 32]);
+
+impl<E: Pairing, QAP: R1CSToQAP, D: EvaluationDomain<E::ScalarField>> Groth16<E, QAP, D> {
+    /// The fingerprint of the witness of `proof` under the randomizer `rnd`
+    /// of `pk`, a digest of `A - r * delta * G`.
+    pub fn witness_fingerprint(
+        pk: &ProvingKey<E>,
+        rnd: &SecretRandomizer<E>,
+        proof: &Proof<E>,
+    ) -> WitnessFingerprint {
+        let r = &rnd.randomizer().r;
+        let witness_g1 = (proof.a.into_group() - pk.delta_g1 * r.expose()).into_affine();
+        let mut bytes = Vec::new();
+        witness_g1
+            .serialize_compressed(&mut bytes)
+            .expect("serializing into a vector cannot fail");
+        let digest = Sha256::new()
+            .chain_update(FINGERPRINT_DOMAIN)
+            .chain_update(bytes)
+            .finalize();
+        WitnessFingerprint(digest.into())
+    }
+}
+
+/// An index of the witnesses proven across rounds of a proving key, each
+/// round under its own randomizer, to catch a witness submitted again.
+///
+/// Proofs must be verified before they are inserted. The index is as secret
+/// as the randomizers: it is kept by the party that holds them.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FingerprintIndex {
+    rounds: HashMap<WitnessFingerprint, u64>,
+}
+
+impl FingerprintIndex {
+    /// An empty index.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the witness of `proof`, made under the randomizer `rnd` of
+    /// `pk` in `round`. Returns the first round in which the witness was
+    /// proven if it was already, and `None` otherwise.
+    pub fn insert<E: Pairing>(
+        &mut self,
+        pk: &ProvingKey<E>,
+        rnd: &SecretRandomizer<E>,
+        proof: &Proof<E>,
+        round: u64,
+    ) -> Option<u64> {
+        let fingerprint = Groth16::<E>::witness_fingerprint(pk, rnd, proof);
+        match self.rounds.get(&fingerprint) {
+            Some(&first) => Some(first),
+            None => {
+                self.rounds.insert(fingerprint, round);
+                None
+            },
+        }
+    }
+
+    /// The first round in which the witness of `proof`, made under the
+    /// randomizer `rnd` of `pk`, was recorded, if any.
+    pub fn lookup<E: Pairing>(
+        &self,
+        pk: &ProvingKey<E>,
+        rnd: &SecretRandomizer<E>,
+        proof: &Proof<E>,
+    ) -> Option<u64> {
+        let fingerprint = Groth16::<E>::witness_fingerprint(pk, rnd, proof);
+        self.rounds.get(&fingerprint).copied()
+    }
+
+    /// The number of distinct witnesses recorded.
+    pub fn len(&self) -> usize {
+        self.rounds.len()
+    }
+
+    /// Whether no witness was recorded.
+    pub fn is_empty(&self) -> bool {
+        self.rounds.is_empty()
+    }
+}
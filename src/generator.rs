@@ -1,18 +1,21 @@
-use crate::{r1cs_to_qap::R1CSToQAP, Groth16, ProvingKey, Vec, VerifyingKey};
+use crate::{
+    r1cs_to_qap::R1CSToQAP, secret::SecretScalar, Groth16, ProverIndex, ProvingKey, QueryDensity,
+    Trapdoor, Vec, VerifyingKey,
+};
 use ark_ec::{pairing::Pairing, scalar_mul::fixed_base::FixedBase, CurveGroup, Group};
 use ark_ff::{Field, PrimeField, UniformRand, Zero};
-use ark_poly::{EvaluationDomain, GeneralEvaluationDomain};
+use ark_poly::EvaluationDomain;
 use ark_relations::r1cs::{
     ConstraintSynthesizer, ConstraintSystem, OptimizationGoal, Result as R1CSResult,
     SynthesisError, SynthesisMode,
 };
 use ark_std::rand::Rng;
-use ark_std::{cfg_into_iter, cfg_iter};
+use zeroize::Zeroizing;
 
 #[cfg(feature = "parallel")]
 use rayon::prelude::*;
 
-impl<E: Pairing, QAP: R1CSToQAP> Groth16<E, QAP> {
+impl<E: Pairing, QAP: R1CSToQAP, D: EvaluationDomain<E::ScalarField>> Groth16<E, QAP, D> {
     /// Generates a random common reference string for
     /// a circuit using the provided R1CS-to-QAP reduction.
     #[inline]
@@ -23,15 +26,98 @@ impl<E: Pairing, QAP: R1CSToQAP> Groth16<E, QAP> {
     where
         C: ConstraintSynthesizer<E::ScalarField>,
     {
-        let alpha = E::ScalarField::rand(rng);
-        let beta = E::ScalarField::rand(rng);
-        let gamma = E::ScalarField::rand(rng);
-        let delta = E::ScalarField::rand(rng);
+        Self::generate_random_parameters_with_commitments(circuit, &[], rng)
+    }
+
+    /// Generates a random common reference string for a circuit using the
+    /// provided R1CS-to-QAP reduction, whose proofs commit to the witnesses
+    /// at the indices `committed_witnesses` of the witness assignment in
+    /// their `D` element. If any witness is committed, proofs are made by
+    /// [`create_committed_proof`](Self::create_committed_proof).
+    #[inline]
+    pub fn generate_random_parameters_with_commitments<C>(
+        circuit: C,
+        committed_witnesses: &[usize],
+        rng: &mut impl Rng,
+    ) -> R1CSResult<ProvingKey<E>>
+    where
+        C: ConstraintSynthesizer<E::ScalarField>,
+    {
+        Self::generate_random_index_with_commitments(circuit, committed_witnesses, rng)
+            .map(|index| index.pk)
+    }
+
+    /// Generates a random common reference string for a circuit using the
+    /// provided R1CS-to-QAP reduction, and keeps the circuit's constraint
+    /// matrices alongside it in a [`ProverIndex`].
+    #[inline]
+    pub fn generate_random_index_with_reduction<C>(
+        circuit: C,
+        rng: &mut impl Rng,
+    ) -> R1CSResult<ProverIndex<E, D>>
+    where
+        C: ConstraintSynthesizer<E::ScalarField>,
+    {
+        Self::generate_random_index_with_commitments(circuit, &[], rng)
+    }
+
+    /// Generates a random common reference string committing to the witnesses
+    /// at the indices `committed_witnesses`, as
+    /// [`generate_random_parameters_with_commitments`](Self::generate_random_parameters_with_commitments),
+    /// and keeps the circuit's constraint matrices alongside it in a
+    /// [`ProverIndex`].
+    pub fn generate_random_index_with_commitments<C>(
+        circuit: C,
+        committed_witnesses: &[usize],
+        rng: &mut impl Rng,
+    ) -> R1CSResult<ProverIndex<E, D>>
+    where
+        C: ConstraintSynthesizer<E::ScalarField>,
+    {
+        let alpha = SecretScalar::new(E::ScalarField::rand(rng));
+        let beta = SecretScalar::new(E::ScalarField::rand(rng));
+        let gamma = SecretScalar::new(E::ScalarField::rand(rng));
+        let delta = SecretScalar::new(E::ScalarField::rand(rng));
 
         let g1_generator = E::G1::rand(rng);
         let g2_generator = E::G2::rand(rng);
 
-        Self::generate_parameters_with_qap(
+        // Keys committing to no witness need no blinding base.
+        let eta = SecretScalar::new(if committed_witnesses.is_empty() {
+            E::ScalarField::zero()
+        } else {
+            E::ScalarField::rand(rng)
+        });
+
+        Self::generate_index_with_commitments(
+            circuit,
+            *alpha.expose(),
+            *beta.expose(),
+            *gamma.expose(),
+            *delta.expose(),
+            *eta.expose(),
+            g1_generator,
+            g2_generator,
+            committed_witnesses,
+            rng,
+        )
+    }
+
+    /// Create parameters for a circuit, given some toxic waste, R1CS to QAP calculator and group generators
+    pub fn generate_parameters_with_qap<C>(
+        circuit: C,
+        alpha: E::ScalarField,
+        beta: E::ScalarField,
+        gamma: E::ScalarField,
+        delta: E::ScalarField,
+        g1_generator: E::G1,
+        g2_generator: E::G2,
+        rng: &mut impl Rng,
+    ) -> R1CSResult<ProvingKey<E>>
+    where
+        C: ConstraintSynthesizer<E::ScalarField>,
+    {
+        Self::generate_index_with_qap(
             circuit,
             alpha,
             beta,
@@ -41,10 +127,15 @@ impl<E: Pairing, QAP: R1CSToQAP> Groth16<E, QAP> {
             g2_generator,
             rng,
         )
+        .map(|index| index.pk)
     }
 
-    /// Create parameters for a circuit, given some toxic waste, R1CS to QAP calculator and group generators
-    pub fn generate_parameters_with_qap<C>(
+    /// Create a prover index for a circuit, given some toxic waste, R1CS to QAP
+    /// calculator and group generators. This is
+    /// [`generate_parameters_with_qap`](Self::generate_parameters_with_qap)
+    /// that also returns the constraint matrices and evaluation domain.
+    #[allow(clippy::too_many_arguments)]
+    pub fn generate_index_with_qap<C>(
         circuit: C,
         alpha: E::ScalarField,
         beta: E::ScalarField,
@@ -53,11 +144,153 @@ impl<E: Pairing, QAP: R1CSToQAP> Groth16<E, QAP> {
         g1_generator: E::G1,
         g2_generator: E::G2,
         rng: &mut impl Rng,
-    ) -> R1CSResult<ProvingKey<E>>
+    ) -> R1CSResult<ProverIndex<E, D>>
+    where
+        C: ConstraintSynthesizer<E::ScalarField>,
+    {
+        Self::generate_index_with_commitments(
+            circuit,
+            alpha,
+            beta,
+            gamma,
+            delta,
+            E::ScalarField::zero(),
+            g1_generator,
+            g2_generator,
+            &[],
+            rng,
+        )
+    }
+
+    /// Create a prover index for a circuit whose proofs commit to the
+    /// witnesses at the indices `committed_witnesses`, given some toxic
+    /// waste including the blinding trapdoor `eta` of the commitment, R1CS to
+    /// QAP calculator and group generators.
+    ///
+    /// The committed witnesses get bases over `gamma` in the verification key
+    /// in place of their bases over `delta` in the L-query, as in
+    /// [LegoGroth16](https://eprint.iacr.org/2019/142.pdf).
+    #[allow(clippy::too_many_arguments)]
+    pub fn generate_index_with_commitments<C>(
+        circuit: C,
+        alpha: E::ScalarField,
+        beta: E::ScalarField,
+        gamma: E::ScalarField,
+        delta: E::ScalarField,
+        eta: E::ScalarField,
+        g1_generator: E::G1,
+        g2_generator: E::G2,
+        committed_witnesses: &[usize],
+        rng: &mut impl Rng,
+    ) -> R1CSResult<ProverIndex<E, D>>
+    where
+        C: ConstraintSynthesizer<E::ScalarField>,
+    {
+        Self::generate_index_and_trapdoor(
+            circuit,
+            alpha,
+            beta,
+            gamma,
+            delta,
+            eta,
+            g1_generator,
+            g2_generator,
+            committed_witnesses,
+            rng,
+        )
+        .map(|(index, _)| index)
+    }
+
+    /// Generates a random common reference string for a circuit using the
+    /// provided R1CS-to-QAP reduction, and returns its [`Trapdoor`] along
+    /// with it, e.g. to simulate proofs with
+    /// [`simulate_proof`](Self::simulate_proof).
+    ///
+    /// The trapdoor breaks the soundness of every proof under the key, and
+    /// must only be kept for testing.
+    #[inline]
+    pub fn generate_random_parameters_with_trapdoor<C>(
+        circuit: C,
+        rng: &mut impl Rng,
+    ) -> R1CSResult<(ProvingKey<E>, Trapdoor<E>)>
+    where
+        C: ConstraintSynthesizer<E::ScalarField>,
+    {
+        Self::generate_random_parameters_with_commitments_and_trapdoor(circuit, &[], rng)
+    }
+
+    /// Generates a random common reference string committing to the witnesses
+    /// at the indices `committed_witnesses`, as
+    /// [`generate_random_parameters_with_commitments`](Self::generate_random_parameters_with_commitments),
+    /// and returns its [`Trapdoor`] along with it, e.g. to simulate proofs
+    /// with [`simulate_committed_proof`](Self::simulate_committed_proof).
+    ///
+    /// The trapdoor breaks the soundness of every proof under the key, and
+    /// must only be kept for testing.
+    pub fn generate_random_parameters_with_commitments_and_trapdoor<C>(
+        circuit: C,
+        committed_witnesses: &[usize],
+        rng: &mut impl Rng,
+    ) -> R1CSResult<(ProvingKey<E>, Trapdoor<E>)>
+    where
+        C: ConstraintSynthesizer<E::ScalarField>,
+    {
+        let alpha = SecretScalar::new(E::ScalarField::rand(rng));
+        let beta = SecretScalar::new(E::ScalarField::rand(rng));
+        let gamma = SecretScalar::new(E::ScalarField::rand(rng));
+        let delta = SecretScalar::new(E::ScalarField::rand(rng));
+
+        let g1_generator = E::G1::rand(rng);
+        let g2_generator = E::G2::rand(rng);
+
+        // Keys committing to no witness need no blinding base.
+        let eta = SecretScalar::new(if committed_witnesses.is_empty() {
+            E::ScalarField::zero()
+        } else {
+            E::ScalarField::rand(rng)
+        });
+
+        Self::generate_index_and_trapdoor(
+            circuit,
+            *alpha.expose(),
+            *beta.expose(),
+            *gamma.expose(),
+            *delta.expose(),
+            *eta.expose(),
+            g1_generator,
+            g2_generator,
+            committed_witnesses,
+            rng,
+        )
+        .map(|(index, trapdoor)| (index.pk, trapdoor))
+    }
+
+    /// Create a prover index as
+    /// [`generate_index_with_commitments`](Self::generate_index_with_commitments),
+    /// along with the trapdoor of its key.
+    #[allow(clippy::too_many_arguments)]
+    fn generate_index_and_trapdoor<C>(
+        circuit: C,
+        alpha: E::ScalarField,
+        beta: E::ScalarField,
+        gamma: E::ScalarField,
+        delta: E::ScalarField,
+        eta: E::ScalarField,
+        g1_generator: E::G1,
+        g2_generator: E::G2,
+        committed_witnesses: &[usize],
+        rng: &mut impl Rng,
+    ) -> R1CSResult<(ProverIndex<E, D>, Trapdoor<E>)>
     where
         C: ConstraintSynthesizer<E::ScalarField>,
     {
-        type D<F> = GeneralEvaluationDomain<F>;
+        // The toxic waste and every scalar revealing it are zeroed on drop,
+        // whichever way the setup returns.
+        let alpha = SecretScalar::new(alpha);
+        let beta = SecretScalar::new(beta);
+        let gamma = SecretScalar::new(gamma);
+        let delta = SecretScalar::new(delta);
+        let eta = SecretScalar::new(eta);
 
         let setup_time = start_timer!(|| "Groth16::Generator");
         let cs = ConstraintSystem::new_ref();
@@ -87,45 +320,79 @@ impl<E: Pairing, QAP: R1CSToQAP> Groth16<E, QAP> {
 
         let domain_size = cs.num_constraints() + cs.num_instance_variables();
         let domain = D::new(domain_size).ok_or(SynthesisError::PolynomialDegreeTooLarge)?;
-        let t = domain.sample_element_outside_domain(rng);
+        let t = SecretScalar::new(domain.sample_element_outside_domain(rng));
 
         end_timer!(domain_time);
         ///////////////////////////////////////////////////////////////////////////
 
         let reduction_time = start_timer!(|| "R1CS to QAP Instance Map with Evaluation");
         let num_instance_variables = cs.num_instance_variables();
+        let matrices = cs.to_matrices().ok_or(SynthesisError::MissingCS)?;
         let (a, b, c, zt, qap_num_variables, m_raw) =
-            QAP::instance_map_with_evaluation::<E::ScalarField, D<E::ScalarField>>(cs, &t)?;
+            QAP::instance_map_with_evaluation::<E::ScalarField, D>(cs, t.expose())?;
+        let (a, b, c, zt) = (
+            Zeroizing::new(a),
+            Zeroizing::new(b),
+            Zeroizing::new(c),
+            SecretScalar::new(zt),
+        );
         end_timer!(reduction_time);
 
         // Compute query densities
-        let non_zero_a: usize = cfg_into_iter!(0..qap_num_variables)
-            .map(|i| usize::from(!a[i].is_zero()))
-            .sum();
-
-        let non_zero_b: usize = cfg_into_iter!(0..qap_num_variables)
-            .map(|i| usize::from(!b[i].is_zero()))
-            .sum();
+        let a_query_density = a.iter().map(|a_i| !a_i.is_zero()).collect::<QueryDensity>();
+        let b_query_density = b.iter().map(|b_i| !b_i.is_zero()).collect::<QueryDensity>();
+        let non_zero_a = a_query_density.count_ones();
+        let non_zero_b = b_query_density.count_ones();
 
         let scalar_bits = E::ScalarField::MODULUS_BIT_SIZE as usize;
 
-        let gamma_inverse = gamma.inverse().ok_or(SynthesisError::UnexpectedIdentity)?;
-        let delta_inverse = delta.inverse().ok_or(SynthesisError::UnexpectedIdentity)?;
+        let gamma_inverse = SecretScalar::new(
+            gamma
+                .expose()
+                .inverse()
+                .ok_or(SynthesisError::UnexpectedIdentity)?,
+        );
+        let delta_inverse = SecretScalar::new(
+            delta
+                .expose()
+                .inverse()
+                .ok_or(SynthesisError::UnexpectedIdentity)?,
+        );
 
-        let gamma_abc = cfg_iter!(a[..num_instance_variables])
-            .zip(&b[..num_instance_variables])
-            .zip(&c[..num_instance_variables])
-            .map(|((a, b), c)| (beta * a + &(alpha * b) + c) * &gamma_inverse)
-            .collect::<Vec<_>>();
+        let (alpha_s, beta_s) = (alpha.expose(), beta.expose());
+        let gamma_abc = Zeroizing::new(
+            cfg_iter!(a[..num_instance_variables])
+                .zip(&b[..num_instance_variables])
+                .zip(&c[..num_instance_variables])
+                .map(|((a, b), c)| (*beta_s * a + &(*alpha_s * b) + c) * gamma_inverse.expose())
+                .collect::<Vec<_>>(),
+        );
 
-        let l = cfg_iter!(a[num_instance_variables..])
-            .zip(&b[num_instance_variables..])
-            .zip(&c[num_instance_variables..])
-            .map(|((a, b), c)| (beta * a + &(alpha * b) + c) * &delta_inverse)
-            .collect::<Vec<_>>();
+        let mut l = Zeroizing::new(
+            cfg_iter!(a[num_instance_variables..])
+                .zip(&b[num_instance_variables..])
+                .zip(&c[num_instance_variables..])
+                .map(|((a, b), c)| (*beta_s * a + &(*alpha_s * b) + c) * delta_inverse.expose())
+                .collect::<Vec<_>>(),
+        );
 
         drop(c);
 
+        // The committed witnesses move from the L-query to the commitment
+        // bases, from over `delta` to over `gamma`.
+        let mut commitment_bases = Zeroizing::new(Vec::with_capacity(committed_witnesses.len()));
+        for (k, &i) in committed_witnesses.iter().enumerate() {
+            if i >= l.len() || committed_witnesses[..k].contains(&i) {
+                return Err(SynthesisError::AssignmentMissing);
+            }
+            commitment_bases.push(l[i] * delta.expose() * gamma_inverse.expose());
+            l[i] = E::ScalarField::zero();
+        }
+
+        // Only the non-zero scalars get a base in the A and B queries
+        let a = Zeroizing::new(a_query_density.filter(0, &a));
+        let b = Zeroizing::new(b_query_density.filter(0, &b));
+
         // Compute B window table
         let g2_time = start_timer!(|| "Compute G2 table");
         let g2_window = FixedBase::get_mul_window_size(non_zero_b);
@@ -140,19 +407,20 @@ impl<E: Pairing, QAP: R1CSToQAP> Groth16<E, QAP> {
 
         // Compute G window table
         let g1_window_time = start_timer!(|| "Compute G1 window table");
-        let g1_window =
-            FixedBase::get_mul_window_size(non_zero_a + non_zero_b + qap_num_variables + m_raw + 1);
+        let g1_window = FixedBase::get_mul_window_size(
+            non_zero_a + non_zero_b + qap_num_variables + m_raw + commitment_bases.len() + 1,
+        );
         let g1_table = FixedBase::get_window_table::<E::G1>(scalar_bits, g1_window, g1_generator);
         end_timer!(g1_window_time);
 
         // Generate the R1CS proving key
         let proving_key_time = start_timer!(|| "Generate the R1CS proving key");
 
-        let alpha_g1 = g1_generator.mul_bigint(&alpha.into_bigint());
-        let beta_g1 = g1_generator.mul_bigint(&beta.into_bigint());
-        let beta_g2 = g2_generator.mul_bigint(&beta.into_bigint());
-        let delta_g1 = g1_generator.mul_bigint(&delta.into_bigint());
-        let delta_g2 = g2_generator.mul_bigint(&delta.into_bigint());
+        let alpha_g1 = g1_generator.mul_bigint(&alpha.expose().into_bigint());
+        let beta_g1 = g1_generator.mul_bigint(&beta.expose().into_bigint());
+        let beta_g2 = g2_generator.mul_bigint(&beta.expose().into_bigint());
+        let delta_g1 = g1_generator.mul_bigint(&delta.expose().into_bigint());
+        let delta_g2 = g2_generator.mul_bigint(&delta.expose().into_bigint());
 
         // Compute the A-query
         let a_time = start_timer!(|| "Calculate A");
@@ -172,7 +440,12 @@ impl<E: Pairing, QAP: R1CSToQAP> Groth16<E, QAP> {
             scalar_bits,
             g1_window,
             &g1_table,
-            &QAP::h_query_scalars::<_, D<E::ScalarField>>(m_raw - 1, t, zt, delta_inverse)?,
+            &Zeroizing::new(QAP::h_query_scalars::<_, D>(
+                m_raw - 1,
+                *t.expose(),
+                *zt.expose(),
+                *delta_inverse.expose(),
+            )?),
         );
 
         end_timer!(h_time);
@@ -187,8 +460,14 @@ impl<E: Pairing, QAP: R1CSToQAP> Groth16<E, QAP> {
 
         // Generate R1CS verification key
         let verifying_key_time = start_timer!(|| "Generate the R1CS verification key");
-        let gamma_g2 = g2_generator.mul_bigint(&gamma.into_bigint());
+        let gamma_g2 = g2_generator.mul_bigint(&gamma.expose().into_bigint());
         let gamma_abc_g1 = FixedBase::msm::<E::G1>(scalar_bits, g1_window, &g1_table, &gamma_abc);
+        let commitment_bases_g1 =
+            FixedBase::msm::<E::G1>(scalar_bits, g1_window, &g1_table, &commitment_bases);
+        let eta_gamma_inv = SecretScalar::new(*eta.expose() * gamma_inverse.expose());
+        let eta_delta_inv = SecretScalar::new(*eta.expose() * delta_inverse.expose());
+        let eta_gamma_inv_g1 = g1_generator.mul_bigint(&eta_gamma_inv.expose().into_bigint());
+        let eta_delta_inv_g1 = g1_generator.mul_bigint(&eta_delta_inv.expose().into_bigint());
 
         drop(g1_table);
 
@@ -200,6 +479,8 @@ impl<E: Pairing, QAP: R1CSToQAP> Groth16<E, QAP> {
             gamma_g2: gamma_g2.into_affine(),
             delta_g2: delta_g2.into_affine(),
             gamma_abc_g1: E::G1::normalize_batch(&gamma_abc_g1),
+            commitment_bases_g1: E::G1::normalize_batch(&commitment_bases_g1),
+            eta_gamma_inv_g1: eta_gamma_inv_g1.into_affine(),
         };
 
         let batch_normalization_time = start_timer!(|| "Convert proving key elements to affine");
@@ -211,16 +492,37 @@ impl<E: Pairing, QAP: R1CSToQAP> Groth16<E, QAP> {
         end_timer!(batch_normalization_time);
         end_timer!(setup_time);
 
-        Ok(ProvingKey {
+        let pk = ProvingKey {
             vk,
             beta_g1: beta_g1.into_affine(),
             delta_g1: delta_g1.into_affine(),
             delta_g2: delta_g2.into_affine(),
             a_query,
+            a_query_density,
             b_g1_query,
             b_g2_query,
+            b_query_density,
             h_query,
             l_query,
-        })
+            committed_witnesses: committed_witnesses.to_vec(),
+            eta_delta_inv_g1: eta_delta_inv_g1.into_affine(),
+        };
+
+        let index = ProverIndex {
+            pk,
+            matrices,
+            domain,
+        };
+        // The toxic waste only outlives the setup in the trapdoor, which
+        // zeroes it on drop.
+        let trapdoor = Trapdoor {
+            alpha,
+            beta,
+            gamma,
+            delta,
+            t,
+        };
+
+        Ok((index, trapdoor))
     }
 }
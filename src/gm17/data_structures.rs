@@ -0,0 +1,144 @@
+use crate::secret::SecretScalar;
+use ark_ec::pairing::Pairing;
+use ark_serialize::*;
+use ark_std::vec::Vec;
+
+/// The randomizer of a GM17 prover. Its scalar `r` is secret, and only
+/// serialized by [`export`](Self::export).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Randomizer<E: Pairing> {
+    /// Randomizer `r` in scalar.
+    pub r: SecretScalar<E::ScalarField>,
+    /// The part `r (alpha + beta) gamma t(x) + r^2 gamma^2 t(x)^2` of `C`
+    /// that does not depend on the witness, in `G1`.
+    pub p: E::G1Affine,
+}
+
+impl<E: Pairing> Default for Randomizer<E> {
+    fn default() -> Self {
+        Self {
+            r: SecretScalar::default(),
+            p: E::G1Affine::default(),
+        }
+    }
+}
+
+impl<E: Pairing> Randomizer<E> {
+    /// Write the randomizer, secret included, to `writer`.
+    pub fn export<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.r.export(&mut writer)?;
+        self.p.serialize_compressed(&mut writer)
+    }
+
+    /// Read a randomizer written by [`export`](Self::export).
+    pub fn import<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        Ok(Self {
+            r: SecretScalar::import(&mut reader)?,
+            p: E::G1Affine::deserialize_compressed(&mut reader)?,
+        })
+    }
+}
+
+/// A proof in the GM17 SNARK.
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Proof<E: Pairing> {
+    /// The `A` element in `G1`.
+    pub a: E::G1Affine,
+    /// The `B` element in `G2`.
+    pub b: E::G2Affine,
+    /// The `C` element in `G1`.
+    pub c: E::G1Affine,
+}
+
+impl<E: Pairing> Default for Proof<E> {
+    fn default() -> Self {
+        Self {
+            a: E::G1Affine::default(),
+            b: E::G2Affine::default(),
+            c: E::G1Affine::default(),
+        }
+    }
+}
+
+/// A verification key in the GM17 SNARK.
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct VerifyingKey<E: Pairing> {
+    /// The generator `H` of `E::G2`.
+    pub h_g2: E::G2Affine,
+    /// The `alpha * G`, where `G` is the generator of `E::G1`.
+    pub g_alpha_g1: E::G1Affine,
+    /// The `beta * H`.
+    pub h_beta_g2: E::G2Affine,
+    /// The `gamma * G`.
+    pub g_gamma_g1: E::G1Affine,
+    /// The `gamma * H`.
+    pub h_gamma_g2: E::G2Affine,
+    /// The `((alpha + beta) u_i(x) + gamma w_i(x)) * G` of the instance
+    /// variables, where `u_i` and `w_i` are the SAP polynomials.
+    pub query: Vec<E::G1Affine>,
+}
+
+impl<E: Pairing> Default for VerifyingKey<E> {
+    fn default() -> Self {
+        Self {
+            h_g2: E::G2Affine::default(),
+            g_alpha_g1: E::G1Affine::default(),
+            h_beta_g2: E::G2Affine::default(),
+            g_gamma_g1: E::G1Affine::default(),
+            h_gamma_g2: E::G2Affine::default(),
+            query: Vec::new(),
+        }
+    }
+}
+
+/// Preprocessed verification key parameters that enable faster verification
+/// at the expense of larger size in memory.
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PreparedVerifyingKey<E: Pairing> {
+    /// The unprepared verification key.
+    pub vk: VerifyingKey<E>,
+    /// The element `e(alpha * G, beta * H)` in `E::GT`.
+    pub g_alpha_h_beta: E::TargetField,
+    /// The element `- H` in `E::G2`, prepared for use in pairings.
+    pub h_g2_neg_pc: E::G2Prepared,
+    /// The element `- gamma * H` in `E::G2`, prepared for use in pairings.
+    pub h_gamma_g2_neg_pc: E::G2Prepared,
+}
+
+impl<E: Pairing> From<PreparedVerifyingKey<E>> for VerifyingKey<E> {
+    fn from(other: PreparedVerifyingKey<E>) -> Self {
+        other.vk
+    }
+}
+
+impl<E: Pairing> From<VerifyingKey<E>> for PreparedVerifyingKey<E> {
+    fn from(other: VerifyingKey<E>) -> Self {
+        super::prepare_verifying_key(&other)
+    }
+}
+
+/// The prover key for the GM17 SNARK.
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct ProvingKey<E: Pairing> {
+    /// The underlying verification key.
+    pub vk: VerifyingKey<E>,
+    /// The elements `gamma u_i(x) * G` of every SAP variable.
+    pub a_query: Vec<E::G1Affine>,
+    /// The elements `gamma u_i(x) * H` of every SAP variable.
+    pub b_query: Vec<E::G2Affine>,
+    /// The elements `gamma ((alpha + beta) u_i(x) + gamma w_i(x)) * G` of
+    /// the SAP variables past the instance.
+    pub c_query_1: Vec<E::G1Affine>,
+    /// The elements `2 gamma^2 t(x) u_i(x) * G` of every SAP variable.
+    pub c_query_2: Vec<E::G1Affine>,
+    /// The element `gamma t(x) * G`.
+    pub g_gamma_z: E::G1Affine,
+    /// The element `gamma t(x) * H`.
+    pub h_gamma_z: E::G2Affine,
+    /// The element `(alpha + beta) gamma t(x) * G`.
+    pub g_ab_gamma_z: E::G1Affine,
+    /// The element `gamma^2 t(x)^2 * G`.
+    pub g_gamma2_z2: E::G1Affine,
+    /// The elements `gamma^2 t(x) x^i * G` for the coefficients of `h`.
+    pub g_gamma2_z_t: Vec<E::G1Affine>,
+}
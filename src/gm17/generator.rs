@@ -0,0 +1,179 @@
+use super::{r1cs_to_sap::R1CSToSAP, ProvingKey, VerifyingKey, GM17};
+use crate::Vec;
+use ark_ec::{pairing::Pairing, scalar_mul::fixed_base::FixedBase, CurveGroup};
+use ark_ff::{Field, PrimeField, UniformRand};
+use ark_poly::{EvaluationDomain, GeneralEvaluationDomain};
+use ark_relations::r1cs::{
+    ConstraintSynthesizer, ConstraintSystem, OptimizationGoal, Result as R1CSResult,
+    SynthesisError, SynthesisMode,
+};
+use ark_std::{cfg_iter, rand::Rng};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+impl<E: Pairing> GM17<E> {
+    /// Generates a random common reference string for a circuit.
+    #[inline]
+    pub fn generate_random_parameters<C>(
+        circuit: C,
+        rng: &mut impl Rng,
+    ) -> R1CSResult<ProvingKey<E>>
+    where
+        C: ConstraintSynthesizer<E::ScalarField>,
+    {
+        let alpha = E::ScalarField::rand(rng);
+        let beta = E::ScalarField::rand(rng);
+        let gamma = E::ScalarField::rand(rng);
+
+        let g1_generator = E::G1::rand(rng);
+        let g2_generator = E::G2::rand(rng);
+
+        Self::generate_parameters(circuit, alpha, beta, gamma, g1_generator, g2_generator, rng)
+    }
+
+    /// Create parameters for a circuit, given some toxic waste and group
+    /// generators.
+    pub fn generate_parameters<C>(
+        circuit: C,
+        alpha: E::ScalarField,
+        beta: E::ScalarField,
+        gamma: E::ScalarField,
+        g1_generator: E::G1,
+        g2_generator: E::G2,
+        rng: &mut impl Rng,
+    ) -> R1CSResult<ProvingKey<E>>
+    where
+        C: ConstraintSynthesizer<E::ScalarField>,
+    {
+        type D<F> = GeneralEvaluationDomain<F>;
+
+        let setup_time = start_timer!(|| "GM17::Generator");
+        let cs = ConstraintSystem::new_ref();
+        cs.set_optimization_goal(OptimizationGoal::Constraints);
+        cs.set_mode(SynthesisMode::Setup);
+
+        // Synthesize the circuit.
+        let synthesis_time = start_timer!(|| "Constraint synthesis");
+        circuit.generate_constraints(cs.clone())?;
+        end_timer!(synthesis_time);
+
+        let lc_time = start_timer!(|| "Inlining LCs");
+        cs.finalize();
+        end_timer!(lc_time);
+
+        // Following is the mapping of symbols from the GM17 paper to this implementation
+        // l -> num_instance_variables
+        // x -> t
+        // t(x) -> zt
+        // u_i(x) -> u
+        // w_i(x) -> w
+
+        ///////////////////////////////////////////////////////////////////////////
+        let domain_time = start_timer!(|| "Constructing evaluation domain");
+
+        let domain_size = 2 * cs.num_constraints() + cs.num_instance_variables();
+        let domain = D::<E::ScalarField>::new(domain_size)
+            .ok_or(SynthesisError::PolynomialDegreeTooLarge)?;
+        let t = domain.sample_element_outside_domain(rng);
+
+        end_timer!(domain_time);
+        ///////////////////////////////////////////////////////////////////////////
+
+        let reduction_time = start_timer!(|| "R1CS to SAP Instance Map with Evaluation");
+        let num_instance_variables = cs.num_instance_variables();
+        let (u, w, zt, domain_size) =
+            R1CSToSAP::instance_map_with_evaluation::<E::ScalarField, D<E::ScalarField>>(cs, &t)?;
+        end_timer!(reduction_time);
+
+        let scalar_bits = E::ScalarField::MODULUS_BIT_SIZE as usize;
+
+        let gamma2 = gamma.square();
+        let alpha_beta = alpha + beta;
+        let gamma_z = gamma * zt;
+
+        let query = cfg_iter!(u[..num_instance_variables])
+            .zip(&w[..num_instance_variables])
+            .map(|(u, w)| alpha_beta * u + gamma * w)
+            .collect::<Vec<_>>();
+        let c_1 = cfg_iter!(u[num_instance_variables..])
+            .zip(&w[num_instance_variables..])
+            .map(|(u, w)| gamma * (alpha_beta * u + gamma * w))
+            .collect::<Vec<_>>();
+        let a = cfg_iter!(u).map(|u| gamma * u).collect::<Vec<_>>();
+        let c_2 = cfg_iter!(u)
+            .map(|u| (gamma_z + gamma_z) * gamma * u)
+            .collect::<Vec<_>>();
+        let mut z_t = Vec::with_capacity(domain_size - 1);
+        let mut power = gamma2 * zt;
+        for _ in 0..domain_size - 1 {
+            z_t.push(power);
+            power *= t;
+        }
+
+        // Compute the G2 window table and the B-query
+        let g2_time = start_timer!(|| "Compute G2 table and B-query");
+        let g2_window = FixedBase::get_mul_window_size(a.len() + 3);
+        let g2_table = FixedBase::get_window_table::<E::G2>(scalar_bits, g2_window, g2_generator);
+        let b_query = FixedBase::msm::<E::G2>(scalar_bits, g2_window, &g2_table, &a);
+        let h_elements = FixedBase::msm::<E::G2>(
+            scalar_bits,
+            g2_window,
+            &g2_table,
+            &[E::ScalarField::ONE, beta, gamma, gamma_z],
+        );
+        drop(g2_table);
+        end_timer!(g2_time);
+
+        // Compute the G1 window table and the queries in G1
+        let g1_time = start_timer!(|| "Compute G1 table and queries");
+        let g1_window = FixedBase::get_mul_window_size(
+            query.len() + c_1.len() + a.len() + c_2.len() + z_t.len() + 5,
+        );
+        let g1_table = FixedBase::get_window_table::<E::G1>(scalar_bits, g1_window, g1_generator);
+        let g1_msm = |scalars: &[E::ScalarField]| {
+            let elements = FixedBase::msm::<E::G1>(scalar_bits, g1_window, &g1_table, scalars);
+            E::G1::normalize_batch(&elements)
+        };
+        let query = g1_msm(&query);
+        let c_query_1 = g1_msm(&c_1);
+        let a_query = g1_msm(&a);
+        let c_query_2 = g1_msm(&c_2);
+        let g_gamma2_z_t = g1_msm(&z_t);
+        let g_elements = g1_msm(&[
+            alpha,
+            gamma,
+            gamma_z,
+            alpha_beta * gamma_z,
+            gamma_z.square(),
+        ]);
+        end_timer!(g1_time);
+
+        let b_query = E::G2::normalize_batch(&b_query);
+        let h_elements = E::G2::normalize_batch(&h_elements);
+
+        let vk = VerifyingKey::<E> {
+            h_g2: h_elements[0],
+            g_alpha_g1: g_elements[0],
+            h_beta_g2: h_elements[1],
+            g_gamma_g1: g_elements[1],
+            h_gamma_g2: h_elements[2],
+            query,
+        };
+
+        end_timer!(setup_time);
+
+        Ok(ProvingKey {
+            vk,
+            a_query,
+            b_query,
+            c_query_1,
+            c_query_2,
+            g_gamma_z: g_elements[2],
+            h_gamma_z: h_elements[3],
+            g_ab_gamma_z: g_elements[3],
+            g_gamma2_z2: g_elements[4],
+            g_gamma2_z_t,
+        })
+    }
+}
@@ -0,0 +1,90 @@
+/// Reduce an R1CS instance to a *Square Arithmetic Program* instance.
+pub mod r1cs_to_sap;
+
+/// Data structures used by the prover, verifier, and generator.
+pub mod data_structures;
+
+/// Generate public parameters for the GM17 zkSNARK construction.
+pub mod generator;
+
+/// Create proofs for the GM17 zkSNARK construction.
+pub mod prover;
+
+/// Verify proofs for the GM17 zkSNARK construction.
+pub mod verifier;
+
+pub use self::data_structures::*;
+pub use self::verifier::*;
+
+use crate::data_structures::{CircuitSpecificSetupPolymorphicSNARK, PolymorphicSNARK};
+use ark_ec::pairing::Pairing;
+use ark_relations::r1cs::{ConstraintSynthesizer, SynthesisError};
+use ark_std::rand::RngCore;
+use ark_std::{marker::PhantomData, vec::Vec};
+
+/// The SNARK of [[GM17]](https://eprint.iacr.org/2017/540.pdf), over the
+/// R1CS-to-SAP reduction of [`r1cs_to_sap::R1CSToSAP`].
+///
+/// As for [`Groth16`](crate::Groth16), the randomness `r` of the prover is
+/// fixed by a [`Randomizer`], which also carries the part `P` of `C` that does
+/// not depend on the witness, so that proofs are deterministic in the witness
+/// for a given randomizer. Verification takes more pairings than for Groth16,
+/// but proofs cannot be rerandomized or otherwise mauled into new proofs.
+pub struct GM17<E: Pairing> {
+    _p: PhantomData<E>,
+}
+
+impl<E: Pairing> PolymorphicSNARK<E::ScalarField> for GM17<E> {
+    type ProvingKey = ProvingKey<E>;
+    type VerifyingKey = VerifyingKey<E>;
+    type Randomizer = Randomizer<E>;
+    type Proof = Proof<E>;
+    type ProcessedVerifyingKey = PreparedVerifyingKey<E>;
+    type Error = SynthesisError;
+
+    fn circuit_specific_setup<C: ConstraintSynthesizer<E::ScalarField>, R: RngCore>(
+        circuit: C,
+        rng: &mut R,
+    ) -> Result<(Self::ProvingKey, Self::VerifyingKey), Self::Error> {
+        let pk = Self::generate_random_parameters(circuit, rng)?;
+        let vk = pk.vk.clone();
+
+        Ok((pk, vk))
+    }
+
+    fn rndgen<R: RngCore>(
+        pk: &Self::ProvingKey,
+        rng: &mut R,
+    ) -> Result<Self::Randomizer, Self::Error> {
+        Self::create_randomizer(pk, rng)
+    }
+
+    fn prove<C: ConstraintSynthesizer<E::ScalarField>>(
+        pk: &Self::ProvingKey,
+        circuit: C,
+        rnd: &Self::Randomizer,
+    ) -> Result<Self::Proof, Self::Error> {
+        Self::create_proof(circuit, pk, rnd)
+    }
+
+    fn process_vk(
+        circuit_vk: &Self::VerifyingKey,
+    ) -> Result<Self::ProcessedVerifyingKey, Self::Error> {
+        Ok(prepare_verifying_key(circuit_vk))
+    }
+
+    fn verify_with_processed_vk(
+        circuit_pvk: &Self::ProcessedVerifyingKey,
+        x: &[E::ScalarField],
+        proof: &Self::Proof,
+        rnd: &Self::Randomizer,
+    ) -> Result<bool, Self::Error> {
+        Self::verify_proof(circuit_pvk, proof, rnd, x)
+    }
+
+    fn compare_all_proofs(proofs: &Vec<Self::Proof>) -> Result<bool, Self::Error> {
+        Self::compare_proofs(proofs)
+    }
+}
+
+impl<E: Pairing> CircuitSpecificSetupPolymorphicSNARK<E::ScalarField> for GM17<E> {}
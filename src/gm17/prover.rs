@@ -0,0 +1,88 @@
+use super::{r1cs_to_sap::R1CSToSAP, Proof, ProvingKey, Randomizer, GM17};
+use ark_ec::{pairing::Pairing, CurveGroup, VariableBaseMSM};
+use ark_ff::UniformRand;
+use ark_poly::GeneralEvaluationDomain;
+use ark_relations::r1cs::{
+    ConstraintSynthesizer, ConstraintSystem, OptimizationGoal, Result as R1CSResult,
+    SynthesisError, SynthesisMode,
+};
+use ark_std::rand::Rng;
+
+impl<E: Pairing> GM17<E> {
+    /// Create a randomizer, whose proofs are deterministic in the witness.
+    pub fn create_randomizer(pk: &ProvingKey<E>, rng: &mut impl Rng) -> R1CSResult<Randomizer<E>> {
+        let r = E::ScalarField::rand(rng);
+        let p = pk.g_ab_gamma_z * r + pk.g_gamma2_z2 * (r * r);
+
+        Ok(Randomizer {
+            r: r.into(),
+            p: p.into_affine(),
+        })
+    }
+
+    /// Create a GM17 proof under the randomizer `rnd`, leaving out of `C` the
+    /// part `rnd.p` that does not depend on the witness.
+    pub fn create_proof<C>(
+        circuit: C,
+        pk: &ProvingKey<E>,
+        rnd: &Randomizer<E>,
+    ) -> R1CSResult<Proof<E>>
+    where
+        C: ConstraintSynthesizer<E::ScalarField>,
+    {
+        let prover_time = start_timer!(|| "GM17::Prover");
+        let cs = ConstraintSystem::new_ref();
+        cs.set_optimization_goal(OptimizationGoal::Constraints);
+        cs.set_mode(SynthesisMode::Prove {
+            construct_matrices: true,
+        });
+
+        // Synthesize the circuit.
+        let synthesis_time = start_timer!(|| "Constraint synthesis");
+        circuit.generate_constraints(cs.clone())?;
+        debug_assert!(cs.is_satisfied().unwrap());
+        end_timer!(synthesis_time);
+
+        let lc_time = start_timer!(|| "Inlining LCs");
+        cs.finalize();
+        end_timer!(lc_time);
+
+        let witness_map_time = start_timer!(|| "R1CS to SAP witness map");
+        let num_instance_variables = cs.num_instance_variables();
+        let (assignment, h) =
+            R1CSToSAP::witness_map::<E::ScalarField, GeneralEvaluationDomain<E::ScalarField>>(cs)?;
+        end_timer!(witness_map_time);
+
+        if assignment.len() != pk.a_query.len()
+            || num_instance_variables != pk.vk.query.len()
+            || h.len() != pk.g_gamma2_z_t.len()
+        {
+            return Err(SynthesisError::MalformedVerifyingKey);
+        }
+
+        // Compute A
+        let a_acc_time = start_timer!(|| "Compute A");
+        let a = E::G1::msm_unchecked(&pk.a_query, &assignment) + pk.g_gamma_z * rnd.r.expose();
+        end_timer!(a_acc_time);
+
+        // Compute B
+        let b_acc_time = start_timer!(|| "Compute B");
+        let b = E::G2::msm_unchecked(&pk.b_query, &assignment) + pk.h_gamma_z * rnd.r.expose();
+        end_timer!(b_acc_time);
+
+        // Compute C
+        let c_acc_time = start_timer!(|| "Compute C");
+        let mut c = E::G1::msm_unchecked(&pk.c_query_1, &assignment[num_instance_variables..]);
+        c += E::G1::msm_unchecked(&pk.g_gamma2_z_t, &h);
+        c += E::G1::msm_unchecked(&pk.c_query_2, &assignment) * rnd.r.expose();
+        end_timer!(c_acc_time);
+
+        end_timer!(prover_time);
+
+        Ok(Proof {
+            a: a.into_affine(),
+            b: b.into_affine(),
+            c: c.into_affine(),
+        })
+    }
+}
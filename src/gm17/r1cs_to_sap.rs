@@ -0,0 +1,153 @@
+use ark_ff::PrimeField;
+use ark_poly::EvaluationDomain;
+use ark_relations::r1cs::{ConstraintSystemRef, Result as R1CSResult, SynthesisError};
+use ark_std::{cfg_iter_mut, vec, vec::Vec};
+use core::ops::Deref;
+
+use crate::r1cs_to_qap::evaluate_constraint;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// Computes instance and witness reductions from R1CS to
+/// Square Arithmetic Programs (SAPs).
+///
+/// Every constraint `a * b = c` becomes the two square constraints
+/// `(a + b)^2 = 4c + e` and `(a - b)^2 = e` over a new variable `e`, and every
+/// public input `x_i` gets the constraint `x_i^2 = s_i` over a new variable
+/// `s_i` (`1^2 = 1` for the constant one), which makes the polynomials of the
+/// instance variables linearly independent.
+///
+/// The SAP variables are the R1CS variables, followed by the `e` of every
+/// constraint and the `s_i` of every public input.
+pub struct R1CSToSAP;
+
+impl R1CSToSAP {
+    /// Computes the SAP instance of `cs` evaluated at `t`: the `u` and `w`
+    /// polynomials of every SAP variable, the vanishing polynomial of the
+    /// domain and the size of the domain.
+    #[allow(clippy::type_complexity)]
+    pub fn instance_map_with_evaluation<F: PrimeField, D: EvaluationDomain<F>>(
+        cs: ConstraintSystemRef<F>,
+        t: &F,
+    ) -> R1CSResult<(Vec<F>, Vec<F>, F, usize)> {
+        let matrices = cs.to_matrices().unwrap();
+        let num_instance_variables = cs.num_instance_variables();
+        let num_constraints = cs.num_constraints();
+        let domain = D::new(2 * num_constraints + num_instance_variables)
+            .ok_or(SynthesisError::PolynomialDegreeTooLarge)?;
+
+        let zt = domain.evaluate_vanishing_polynomial(*t);
+
+        // Evaluate all Lagrange polynomials
+        let coefficients_time = start_timer!(|| "Evaluate Lagrange coefficients");
+        let l = domain.evaluate_all_lagrange_coefficients(*t);
+        end_timer!(coefficients_time);
+
+        let r1cs_num_variables = num_instance_variables + cs.num_witness_variables();
+        let sap_num_variables = r1cs_num_variables + num_constraints + num_instance_variables - 1;
+        let mut u = vec![F::zero(); sap_num_variables];
+        let mut w = vec![F::zero(); sap_num_variables];
+
+        let four = F::from(4u64);
+        for i in 0..num_constraints {
+            let (even, odd) = (l[2 * i], l[2 * i + 1]);
+            for &(ref coeff, index) in &matrices.a[i] {
+                u[index] += (even + odd) * coeff;
+            }
+            for &(ref coeff, index) in &matrices.b[i] {
+                u[index] += (even - odd) * coeff;
+            }
+            for &(ref coeff, index) in &matrices.c[i] {
+                w[index] += even * four * coeff;
+            }
+            w[r1cs_num_variables + i] = even + odd;
+        }
+
+        let offset = 2 * num_constraints;
+        u[0] += l[offset];
+        w[0] += l[offset];
+        for i in 1..num_instance_variables {
+            u[i] += l[offset + i];
+            w[r1cs_num_variables + num_constraints + i - 1] = l[offset + i];
+        }
+
+        Ok((u, w, zt, domain.size()))
+    }
+
+    /// Computes the SAP assignment extending the R1CS assignment of `prover`,
+    /// and the coefficients of `h = (U^2 - W) / t` for the polynomials `U` and
+    /// `W` it defines.
+    pub fn witness_map<F: PrimeField, D: EvaluationDomain<F>>(
+        prover: ConstraintSystemRef<F>,
+    ) -> R1CSResult<(Vec<F>, Vec<F>)> {
+        let matrices = prover.to_matrices().unwrap();
+        let num_instance_variables = prover.num_instance_variables();
+        let num_constraints = prover.num_constraints();
+        let domain = D::new(2 * num_constraints + num_instance_variables)
+            .ok_or(SynthesisError::PolynomialDegreeTooLarge)?;
+        let domain_size = domain.size();
+
+        let cs = prover.borrow().unwrap();
+        let prover = cs.deref();
+        let mut assignment = [
+            prover.instance_assignment.as_slice(),
+            prover.witness_assignment.as_slice(),
+        ]
+        .concat();
+
+        let four = F::from(4u64);
+        let mut u = vec![F::zero(); domain_size];
+        let mut w = vec![F::zero(); domain_size];
+        let mut extra = Vec::with_capacity(num_constraints + num_instance_variables - 1);
+        for i in 0..num_constraints {
+            let a: F = evaluate_constraint(&matrices.a[i], &assignment);
+            let b: F = evaluate_constraint(&matrices.b[i], &assignment);
+            let c: F = evaluate_constraint(&matrices.c[i], &assignment);
+            let e = (a - b).square();
+            u[2 * i] = a + b;
+            u[2 * i + 1] = a - b;
+            w[2 * i] = four * c + e;
+            w[2 * i + 1] = e;
+            extra.push(e);
+        }
+
+        let offset = 2 * num_constraints;
+        u[offset] = F::one();
+        w[offset] = F::one();
+        let squares = assignment[1..num_instance_variables]
+            .iter()
+            .map(|x| x.square())
+            .collect::<Vec<_>>();
+        for (i, (x, square)) in assignment[1..num_instance_variables]
+            .iter()
+            .zip(&squares)
+            .enumerate()
+        {
+            u[offset + 1 + i] = *x;
+            w[offset + 1 + i] = *square;
+        }
+        extra.extend(squares);
+        assignment.extend(extra);
+
+        domain.ifft_in_place(&mut u);
+        domain.ifft_in_place(&mut w);
+
+        let coset_domain = domain.get_coset(F::GENERATOR).unwrap();
+        coset_domain.fft_in_place(&mut u);
+        coset_domain.fft_in_place(&mut w);
+
+        let vanishing_polynomial_over_coset = domain
+            .evaluate_vanishing_polynomial(F::GENERATOR)
+            .inverse()
+            .unwrap();
+        cfg_iter_mut!(u).zip(w).for_each(|(u_i, w_i)| {
+            *u_i = (u_i.square() - w_i) * vanishing_polynomial_over_coset;
+        });
+
+        coset_domain.ifft_in_place(&mut u);
+        u.truncate(domain_size - 1);
+
+        Ok((assignment, u))
+    }
+}
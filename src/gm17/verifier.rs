@@ -0,0 +1,93 @@
+use super::{PreparedVerifyingKey, Proof, Randomizer, VerifyingKey, GM17};
+use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup, VariableBaseMSM};
+use ark_ff::Zero;
+use ark_relations::r1cs::{Result as R1CSResult, SynthesisError};
+
+use core::ops::Neg;
+use std::collections::HashSet;
+
+/// Prepare the verifying key `vk` for use in proof verification.
+pub fn prepare_verifying_key<E: Pairing>(vk: &VerifyingKey<E>) -> PreparedVerifyingKey<E> {
+    PreparedVerifyingKey {
+        vk: vk.clone(),
+        g_alpha_h_beta: E::pairing(vk.g_alpha_g1, vk.h_beta_g2).0,
+        h_g2_neg_pc: vk.h_g2.into_group().neg().into_affine().into(),
+        h_gamma_g2_neg_pc: vk.h_gamma_g2.into_group().neg().into_affine().into(),
+    }
+}
+
+impl<E: Pairing> GM17<E> {
+    /// Prepare proof inputs for use with [`verify_proof_with_prepared_inputs`], wrt the prepared
+    /// verification key `pvk` and instance public inputs.
+    pub fn prepare_inputs(
+        pvk: &PreparedVerifyingKey<E>,
+        public_inputs: &[E::ScalarField],
+    ) -> R1CSResult<E::G1> {
+        if (public_inputs.len() + 1) != pvk.vk.query.len() {
+            return Err(SynthesisError::MalformedVerifyingKey);
+        }
+
+        Ok(pvk.vk.query[0] + E::G1::msm_unchecked(&pvk.vk.query[1..], public_inputs))
+    }
+
+    /// Verify a GM17 proof `proof` against the prepared verification key `pvk` and prepared public
+    /// inputs, under the randomizer `rnd`.
+    pub fn verify_proof_with_prepared_inputs(
+        pvk: &PreparedVerifyingKey<E>,
+        proof: &Proof<E>,
+        rnd: &Randomizer<E>,
+        prepared_inputs: &E::G1,
+    ) -> R1CSResult<bool> {
+        // e(A + alpha G, B + beta H) = e(alpha G, beta H) e(psi, gamma H) e(C + P, H)
+        let test1 = E::multi_miller_loop(
+            [
+                <E::G1Affine as Into<E::G1Prepared>>::into(
+                    (proof.a + pvk.vk.g_alpha_g1).into_affine(),
+                ),
+                prepared_inputs.into_affine().into(),
+                (proof.c + rnd.p).into_affine().into(),
+            ],
+            [
+                (proof.b + pvk.vk.h_beta_g2).into_affine().into(),
+                pvk.h_gamma_g2_neg_pc.clone(),
+                pvk.h_g2_neg_pc.clone(),
+            ],
+        );
+        let test1 = E::final_exponentiation(test1).ok_or(SynthesisError::UnexpectedIdentity)?;
+
+        // e(A, gamma H) = e(gamma G, B)
+        let test2 = E::multi_pairing(
+            [proof.a, pvk.vk.g_gamma_g1.into_group().neg().into_affine()],
+            [pvk.vk.h_gamma_g2, proof.b],
+        );
+
+        Ok(test1.0 == pvk.g_alpha_h_beta && test2.is_zero())
+    }
+
+    /// Verify a GM17 proof `proof` against the prepared verification key `pvk`,
+    /// with respect to the instance `public_inputs`.
+    pub fn verify_proof(
+        pvk: &PreparedVerifyingKey<E>,
+        proof: &Proof<E>,
+        rnd: &Randomizer<E>,
+        public_inputs: &[E::ScalarField],
+    ) -> R1CSResult<bool> {
+        let prepared_inputs = Self::prepare_inputs(pvk, public_inputs)?;
+        Self::verify_proof_with_prepared_inputs(pvk, proof, rnd, &prepared_inputs)
+    }
+
+    /// Compare the elements of `proofs`, returning `false` if two of them
+    /// share an `A`, `B` or `C` element.
+    pub fn compare_proofs(proofs: &[Proof<E>]) -> R1CSResult<bool> {
+        let mut uniq = HashSet::new();
+        let result_a = proofs.iter().all(move |proof| uniq.insert(proof.a));
+
+        let mut uniq = HashSet::new();
+        let result_b = proofs.iter().all(move |proof| uniq.insert(proof.b));
+
+        let mut uniq = HashSet::new();
+        let result_c = proofs.iter().all(move |proof| uniq.insert(proof.c));
+
+        Ok(result_a && result_b && result_c)
+    }
+}
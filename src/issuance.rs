@@ -0,0 +1,226 @@
+//! An issuer signs the public part of a randomizer, so that verifiers who
+//! receive it through a side channel can check that it comes from the
+//! organizer, for the circuit they verify, and within its validity window.
+//!
+//! Signatures are BLS signatures over the pairing of the proof system: the
+//! issuer key is `x * H` in `E::G2`, and the signature of a message `m` is
+//! `x * H(m)` in `E::G1`, checked by `e(sig, H) == e(H(m), x * H)`. Messages
+//! are hashed to `E::G1` by try-and-increment followed by cofactor clearing,
+//! which works for any pairing but is not constant-time. The signed messages
+//! are public, so this leaks nothing.
+
+use crate::{r1cs_to_qap::R1CSToQAP, secret::SecretScalar, Groth16, Randomizer, VerifyingKey};
+use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup};
+use ark_ff::{UniformRand, Zero};
+use ark_poly::EvaluationDomain;
+use ark_serialize::*;
+use ark_std::{
+    rand::{CryptoRng, RngCore},
+    vec::Vec,
+};
+use sha2::{Digest, Sha256};
+
+/// The domain separation tag of the messages signed by an issuer.
+const ISSUANCE_DOMAIN: &[u8] = b"polymorphic-groth16-issuance";
+
+/// An error of issuing a randomizer or verifying an issued one.
+#[derive(Clone, Debug, PartialEq)]
+pub enum IssuanceError {
+    /// The validity window ends before it starts.
+    InvalidValidityWindow,
+    /// The randomizer is checked before its validity window.
+    NotYetValid,
+    /// The randomizer is checked after its validity window.
+    Expired,
+    /// The randomizer was issued for another verifying key.
+    VerifyingKeyMismatch,
+    /// The randomizer is not the one that was issued.
+    RandomizerMismatch,
+    /// The signature is not the issuer's.
+    InvalidSignature,
+}
+
+impl core::fmt::Display for IssuanceError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::InvalidValidityWindow => write!(f, "the validity window is empty"),
+            Self::NotYetValid => write!(f, "the randomizer is not valid yet"),
+            Self::Expired => write!(f, "the randomizer has expired"),
+            Self::VerifyingKeyMismatch => {
+                write!(f, "the randomizer was issued for another verifying key")
+            },
+            Self::RandomizerMismatch => write!(f, "the randomizer is not the issued one"),
+            Self::InvalidSignature => write!(f, "the signature of the issuer is invalid"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for IssuanceError {}
+
+/// The signing key of an issuer, a secret scalar `x`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IssuerSecretKey<E: Pairing> {
+    x: SecretScalar<E::ScalarField>,
+}
+
+impl<E: Pairing> IssuerSecretKey<E> {
+    /// Sample a signing key.
+    pub fn generate<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        let mut x = E::ScalarField::rand(rng);
+        while x.is_zero() {
+            x = E::ScalarField::rand(rng);
+        }
+        Self { x: x.into() }
+    }
+
+    /// The public key `x * H` of the issuer.
+    pub fn public_key(&self) -> IssuerPublicKey<E> {
+        IssuerPublicKey {
+            x_g2: (E::G2Affine::generator() * self.x.expose()).into_affine(),
+        }
+    }
+
+    /// Write the signing key to `writer`.
+    pub fn export<W: Write>(&self, writer: W) -> Result<(), SerializationError> {
+        self.x.export(writer)
+    }
+
+    /// Read a signing key written by [`export`](Self::export).
+    pub fn import<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        Ok(Self {
+            x: SecretScalar::import(reader)?,
+        })
+    }
+}
+
+/// The public key of an issuer.
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct IssuerPublicKey<E: Pairing> {
+    /// The element `x * H` in `E::G2`.
+    pub x_g2: E::G2Affine,
+}
+
+/// The public part of a randomizer, signed by its issuer together with the
+/// verifying key it is meant for and a validity window.
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct IssuedRandomizer<E: Pairing> {
+    /// The `P` element of the randomizer.
+    pub p: E::G1Affine,
+    /// The `Q` element of the randomizer.
+    pub q: E::G1Affine,
+    /// The [`VerifyingKey::digest`] of the circuit.
+    pub vk_digest: [u8; 32],
+    /// The first instant, e.g. in UNIX seconds, at which the randomizer is
+    /// valid.
+    pub not_before: u64,
+    /// The last instant at which the randomizer is valid.
+    pub not_after: u64,
+    /// The signature of the issuer, in `E::G1`.
+    pub signature: E::G1Affine,
+}
+
+impl<E: Pairing> IssuedRandomizer<E> {
+    /// The message signed by the issuer.
+    fn message(&self) -> Vec<u8> {
+        let mut message = Vec::new();
+        self.p
+            .serialize_compressed(&mut message)
+            .and_then(|_| self.q.serialize_compressed(&mut message))
+            .expect("serializing into a vector cannot fail");
+        message.extend_from_slice(&self.vk_digest);
+        message.extend_from_slice(&self.not_before.to_le_bytes());
+        message.extend_from_slice(&self.not_after.to_le_bytes());
+        message
+    }
+}
+
+/// Hash `message` to a non-identity element of the prime order subgroup of
+/// `E::G1`, by hashing it with a counter to candidate `x` coordinates until
+/// one is on the curve.
+fn hash_to_g1<E: Pairing>(message: &[u8]) -> E::G1Affine {
+    let len = E::G1Affine::generator().compressed_size();
+    for counter in 0u64.. {
+        let mut bytes = Vec::with_capacity(len + 32);
+        let mut block = 0u64;
+        while bytes.len() < len {
+            let digest = Sha256::new()
+                .chain_update(ISSUANCE_DOMAIN)
+                .chain_update(counter.to_le_bytes())
+                .chain_update(block.to_le_bytes())
+                .chain_update(message)
+                .finalize();
+            bytes.extend_from_slice(&digest);
+            block += 1;
+        }
+        bytes.truncate(len);
+        if let Some(point) = E::G1Affine::from_random_bytes(&bytes) {
+            let point = point.clear_cofactor();
+            if !point.is_zero() {
+                return point;
+            }
+        }
+    }
+    unreachable!()
+}
+
+impl<E: Pairing, QAP: R1CSToQAP, D: EvaluationDomain<E::ScalarField>> Groth16<E, QAP, D> {
+    /// Sign the public part of `rnd` for the verifying key `vk`, valid from
+    /// `not_before` to `not_after` inclusive.
+    pub fn issue_randomizer(
+        issuer_key: &IssuerSecretKey<E>,
+        vk: &VerifyingKey<E>,
+        rnd: &Randomizer<E>,
+        not_before: u64,
+        not_after: u64,
+    ) -> Result<IssuedRandomizer<E>, IssuanceError> {
+        if not_after < not_before {
+            return Err(IssuanceError::InvalidValidityWindow);
+        }
+        let mut issued = IssuedRandomizer {
+            p: rnd.p,
+            q: rnd.q,
+            vk_digest: vk.digest(),
+            not_before,
+            not_after,
+            signature: E::G1Affine::zero(),
+        };
+        issued.signature =
+            (hash_to_g1::<E>(&issued.message()) * issuer_key.x.expose()).into_affine();
+        Ok(issued)
+    }
+
+    /// Check that `rnd` was issued by the owner of `issuer_key` for `vk`, and
+    /// that `now` lies in its validity window. Verifiers should call this
+    /// before accepting proofs under `rnd`.
+    pub fn verify_issued_randomizer(
+        issuer_key: &IssuerPublicKey<E>,
+        vk: &VerifyingKey<E>,
+        rnd: &Randomizer<E>,
+        issued: &IssuedRandomizer<E>,
+        now: u64,
+    ) -> Result<(), IssuanceError> {
+        if rnd.p != issued.p || rnd.q != issued.q {
+            return Err(IssuanceError::RandomizerMismatch);
+        }
+        if vk.digest() != issued.vk_digest {
+            return Err(IssuanceError::VerifyingKeyMismatch);
+        }
+        if now < issued.not_before {
+            return Err(IssuanceError::NotYetValid);
+        }
+        if now > issued.not_after {
+            return Err(IssuanceError::Expired);
+        }
+
+        let hash = hash_to_g1::<E>(&issued.message());
+        let check = E::multi_pairing(
+            [issued.signature, (-hash.into_group()).into_affine()],
+            [E::G2Affine::generator(), issuer_key.x_g2],
+        );
+        if issued.signature.is_zero() || issuer_key.x_g2.is_zero() || !check.is_zero() {
+            return Err(IssuanceError::InvalidSignature);
+        }
+        Ok(())
+    }
+}
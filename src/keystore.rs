@@ -0,0 +1,593 @@
+use crate::{Randomizer, Trapdoor};
+use ark_ec::pairing::Pairing;
+use ark_serialize::{Read, SerializationError, Write};
+use ark_std::{
+    rand::RngCore,
+    string::{String, ToString},
+    vec::Vec,
+};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use sha2::{Digest, Sha256};
+use zeroize::Zeroize;
+
+/// The magic bytes opening a keystore file.
+pub const KEYSTORE_MAGIC: [u8; 4] = *b"PZKS";
+
+/// The version of the keystore format written by this crate.
+pub const KEYSTORE_VERSION: u8 = 2;
+
+/// The most memory, in bytes, that [`Keystore::load`] lets the key derivation
+/// of a file take: 1 GiB.
+pub const DEFAULT_MAX_KDF_MEMORY: u64 = 1 << 30;
+
+/// The byte length of the salt of the key derivation.
+const SALT_LEN: usize = 32;
+
+/// The byte length of the nonce of the encryption.
+const NONCE_LEN: usize = 12;
+
+/// The byte length of the key check.
+const KEY_CHECK_LEN: usize = 32;
+
+/// The byte length of the authentication tag of each ciphertext.
+const TAG_LEN: usize = 16;
+
+/// The byte length of the header, up to and including the number of entries.
+const HEADER_LEN: usize = 4 + 1 + 1 + 4 + 4 + SALT_LEN + NONCE_LEN + KEY_CHECK_LEN + 4;
+
+/// The domain separation tag of the key check.
+const KEY_CHECK_DOMAIN: &[u8] = b"polymorphic-groth16-keystore-key-check";
+
+/// An error reading or writing a [`Keystore`].
+#[derive(Debug)]
+pub enum KeystoreError {
+    /// The file is truncated, malformed, or could not be read or written.
+    Serialization(SerializationError),
+    /// The file is not a keystore.
+    NotAKeystore,
+    /// The file is in a format version this crate does not read.
+    UnsupportedVersion(u8),
+    /// The key derivation parameters are out of range.
+    InvalidParameters,
+    /// The password does not open the keystore.
+    WrongPassword,
+    /// The file was modified after it was written.
+    IntegrityCheckFailed,
+    /// A label is already in use, or too long.
+    InvalidLabel(String),
+}
+
+impl From<SerializationError> for KeystoreError {
+    fn from(e: SerializationError) -> Self {
+        Self::Serialization(e)
+    }
+}
+
+impl From<std::io::Error> for KeystoreError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Serialization(e.into())
+    }
+}
+
+impl core::fmt::Display for KeystoreError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Serialization(e) => write!(f, "malformed keystore: {}", e),
+            Self::NotAKeystore => write!(f, "not a keystore file"),
+            Self::UnsupportedVersion(v) => write!(f, "unsupported keystore version {}", v),
+            Self::InvalidParameters => write!(f, "invalid key derivation parameters"),
+            Self::WrongPassword => write!(f, "wrong keystore password"),
+            Self::IntegrityCheckFailed => write!(f, "keystore integrity check failed"),
+            Self::InvalidLabel(label) => write!(f, "invalid keystore label {:?}", label),
+        }
+    }
+}
+
+impl std::error::Error for KeystoreError {}
+
+/// The parameters of the scrypt key derivation of a keystore.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KdfParams {
+    /// The base-two logarithm of the CPU and memory cost `N`.
+    pub log_n: u8,
+    /// The block size `r`.
+    pub r: u32,
+    /// The parallelization `p`.
+    pub p: u32,
+}
+
+impl Default for KdfParams {
+    /// `N = 2^15`, `r = 8`, `p = 1`, which takes 32 MiB of memory.
+    fn default() -> Self {
+        Self {
+            log_n: 15,
+            r: 8,
+            p: 1,
+        }
+    }
+}
+
+impl KdfParams {
+    /// The bytes of memory the key derivation takes, `128 * r * (N + p + 1)`.
+    pub fn memory(&self) -> u128 {
+        let n = 1u128.checked_shl(self.log_n.into()).unwrap_or(u128::MAX);
+        (128 * self.r as u128).saturating_mul(n.saturating_add(self.p as u128 + 1))
+    }
+
+    /// Fill `output`, of 10 to 64 bytes, with the scrypt derivation of
+    /// `password` and `salt` under these parameters.
+    pub fn derive_key(
+        &self,
+        password: &[u8],
+        salt: &[u8],
+        output: &mut [u8],
+    ) -> Result<(), KeystoreError> {
+        let params = self.scrypt_params(output.len())?;
+        scrypt::scrypt(password, salt, &params, output)
+            .map_err(|_| KeystoreError::InvalidParameters)
+    }
+
+    fn scrypt_params(&self, len: usize) -> Result<scrypt::Params, KeystoreError> {
+        if self.log_n == 0 {
+            return Err(KeystoreError::InvalidParameters);
+        }
+        scrypt::Params::new(self.log_n, self.r, self.p, len)
+            .map_err(|_| KeystoreError::InvalidParameters)
+    }
+}
+
+/// The kind of secret stored under a label of a [`Keystore`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SecretKind {
+    /// A [`Randomizer`].
+    Randomizer,
+    /// A setup [`Trapdoor`].
+    Trapdoor,
+}
+
+impl SecretKind {
+    fn to_byte(self) -> u8 {
+        match self {
+            Self::Randomizer => 0,
+            Self::Trapdoor => 1,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Result<Self, KeystoreError> {
+        match byte {
+            0 => Ok(Self::Randomizer),
+            1 => Ok(Self::Trapdoor),
+            _ => Err(SerializationError::InvalidData.into()),
+        }
+    }
+}
+
+/// A secret stored in a [`Keystore`].
+#[derive(Clone, Debug, PartialEq)]
+enum Entry<E: Pairing> {
+    Randomizer(Randomizer<E>),
+    Trapdoor(Trapdoor<E>),
+}
+
+impl<E: Pairing> Entry<E> {
+    fn kind(&self) -> SecretKind {
+        match self {
+            Self::Randomizer(_) => SecretKind::Randomizer,
+            Self::Trapdoor(_) => SecretKind::Trapdoor,
+        }
+    }
+}
+
+/// Secret randomizers and setup trapdoors under labels, stored encrypted
+/// under a password.
+///
+/// # File format
+///
+/// All integers are little-endian.
+///
+/// | bytes                | content                                         |
+/// |----------------------|-------------------------------------------------|
+/// | 4                    | magic `PZKS`                                    |
+/// | 1                    | format version, currently `2`                   |
+/// | 1                    | `log2(N)` of scrypt                             |
+/// | 4                    | `r` of scrypt                                   |
+/// | 4                    | `p` of scrypt                                   |
+/// | 32                   | salt                                            |
+/// | 12                   | nonce                                           |
+/// | 32                   | key check                                       |
+/// | 4                    | number of entries                               |
+/// | per entry            | kind (`0` randomizer, `1` trapdoor), 2-byte label length, UTF-8 label, 4-byte ciphertext length, ciphertext |
+///
+/// The password and salt derive through scrypt a 32-byte encryption key and
+/// a 32-byte check key. The key check is the SHA-256 of the check key, and
+/// tells a wrong password from a tampered file. Secrets are encrypted with
+/// their [`Randomizer::export`] or [`Trapdoor::export`] encoding by
+/// ChaCha20-Poly1305. The `i`-th entry is encrypted under the nonce with `i`
+/// added to its last four bytes, and authenticates the header and its own
+/// kind, label and ciphertext length as associated data, so that entries
+/// cannot be altered, reordered, dropped or added. The file is written with a
+/// fresh salt and nonce on every save.
+///
+/// Labels and kinds are stored in the clear, so that [`Keystore::list`] needs
+/// no password.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Keystore<E: Pairing> {
+    entries: Vec<(String, Entry<E>)>,
+}
+
+impl<E: Pairing> Default for Keystore<E> {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl<E: Pairing> Keystore<E> {
+    /// An empty keystore.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store the randomizer `rnd` under `label`.
+    pub fn insert_randomizer(
+        &mut self,
+        label: &str,
+        rnd: Randomizer<E>,
+    ) -> Result<(), KeystoreError> {
+        self.insert(label, Entry::Randomizer(rnd))
+    }
+
+    /// Store the setup trapdoor `trapdoor` under `label`.
+    pub fn insert_trapdoor(
+        &mut self,
+        label: &str,
+        trapdoor: Trapdoor<E>,
+    ) -> Result<(), KeystoreError> {
+        self.insert(label, Entry::Trapdoor(trapdoor))
+    }
+
+    fn insert(&mut self, label: &str, entry: Entry<E>) -> Result<(), KeystoreError> {
+        if label.len() > u16::MAX as usize || self.entries.iter().any(|(l, _)| l == label) {
+            return Err(KeystoreError::InvalidLabel(label.to_string()));
+        }
+        self.entries.push((label.to_string(), entry));
+        Ok(())
+    }
+
+    /// The randomizer stored under `label`, if any.
+    pub fn randomizer(&self, label: &str) -> Option<&Randomizer<E>> {
+        self.entries.iter().find_map(|(l, entry)| match entry {
+            Entry::Randomizer(rnd) if l == label => Some(rnd),
+            _ => None,
+        })
+    }
+
+    /// The setup trapdoor stored under `label`, if any.
+    pub fn trapdoor(&self, label: &str) -> Option<&Trapdoor<E>> {
+        self.entries.iter().find_map(|(l, entry)| match entry {
+            Entry::Trapdoor(trapdoor) if l == label => Some(trapdoor),
+            _ => None,
+        })
+    }
+
+    /// Remove the secret stored under `label`, returning whether there was
+    /// one.
+    pub fn remove(&mut self, label: &str) -> bool {
+        let len = self.entries.len();
+        self.entries.retain(|(l, _)| l != label);
+        self.entries.len() != len
+    }
+
+    /// The labels of the stored secrets, with their kinds, in insertion order.
+    pub fn labels(&self) -> Vec<(&str, SecretKind)> {
+        self.entries
+            .iter()
+            .map(|(label, entry)| (label.as_str(), entry.kind()))
+            .collect()
+    }
+
+    /// Encrypt the keystore under `password` with the key derivation
+    /// parameters `params`, and write it to `writer`.
+    pub fn save<W: Write, R: RngCore>(
+        &self,
+        mut writer: W,
+        password: &[u8],
+        params: KdfParams,
+        rng: &mut R,
+    ) -> Result<(), KeystoreError> {
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce = [0u8; NONCE_LEN];
+        rng.fill_bytes(&mut salt);
+        rng.fill_bytes(&mut nonce);
+        let keys = DerivedKeys::derive(password, &salt, params)?;
+
+        let mut file = Vec::new();
+        file.extend_from_slice(&KEYSTORE_MAGIC);
+        file.push(KEYSTORE_VERSION);
+        file.push(params.log_n);
+        file.extend_from_slice(&params.r.to_le_bytes());
+        file.extend_from_slice(&params.p.to_le_bytes());
+        file.extend_from_slice(&salt);
+        file.extend_from_slice(&nonce);
+        file.extend_from_slice(&keys.key_check());
+        file.extend_from_slice(&(self.entries.len() as u32).to_le_bytes());
+        for (i, (label, entry)) in self.entries.iter().enumerate() {
+            let mut plaintext = Vec::new();
+            match entry {
+                Entry::Randomizer(rnd) => rnd.export(&mut plaintext)?,
+                Entry::Trapdoor(trapdoor) => trapdoor.export(&mut plaintext)?,
+            }
+            let metadata = entry_metadata(entry.kind(), label, plaintext.len() + TAG_LEN);
+            let ciphertext =
+                keys.encrypt(&file[..HEADER_LEN], &nonce, i as u32, &metadata, &plaintext);
+            plaintext.zeroize();
+
+            file.extend_from_slice(&metadata);
+            file.extend_from_slice(&ciphertext);
+        }
+
+        writer.write_all(&file)?;
+        Ok(())
+    }
+
+    /// Read a keystore written by [`save`](Self::save), and decrypt it with
+    /// `password`.
+    ///
+    /// Files whose key derivation takes more than [`DEFAULT_MAX_KDF_MEMORY`]
+    /// are rejected with [`KeystoreError::InvalidParameters`] before deriving
+    /// anything; see [`load_with_memory_limit`](Self::load_with_memory_limit).
+    pub fn load<R: Read>(reader: R, password: &[u8]) -> Result<Self, KeystoreError> {
+        Self::load_with_memory_limit(reader, password, DEFAULT_MAX_KDF_MEMORY)
+    }
+
+    /// Read a keystore written by [`save`](Self::save), and decrypt it with
+    /// `password`, if its key derivation takes at most `max_kdf_memory`
+    /// bytes of memory.
+    ///
+    /// The key derivation parameters are read from the file before it is
+    /// authenticated, so the limit is what keeps a crafted file from
+    /// exhausting the memory of the reader.
+    pub fn load_with_memory_limit<R: Read>(
+        mut reader: R,
+        password: &[u8],
+        max_kdf_memory: u64,
+    ) -> Result<Self, KeystoreError> {
+        let mut file = Vec::new();
+        reader.read_to_end(&mut file)?;
+        let header = Header::parse(&file)?;
+        if header.params.memory() > max_kdf_memory as u128 {
+            return Err(KeystoreError::InvalidParameters);
+        }
+
+        let keys = DerivedKeys::derive(password, header.salt, header.params)?;
+        if !constant_time_eq(&keys.key_check(), header.key_check) {
+            return Err(KeystoreError::WrongPassword);
+        }
+
+        let mut entries = Vec::with_capacity(header.entries.len());
+        for (i, (label, kind, ciphertext)) in header.entries.into_iter().enumerate() {
+            let metadata = entry_metadata(kind, &label, ciphertext.len());
+            let mut plaintext = keys.decrypt(
+                &file[..HEADER_LEN],
+                header.nonce,
+                i as u32,
+                &metadata,
+                ciphertext,
+            )?;
+            let entry = match kind {
+                SecretKind::Randomizer => Randomizer::import(&plaintext[..]).map(Entry::Randomizer),
+                SecretKind::Trapdoor => Trapdoor::import(&plaintext[..]).map(Entry::Trapdoor),
+            };
+            plaintext.zeroize();
+            entries.push((label, entry?));
+        }
+
+        Ok(Self { entries })
+    }
+
+    /// The labels of the secrets of a keystore written by
+    /// [`save`](Self::save), with their kinds, read without a password.
+    ///
+    /// Labels are only authenticated when the keystore is
+    /// [loaded](Self::load).
+    pub fn list<R: Read>(mut reader: R) -> Result<Vec<(String, SecretKind)>, KeystoreError> {
+        let mut file = Vec::new();
+        reader.read_to_end(&mut file)?;
+        Ok(Header::parse(&file)?
+            .entries
+            .into_iter()
+            .map(|(label, kind, _)| (label, kind))
+            .collect())
+    }
+
+    /// Re-encrypt the keystore read from `reader` under `old_password` with
+    /// `new_password` and the parameters `params`, and write it to `writer`.
+    pub fn rotate_password<R: Read, W: Write, G: RngCore>(
+        reader: R,
+        old_password: &[u8],
+        writer: W,
+        new_password: &[u8],
+        params: KdfParams,
+        rng: &mut G,
+    ) -> Result<(), KeystoreError> {
+        Self::load(reader, old_password)?.save(writer, new_password, params, rng)
+    }
+}
+
+/// The parsed clear-text parts of a keystore file, and its ciphertexts.
+struct Header<'a> {
+    params: KdfParams,
+    salt: &'a [u8],
+    nonce: &'a [u8; NONCE_LEN],
+    key_check: &'a [u8],
+    entries: Vec<(String, SecretKind, &'a [u8])>,
+}
+
+impl<'a> Header<'a> {
+    fn parse(file: &'a [u8]) -> Result<Self, KeystoreError> {
+        let mut bytes = file;
+        let mut take = |n: usize| -> Result<&'a [u8], KeystoreError> {
+            if bytes.len() < n {
+                return Err(SerializationError::InvalidData.into());
+            }
+            let (head, tail) = bytes.split_at(n);
+            bytes = tail;
+            Ok(head)
+        };
+        let u32_le = |b: &[u8]| u32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+
+        if take(4)? != KEYSTORE_MAGIC {
+            return Err(KeystoreError::NotAKeystore);
+        }
+        let version = take(1)?[0];
+        if version != KEYSTORE_VERSION {
+            return Err(KeystoreError::UnsupportedVersion(version));
+        }
+        let params = KdfParams {
+            log_n: take(1)?[0],
+            r: u32_le(take(4)?),
+            p: u32_le(take(4)?),
+        };
+        params.scrypt_params(64)?;
+        let salt = take(SALT_LEN)?;
+        let nonce = take(NONCE_LEN)?
+            .try_into()
+            .expect("the nonce has NONCE_LEN bytes");
+        let key_check = take(KEY_CHECK_LEN)?;
+
+        let num_entries = u32_le(take(4)?);
+        let mut entries = Vec::new();
+        for _ in 0..num_entries {
+            let kind = SecretKind::from_byte(take(1)?[0])?;
+            let label_len = take(2)?;
+            let label = take(u16::from_le_bytes([label_len[0], label_len[1]]) as usize)?;
+            let label = core::str::from_utf8(label)
+                .map_err(|_| SerializationError::InvalidData)?
+                .to_string();
+            let ciphertext_len = u32_le(take(4)?) as usize;
+            if ciphertext_len < TAG_LEN {
+                return Err(SerializationError::InvalidData.into());
+            }
+            entries.push((label, kind, take(ciphertext_len)?));
+        }
+        if !bytes.is_empty() {
+            return Err(SerializationError::InvalidData.into());
+        }
+
+        Ok(Self {
+            params,
+            salt,
+            nonce,
+            key_check,
+            entries,
+        })
+    }
+}
+
+/// The kind, label and ciphertext length of an entry, as written before its
+/// ciphertext.
+fn entry_metadata(kind: SecretKind, label: &str, ciphertext_len: usize) -> Vec<u8> {
+    let mut metadata = Vec::with_capacity(7 + label.len());
+    metadata.push(kind.to_byte());
+    metadata.extend_from_slice(&(label.len() as u16).to_le_bytes());
+    metadata.extend_from_slice(label.as_bytes());
+    metadata.extend_from_slice(&(ciphertext_len as u32).to_le_bytes());
+    metadata
+}
+
+/// The encryption and check keys derived from a password.
+struct DerivedKeys {
+    encryption_key: [u8; 32],
+    check_key: [u8; 32],
+}
+
+impl DerivedKeys {
+    fn derive(password: &[u8], salt: &[u8], params: KdfParams) -> Result<Self, KeystoreError> {
+        let mut key = [0u8; 64];
+        params.derive_key(password, salt, &mut key)?;
+        let mut keys = Self {
+            encryption_key: [0u8; 32],
+            check_key: [0u8; 32],
+        };
+        keys.encryption_key.copy_from_slice(&key[..32]);
+        keys.check_key.copy_from_slice(&key[32..]);
+        key.zeroize();
+        Ok(keys)
+    }
+
+    fn key_check(&self) -> [u8; KEY_CHECK_LEN] {
+        Sha256::new()
+            .chain_update(KEY_CHECK_DOMAIN)
+            .chain_update(self.check_key)
+            .finalize()
+            .into()
+    }
+
+    /// The nonce of the `entry`-th entry.
+    fn entry_nonce(nonce: &[u8; NONCE_LEN], entry: u32) -> Nonce {
+        let mut entry_nonce = *nonce;
+        let counter = u32::from_le_bytes(nonce[8..].try_into().unwrap()).wrapping_add(entry);
+        entry_nonce[8..].copy_from_slice(&counter.to_le_bytes());
+        entry_nonce.into()
+    }
+
+    /// The associated data of an entry: the header of the file, and the
+    /// metadata of the entry.
+    fn aad(header: &[u8], metadata: &[u8]) -> Vec<u8> {
+        [header, metadata].concat()
+    }
+
+    fn encrypt(
+        &self,
+        header: &[u8],
+        nonce: &[u8; NONCE_LEN],
+        entry: u32,
+        metadata: &[u8],
+        plaintext: &[u8],
+    ) -> Vec<u8> {
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(&self.encryption_key));
+        let aad = Self::aad(header, metadata);
+        let payload = Payload {
+            msg: plaintext,
+            aad: &aad,
+        };
+        cipher
+            .encrypt(&Self::entry_nonce(nonce, entry), payload)
+            .expect("entries are far shorter than the ChaCha20-Poly1305 limit")
+    }
+
+    fn decrypt(
+        &self,
+        header: &[u8],
+        nonce: &[u8; NONCE_LEN],
+        entry: u32,
+        metadata: &[u8],
+        ciphertext: &[u8],
+    ) -> Result<Vec<u8>, KeystoreError> {
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(&self.encryption_key));
+        let aad = Self::aad(header, metadata);
+        let payload = Payload {
+            msg: ciphertext,
+            aad: &aad,
+        };
+        cipher
+            .decrypt(&Self::entry_nonce(nonce, entry), payload)
+            .map_err(|_| KeystoreError::IntegrityCheckFailed)
+    }
+}
+
+impl Drop for DerivedKeys {
+    fn drop(&mut self) {
+        self.encryption_key.zeroize();
+        self.check_key.zeroize();
+    }
+}
+
+/// Compare two byte strings in time independent of where they differ.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
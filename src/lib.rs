@@ -30,22 +30,77 @@ pub mod prover;
 /// Verify proofs for the Groth16 zkSNARK construction.
 pub mod verifier;
 
+/// Secret scalars that are zeroed on drop and only serialized explicitly.
+pub mod secret;
+
+/// A password-encrypted file format for randomizers and setup trapdoors.
+pub mod keystore;
+
+/// Simulate proofs from the trapdoor of the setup.
+pub mod simulator;
+
+/// Sign randomizers, so that verifiers can check who issued them.
+pub mod issuance;
+
+/// Generate randomizers jointly, so that no single party knows their secrets.
+pub mod threshold;
+
+/// Fingerprint witnesses across randomizers, for the holder of their secrets.
+pub mod fingerprint;
+
+/// Rotate the delta of a proving key, retiring the randomizers issued under it.
+pub mod refresh;
+
+/// A commit-reveal protocol for submitting proofs to first-solver-wins contests.
+pub mod submission;
+
+/// Anonymous polls with one vote per member of a Merkle tree.
+#[cfg(feature = "r1cs")]
+pub mod poll;
+
+/// Memory-bounded proving and setup over proving keys stored in chunks.
+pub mod streaming;
+
+/// Binary and JSON file formats for full circuit assignments.
+pub mod witness;
+
+/// Aggregation of proofs sharing a randomizer into a logarithmic-size proof.
+pub mod aggregation;
+
+/// A polymorphic variant of the GM17 zkSNARK, as a second backend.
+pub mod gm17;
+
+/// Circuit combinators built on top of any `ConstraintSynthesizer`.
+pub mod circuits;
+
 pub use self::data_structures::*;
 pub use self::{generator::*, prover::*, verifier::*};
 
 // use ark_crypto_primitives::snark::*;
 use ark_ec::pairing::Pairing;
+use ark_poly::{EvaluationDomain, GeneralEvaluationDomain};
 use ark_relations::r1cs::{ConstraintSynthesizer, SynthesisError};
 use ark_std::rand::RngCore;
 use ark_std::{marker::PhantomData, vec::Vec};
 use r1cs_to_qap::{LibsnarkReduction, R1CSToQAP};
 
 /// The SNARK of [[Groth16]](https://eprint.iacr.org/2016/260.pdf).
-pub struct Groth16<E: Pairing, QAP: R1CSToQAP = LibsnarkReduction> {
-    _p: PhantomData<(E, QAP)>,
+///
+/// `QAP` selects the R1CS-to-QAP reduction and `D` the evaluation domain it
+/// runs over. Keys and proofs are only compatible between instantiations
+/// whose reductions agree, e.g. [`LibsnarkReduction`] and
+/// [`r1cs_to_qap::Radix2Reduction`].
+pub struct Groth16<
+    E: Pairing,
+    QAP: R1CSToQAP = LibsnarkReduction,
+    D: EvaluationDomain<E::ScalarField> = GeneralEvaluationDomain<<E as Pairing>::ScalarField>,
+> {
+    _p: PhantomData<(E, QAP, D)>,
 }
 
-impl<E: Pairing, QAP: R1CSToQAP> PolymorphicSNARK<E::ScalarField> for Groth16<E, QAP> {
+impl<E: Pairing, QAP: R1CSToQAP, D: EvaluationDomain<E::ScalarField>>
+    PolymorphicSNARK<E::ScalarField> for Groth16<E, QAP, D>
+{
     type ProvingKey = ProvingKey<E>;
     type VerifyingKey = VerifyingKey<E>;
     type Randomizer = Randomizer<E>;
@@ -75,7 +130,19 @@ impl<E: Pairing, QAP: R1CSToQAP> PolymorphicSNARK<E::ScalarField> for Groth16<E,
         circuit: C,
         rnd: &Self::Randomizer,
     ) -> Result<Self::Proof, Self::Error> {
-        Self::create_proof_with_reduction(circuit, pk, rnd.r, rnd.s)
+        Self::create_proof_with_prepared_randomizer(
+            circuit,
+            pk,
+            &Self::prepare_randomizer(pk, rnd)?,
+        )
+    }
+
+    fn prove_many<C: ConstraintSynthesizer<E::ScalarField> + Send>(
+        pk: &Self::ProvingKey,
+        circuits: Vec<C>,
+        rnd: &Self::Randomizer,
+    ) -> Result<Vec<Self::Proof>, Self::Error> {
+        Self::create_proofs_with_reduction(circuits, pk, &Self::prepare_randomizer(pk, rnd)?)
     }
 
     fn process_vk(
@@ -98,7 +165,7 @@ impl<E: Pairing, QAP: R1CSToQAP> PolymorphicSNARK<E::ScalarField> for Groth16<E,
     }
 }
 
-impl<E: Pairing, QAP: R1CSToQAP> CircuitSpecificSetupPolymorphicSNARK<E::ScalarField>
-    for Groth16<E, QAP>
+impl<E: Pairing, QAP: R1CSToQAP, D: EvaluationDomain<E::ScalarField>>
+    CircuitSpecificSetupPolymorphicSNARK<E::ScalarField> for Groth16<E, QAP, D>
 {
 }
@@ -0,0 +1,355 @@
+//! Anonymous polls with one vote per member.
+//!
+//! Members are the leaves `H(secret)` of a Poseidon Merkle tree, and a ballot
+//! is a choice together with a proof of knowledge of the secret of a leaf of
+//! the tree, under the randomizer of the poll. The proof also exposes the
+//! nullifier `H(secret, poll_id)` of the member, which is the same for all
+//! the ballots of a member in a poll, whatever their choices. The tally counts
+//! one ballot per nullifier without learning which member cast it. Unlike
+//! the proof elements, which anyone can rescale into another valid proof,
+//! the nullifier is fixed by the secret and the poll.
+//!
+//! Members cast their ballots with [`Ballot::cast`], from the membership
+//! key, the randomizer and the [`PollInfo`] the operator publishes, without
+//! the operator's [`Poll`].
+//!
+//! The nullifiers of a member differ between polls, but the ballots are only
+//! anonymous towards those who do not hold the randomizer of the poll. Every
+//! member proves under it, so every member, and not only the operator, holds
+//! `r` and can strip it from `A` as
+//! [`witness_fingerprint`](crate::Groth16::witness_fingerprint) does, and
+//! compare what remains, a function of the witness of the voter, across
+//! ballots and polls. The operator and all the members are thus trusted not
+//! to link the ballots of a member, and the ballots are only anonymous
+//! towards outsiders.
+
+use crate::{prepare_verifying_key, Groth16, PreparedVerifyingKey, Proof, ProvingKey, Randomizer};
+use ark_crypto_primitives::{
+    crh::{
+        poseidon::{
+            constraints::{CRHGadget, CRHParametersVar, TwoToOneCRHGadget},
+            TwoToOneCRH, CRH,
+        },
+        CRHScheme, CRHSchemeGadget,
+    },
+    merkle_tree::{
+        constraints::{ConfigGadget, PathVar},
+        Config, IdentityDigestConverter, MerkleTree, Path,
+    },
+    sponge::{
+        poseidon::{find_poseidon_ark_and_mds, PoseidonConfig},
+        Absorb,
+    },
+};
+use ark_ec::pairing::Pairing;
+use ark_ff::PrimeField;
+use ark_r1cs_std::{alloc::AllocVar, boolean::Boolean, eq::EqGadget, fields::fp::FpVar};
+use ark_relations::r1cs::{
+    ConstraintSynthesizer, ConstraintSystemRef, Result as R1CSResult, SynthesisError,
+};
+use ark_serialize::*;
+use ark_std::{marker::PhantomData, rand::Rng, vec, vec::Vec};
+use std::collections::HashSet;
+
+/// The Merkle tree configuration of the members of a poll, over Poseidon.
+pub struct MemberTreeConfig<F>(PhantomData<F>);
+
+impl<F: PrimeField + Absorb> Config for MemberTreeConfig<F> {
+    type Leaf = [F];
+    type LeafDigest = F;
+    type LeafInnerDigestConverter = IdentityDigestConverter<F>;
+    type InnerDigest = F;
+    type LeafHash = CRH<F>;
+    type TwoToOneHash = TwoToOneCRH<F>;
+}
+
+/// The gadgets of [`MemberTreeConfig`].
+pub struct MemberTreeConfigVar<F>(PhantomData<F>);
+
+impl<F: PrimeField + Absorb> ConfigGadget<MemberTreeConfig<F>, F> for MemberTreeConfigVar<F> {
+    type Leaf = [FpVar<F>];
+    type LeafDigest = FpVar<F>;
+    type LeafInnerConverter = IdentityDigestConverter<FpVar<F>>;
+    type InnerDigest = FpVar<F>;
+    type LeafHash = CRHGadget<F>;
+    type TwoToOneHash = TwoToOneCRHGadget<F>;
+}
+
+/// The Merkle tree of the members of a poll.
+pub type MemberTree<F> = MerkleTree<MemberTreeConfig<F>>;
+
+/// The Poseidon parameters of the member tree, with rate 2, `x^17` S-boxes,
+/// 8 full and 31 partial rounds.
+pub fn poseidon_config<F: PrimeField>() -> PoseidonConfig<F> {
+    let (ark, mds) = find_poseidon_ark_and_mds::<F>(F::MODULUS_BIT_SIZE as u64, 2, 8, 31, 0);
+    PoseidonConfig::new(8, 31, 17, mds, ark, 2, 1)
+}
+
+/// The leaf of the member with `secret`.
+pub fn member_leaf<F: PrimeField + Absorb>(params: &PoseidonConfig<F>, secret: F) -> F {
+    CRH::evaluate(params, [secret]).expect("Poseidon hashes any input")
+}
+
+/// The nullifier of the member with `secret` in the poll `poll_id`.
+pub fn member_nullifier<F: PrimeField + Absorb>(
+    params: &PoseidonConfig<F>,
+    secret: F,
+    poll_id: F,
+) -> F {
+    CRH::evaluate(params, [secret, poll_id]).expect("Poseidon hashes any input")
+}
+
+/// The tree of the members with leaves `leaves`, padded with zero leaves to
+/// a power of two of at least two.
+pub fn member_tree<F: PrimeField + Absorb>(
+    params: &PoseidonConfig<F>,
+    leaves: &[F],
+) -> R1CSResult<MemberTree<F>> {
+    let mut leaves = leaves.to_vec();
+    leaves.resize(leaves.len().max(2).next_power_of_two(), F::zero());
+    MemberTree::new_with_leaf_digest(params, params, leaves)
+        .map_err(|_| SynthesisError::Unsatisfiable)
+}
+
+/// Proves knowledge of the secret of a leaf of the member tree with root
+/// `root`, with the public inputs `root`, `poll_id`, `choice` and the
+/// nullifier of the member in the poll.
+pub struct MembershipCircuit<F: PrimeField + Absorb> {
+    /// The Poseidon parameters of the tree.
+    pub params: PoseidonConfig<F>,
+    /// The root of the member tree.
+    pub root: F,
+    /// The id of the poll.
+    pub poll_id: F,
+    /// The choice of the ballot.
+    pub choice: F,
+    /// The secret of the member.
+    pub secret: F,
+    /// The path of the leaf of the member.
+    pub path: Path<MemberTreeConfig<F>>,
+}
+
+impl<F: PrimeField + Absorb> MembershipCircuit<F> {
+    /// A circuit for the setup of trees of height `height`.
+    pub fn blank(params: PoseidonConfig<F>, height: usize) -> Self {
+        Self {
+            params,
+            root: F::zero(),
+            poll_id: F::zero(),
+            choice: F::zero(),
+            secret: F::zero(),
+            path: Path {
+                leaf_sibling_hash: F::zero(),
+                auth_path: vec![F::zero(); height.saturating_sub(2)],
+                leaf_index: 0,
+            },
+        }
+    }
+}
+
+impl<F: PrimeField + Absorb> ConstraintSynthesizer<F> for MembershipCircuit<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> R1CSResult<()> {
+        let nullifier = member_nullifier(&self.params, self.secret, self.poll_id);
+        let root = FpVar::new_input(cs.clone(), || Ok(self.root))?;
+        let poll_id = FpVar::new_input(cs.clone(), || Ok(self.poll_id))?;
+        let _choice = FpVar::new_input(cs.clone(), || Ok(self.choice))?;
+        let nullifier = FpVar::new_input(cs.clone(), || Ok(nullifier))?;
+        let secret = FpVar::new_witness(cs.clone(), || Ok(self.secret))?;
+        let params = CRHParametersVar::new_constant(cs.clone(), &self.params)?;
+        CRHGadget::evaluate(&params, &[secret.clone(), poll_id])?.enforce_equal(&nullifier)?;
+        let path =
+            PathVar::<MemberTreeConfig<F>, F, MemberTreeConfigVar<F>>::new_witness(cs, || {
+                Ok(&self.path)
+            })?;
+        path.verify_membership(&params, &params, &root, &[secret][..])?
+            .enforce_equal(&Boolean::TRUE)
+    }
+}
+
+/// What a member needs to cast a ballot in a poll, besides the membership
+/// key and the randomizer of the poll.
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PollInfo<E: Pairing> {
+    /// The root of the member tree.
+    pub root: E::ScalarField,
+    /// The id of the poll.
+    pub poll_id: u64,
+    /// The number of choices.
+    pub num_choices: u64,
+}
+
+/// A choice together with the proof of membership of its voter.
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Ballot<E: Pairing> {
+    /// The index of the choice.
+    pub choice: u64,
+    /// The nullifier of the voter in the poll.
+    pub nullifier: E::ScalarField,
+    /// The proof of membership.
+    pub proof: Proof<E>,
+}
+
+impl<E: Pairing> Ballot<E>
+where
+    E::ScalarField: Absorb,
+{
+    /// Cast the ballot of the member with `secret`, whose leaf is at `path`,
+    /// in the poll `info`, under the membership key `pk` and the randomizer
+    /// `rnd` of the poll.
+    ///
+    /// Fails with [`SynthesisError::Unsatisfiable`] if `path` does not lead
+    /// from the leaf of `secret` to the root, e.g. for an outsider.
+    pub fn cast(
+        pk: &ProvingKey<E>,
+        rnd: &Randomizer<E>,
+        params: &PoseidonConfig<E::ScalarField>,
+        info: &PollInfo<E>,
+        secret: E::ScalarField,
+        path: Path<MemberTreeConfig<E::ScalarField>>,
+        choice: u64,
+    ) -> R1CSResult<Self> {
+        let member = path
+            .verify(params, params, &info.root, &[secret][..])
+            .map_err(|_| SynthesisError::Unsatisfiable)?;
+        if !member {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let poll_id = info.poll_id.into();
+        let circuit = MembershipCircuit {
+            params: params.clone(),
+            root: info.root,
+            poll_id,
+            choice: choice.into(),
+            secret,
+            path,
+        };
+        let rnd = Groth16::<E>::prepare_randomizer(pk, rnd)?;
+        let proof = Groth16::<E>::create_proof_with_prepared_randomizer(circuit, pk, &rnd)?;
+        Ok(Self {
+            choice,
+            nullifier: member_nullifier(params, secret, poll_id),
+            proof,
+        })
+    }
+}
+
+/// The outcome of a poll.
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Tally {
+    /// The number of votes of each choice.
+    pub counts: Vec<u64>,
+    /// The number of ballots whose proof does not verify, or whose choice is
+    /// out of range.
+    pub invalid: u64,
+    /// The number of ballots of members who had already voted.
+    pub duplicates: u64,
+}
+
+/// A poll among the members of a tree, with a randomizer of its own.
+pub struct Poll<E: Pairing>
+where
+    E::ScalarField: Absorb,
+{
+    pk: ProvingKey<E>,
+    pvk: PreparedVerifyingKey<E>,
+    params: PoseidonConfig<E::ScalarField>,
+    info: PollInfo<E>,
+    rnd: Randomizer<E>,
+}
+
+impl<E: Pairing> Poll<E>
+where
+    E::ScalarField: Absorb,
+{
+    /// Open the poll `poll_id` with `num_choices` choices among the members
+    /// of the tree with root `root`, under the membership key `pk`, issuing
+    /// its randomizer. The ids of distinct polls must differ, so that the
+    /// nullifiers of a member do.
+    pub fn new<R: Rng>(
+        pk: ProvingKey<E>,
+        params: PoseidonConfig<E::ScalarField>,
+        root: E::ScalarField,
+        poll_id: u64,
+        num_choices: u64,
+        rng: &mut R,
+    ) -> R1CSResult<Self> {
+        let rnd = Groth16::<E>::create_randomizer(&pk, rng)?;
+        Ok(Self {
+            pvk: prepare_verifying_key(&pk.vk),
+            pk,
+            params,
+            info: PollInfo {
+                root,
+                poll_id,
+                num_choices,
+            },
+            rnd,
+        })
+    }
+
+    /// The randomizer of the poll, which members prove under.
+    pub fn randomizer(&self) -> &Randomizer<E> {
+        &self.rnd
+    }
+
+    /// The poll as members see it, to cast their ballots with
+    /// [`Ballot::cast`].
+    pub fn info(&self) -> &PollInfo<E> {
+        &self.info
+    }
+
+    /// Cast the ballot of the member with `secret`, whose leaf is at `path`,
+    /// as [`Ballot::cast`] does.
+    pub fn vote(
+        &self,
+        secret: E::ScalarField,
+        path: Path<MemberTreeConfig<E::ScalarField>>,
+        choice: u64,
+    ) -> R1CSResult<Ballot<E>> {
+        Ballot::cast(
+            &self.pk,
+            &self.rnd,
+            &self.params,
+            &self.info,
+            secret,
+            path,
+            choice,
+        )
+    }
+
+    fn public_inputs(&self, ballot: &Ballot<E>) -> [E::ScalarField; 4] {
+        [
+            self.info.root,
+            self.info.poll_id.into(),
+            ballot.choice.into(),
+            ballot.nullifier,
+        ]
+    }
+
+    /// Verify `ballots`, and count the valid ballots, one per nullifier.
+    pub fn tally(&self, ballots: &[Ballot<E>]) -> R1CSResult<Tally> {
+        let mut tally = Tally {
+            counts: vec![0; self.info.num_choices as usize],
+            invalid: 0,
+            duplicates: 0,
+        };
+        let mut nullifiers = HashSet::new();
+        for ballot in ballots {
+            let valid = ballot.choice < self.info.num_choices
+                && Groth16::<E>::verify_proof(
+                    &self.pvk,
+                    &ballot.proof,
+                    &self.rnd,
+                    &self.public_inputs(ballot),
+                )?;
+            if !valid {
+                tally.invalid += 1;
+            } else if !nullifiers.insert(ballot.nullifier) {
+                tally.duplicates += 1;
+            } else {
+                tally.counts[ballot.choice as usize] += 1;
+            }
+        }
+        Ok(tally)
+    }
+}
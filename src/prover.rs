@@ -1,39 +1,96 @@
-use crate::Randomizer;
-use crate::{r1cs_to_qap::R1CSToQAP, Groth16, Proof, ProvingKey};
+use crate::{
+    r1cs_to_qap::R1CSToQAP,
+    streaming::{ProverConfig, ProvingKeySource, Query},
+    Groth16, Proof, ProverIndex, ProvingKey, QueryDensity,
+};
+use crate::{
+    secret::SecretScalar, CommitmentOpeningProof, CommittedProof, PreparedProverRandomizer,
+    ProofAccumulators, ProofCache, Randomizer,
+};
 use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup, Group, VariableBaseMSM};
-use ark_ff::{PrimeField, UniformRand, Zero};
-use ark_poly::GeneralEvaluationDomain;
+use ark_ff::{
+    field_hashers::{DefaultFieldHasher, HashToField},
+    BigInteger, One, PrimeField, UniformRand, Zero,
+};
+use ark_poly::EvaluationDomain;
 use ark_relations::r1cs::{
     ConstraintSynthesizer, ConstraintSystem, OptimizationGoal, Result as R1CSResult,
+    SynthesisError, SynthesisMode,
 };
+use ark_serialize::CanonicalSerialize;
 use ark_std::rand::Rng;
-use ark_std::{
-    cfg_into_iter, cfg_iter,
-    ops::{AddAssign, Mul},
-    vec::Vec,
-};
+use ark_std::{borrow::Cow, cfg_into_iter, cfg_iter, iter, ops::Range, vec, vec::Vec};
+
+use sha2::Sha256;
+use zeroize::Zeroize;
 
 #[cfg(feature = "parallel")]
 use rayon::prelude::*;
 
-type D<F> = GeneralEvaluationDomain<F>;
+/// The domain separation tag of the nonces of a [`CommitmentOpeningProof`].
+const OPENING_NONCE_DOMAIN: &[u8] = b"polymorphic-groth16-commitment-nonces";
 
-impl<E: Pairing, QAP: R1CSToQAP> Groth16<E, QAP> {
+/// The domain separation tag of the default blinding of the commitment `D`.
+const COMMITMENT_BLINDING_DOMAIN: &[u8] = b"polymorphic-groth16-commitment-blinding";
+
+impl<E: Pairing, QAP: R1CSToQAP, D: EvaluationDomain<E::ScalarField>> Groth16<E, QAP, D> {
     /// Create randomizer
     #[inline]
     pub fn create_randomizer(pk: &ProvingKey<E>, rng: &mut impl Rng) -> R1CSResult<Randomizer<E>> {
-        let r = E::ScalarField::rand(rng);
-        let s = E::ScalarField::rand(rng);
+        let r = SecretScalar::new(E::ScalarField::rand(rng));
+        let s = SecretScalar::new(E::ScalarField::rand(rng));
+
+        Ok(Self::randomizer_from_scalars(pk, r, s))
+    }
+
+    /// Create a randomizer together with the group elements the prover
+    /// derives from it, for proving many witnesses under it.
+    pub fn create_prepared_randomizer(
+        pk: &ProvingKey<E>,
+        rng: &mut impl Rng,
+    ) -> R1CSResult<PreparedProverRandomizer<E>> {
+        let rnd = Self::create_randomizer(pk, rng)?;
+        Self::prepare_randomizer(pk, &rnd)
+    }
+
+    /// Precompute the group elements the prover derives from `rnd` under the
+    /// proving key `pk`.
+    ///
+    /// Fails on a zero `r` or `s`, e.g. of a randomizer made from a
+    /// [`PublicRandomizer`](crate::PublicRandomizer), under which proofs
+    /// would not verify.
+    pub fn prepare_randomizer<S: ProvingKeySource<E>>(
+        pk: &S,
+        rnd: &Randomizer<E>,
+    ) -> R1CSResult<PreparedProverRandomizer<E>> {
+        if rnd.r.expose().is_zero() || rnd.s.expose().is_zero() {
+            return Err(SynthesisError::UnexpectedIdentity);
+        }
+        let delta_g1 = pk.delta_g1();
+        Ok(PreparedProverRandomizer {
+            rnd: rnd.clone(),
+            r_delta_g1: (delta_g1 * rnd.r.expose()).into_affine(),
+            s_delta_g1: (delta_g1 * rnd.s.expose()).into_affine(),
+            s_delta_g2: (pk.vk().delta_g2 * rnd.s.expose()).into_affine(),
+        })
+    }
 
-        let r_s_delta_g1 = pk.delta_g1 * (r * s);
-        let r_sub_s_alpha_g1 = pk.vk.alpha_g1 * (r - s);
+    pub(crate) fn randomizer_from_scalars<S: ProvingKeySource<E>>(
+        pk: &S,
+        r: SecretScalar<E::ScalarField>,
+        s: SecretScalar<E::ScalarField>,
+    ) -> Randomizer<E> {
+        let r_s = SecretScalar::new(*r.expose() * s.expose());
+        let r_sub_s = SecretScalar::new(*r.expose() - s.expose());
+        let r_s_delta_g1 = pk.delta_g1() * r_s.expose();
+        let r_sub_s_alpha_g1 = pk.vk().alpha_g1 * r_sub_s.expose();
 
-        Ok(Randomizer {
+        Randomizer {
             r,
             s,
             p: r_s_delta_g1.into_affine(),
             q: r_sub_s_alpha_g1.into_affine(),
-        })
+        }
     }
 
     /// Create a Groth16 proof using randomness `r` and `s` and the provided
@@ -42,15 +99,167 @@ impl<E: Pairing, QAP: R1CSToQAP> Groth16<E, QAP> {
     pub fn create_proof_with_reduction<C>(
         circuit: C,
         pk: &ProvingKey<E>,
-        r: E::ScalarField,
-        s: E::ScalarField,
+        r: SecretScalar<E::ScalarField>,
+        s: SecretScalar<E::ScalarField>,
+    ) -> R1CSResult<Proof<E>>
+    where
+        E: Pairing,
+        C: ConstraintSynthesizer<E::ScalarField>,
+        QAP: R1CSToQAP,
+    {
+        let rnd = Self::prepare_randomizer(pk, &Self::randomizer_from_scalars(pk, r, s))?;
+        Self::create_proof_with_prepared_randomizer(circuit, pk, &rnd)
+    }
+
+    /// Create a Groth16 proof under the prepared randomizer `rnd`, using the
+    /// provided R1CS-to-QAP reduction.
+    ///
+    /// Fails with `SynthesisError::MalformedVerifyingKey` if `pk` commits to
+    /// some witnesses, whose proofs are made by
+    /// [`create_committed_proof`](Self::create_committed_proof).
+    #[inline]
+    pub fn create_proof_with_prepared_randomizer<C>(
+        circuit: C,
+        pk: &ProvingKey<E>,
+        rnd: &PreparedProverRandomizer<E>,
     ) -> R1CSResult<Proof<E>>
     where
         E: Pairing,
         C: ConstraintSynthesizer<E::ScalarField>,
         QAP: R1CSToQAP,
     {
+        Self::check_no_commitment(pk)?;
         let prover_time = start_timer!(|| "Groth16::Prover");
+        let (h, instance_assignment, witness_assignment) = Self::synthesize::<C>(circuit)?;
+        let proof = Self::create_proof_with_assignment(
+            pk,
+            rnd,
+            &h,
+            &instance_assignment[1..],
+            &witness_assignment,
+        )?;
+        end_timer!(prover_time);
+
+        Ok(proof)
+    }
+
+    /// Create a Groth16 proof under the prepared randomizer `rnd` with a key
+    /// `pk` that commits to some witnesses, using the provided R1CS-to-QAP
+    /// reduction.
+    ///
+    /// The commitment `D` is blinded by
+    /// [`commitment_blinding`](Self::commitment_blinding), so that it hides
+    /// the committed witnesses from anyone without the secrets of `rnd`.
+    /// Fails with `SynthesisError::MalformedVerifyingKey` if `pk` commits to
+    /// no witness.
+    #[inline]
+    pub fn create_committed_proof<C>(
+        circuit: C,
+        pk: &ProvingKey<E>,
+        rnd: &PreparedProverRandomizer<E>,
+    ) -> R1CSResult<CommittedProof<E>>
+    where
+        E: Pairing,
+        C: ConstraintSynthesizer<E::ScalarField>,
+        QAP: R1CSToQAP,
+    {
+        Self::create_committed_proof_with_optional_blinding(circuit, pk, rnd, None)
+    }
+
+    /// Create a Groth16 proof under the prepared randomizer `rnd` whose
+    /// commitment `D` to the committed witnesses of `pk` is blinded by
+    /// `blinding`, using the provided R1CS-to-QAP reduction.
+    ///
+    /// A zero `blinding` opts into unblinded commitments: `D` then only
+    /// depends on the committed witnesses, so that
+    /// [`compare_proofs_by_commitment`](Self::compare_proofs_by_commitment)
+    /// detects them across randomizers, but anyone can test a guess of them.
+    pub fn create_committed_proof_with_blinding<C>(
+        circuit: C,
+        pk: &ProvingKey<E>,
+        rnd: &PreparedProverRandomizer<E>,
+        blinding: E::ScalarField,
+    ) -> R1CSResult<CommittedProof<E>>
+    where
+        E: Pairing,
+        C: ConstraintSynthesizer<E::ScalarField>,
+        QAP: R1CSToQAP,
+    {
+        Self::create_committed_proof_with_optional_blinding(circuit, pk, rnd, Some(blinding))
+    }
+
+    /// The blinding of the commitment `D` of a proof under `rnd` to the
+    /// committed witnesses `values`, unless another one is given.
+    ///
+    /// It is hashed from `r`, `s` and `values`, so that proving stays
+    /// deterministic while `D` hides `values` from anyone who only holds `P`
+    /// and `Q`. The prover needs it to open `D` with
+    /// [`verify_commitment`](Self::verify_commitment).
+    pub fn commitment_blinding(rnd: &Randomizer<E>, values: &[E::ScalarField]) -> E::ScalarField {
+        let mut message = Vec::new();
+        rnd.r
+            .expose()
+            .serialize_compressed(&mut message)
+            .and_then(|_| rnd.s.expose().serialize_compressed(&mut message))
+            .and_then(|_| values.serialize_compressed(&mut message))
+            .expect("serializing into a vector cannot fail");
+        let mut blinding: Vec<E::ScalarField> = <DefaultFieldHasher<Sha256> as HashToField<
+            E::ScalarField,
+        >>::new(COMMITMENT_BLINDING_DOMAIN)
+        .hash_to_field(&message, 1);
+        message.zeroize();
+        let result = blinding[0];
+        blinding.zeroize();
+        result
+    }
+
+    /// Create a Groth16 proof under `rnd` whose commitment `D` is blinded by
+    /// `blinding`, or by [`commitment_blinding`](Self::commitment_blinding)
+    /// if it is `None`.
+    fn create_committed_proof_with_optional_blinding<C>(
+        circuit: C,
+        pk: &ProvingKey<E>,
+        rnd: &PreparedProverRandomizer<E>,
+        blinding: Option<E::ScalarField>,
+    ) -> R1CSResult<CommittedProof<E>>
+    where
+        E: Pairing,
+        C: ConstraintSynthesizer<E::ScalarField>,
+        QAP: R1CSToQAP,
+    {
+        if pk.committed_witnesses.is_empty() {
+            return Err(SynthesisError::MalformedVerifyingKey);
+        }
+        let prover_time = start_timer!(|| "Groth16::Prover with commitment");
+        let (h, instance_assignment, witness_assignment) = Self::synthesize::<C>(circuit)?;
+        let proof = Self::accumulate_proof(
+            pk,
+            &ProverConfig::UNBOUNDED,
+            rnd,
+            &h,
+            &instance_assignment[1..],
+            &witness_assignment,
+        )?;
+        let proof = Self::commit_witnesses(pk, proof, rnd, &witness_assignment, blinding)?;
+        end_timer!(prover_time);
+
+        Ok(proof)
+    }
+
+    /// Synthesizes `circuit` and returns the QAP witness along with the
+    /// instance and witness assignments.
+    #[allow(clippy::type_complexity)]
+    pub(crate) fn synthesize<C>(
+        circuit: C,
+    ) -> R1CSResult<(
+        Vec<E::ScalarField>,
+        Vec<E::ScalarField>,
+        Vec<E::ScalarField>,
+    )>
+    where
+        C: ConstraintSynthesizer<E::ScalarField>,
+        QAP: R1CSToQAP,
+    {
         let cs = ConstraintSystem::new_ref();
 
         // Set the optimization goal
@@ -67,17 +276,481 @@ impl<E: Pairing, QAP: R1CSToQAP> Groth16<E, QAP> {
         end_timer!(lc_time);
 
         let witness_map_time = start_timer!(|| "R1CS to QAP witness map");
-        let h = QAP::witness_map::<E::ScalarField, D<E::ScalarField>>(cs.clone())?;
+        let h = QAP::witness_map::<E::ScalarField, D>(cs.clone())?;
         end_timer!(witness_map_time);
 
-        let prover = cs.borrow().unwrap();
-        let proof = Self::create_proof_with_assignment(
+        let prover = cs.into_inner().ok_or(SynthesisError::MissingCS)?;
+        Ok((h, prover.instance_assignment, prover.witness_assignment))
+    }
+
+    /// Create a Groth16 proof under the prepared randomizer `rnd` against a
+    /// prover index. The circuit only generates its assignment: constraints are not
+    /// constructed, and the QAP witness map runs over the matrices and domain
+    /// stored in `index`. The assignment is checked against those matrices,
+    /// and `SynthesisError::Unsatisfiable` is returned if it violates a
+    /// constraint.
+    pub fn create_proof_with_index<C>(
+        circuit: C,
+        index: &ProverIndex<E, D>,
+        rnd: &PreparedProverRandomizer<E>,
+    ) -> R1CSResult<Proof<E>>
+    where
+        E: Pairing,
+        C: ConstraintSynthesizer<E::ScalarField>,
+        QAP: R1CSToQAP,
+    {
+        let prover_time = start_timer!(|| "Groth16::Prover with index");
+        let cs = ConstraintSystem::new_ref();
+        cs.set_optimization_goal(OptimizationGoal::Constraints);
+        cs.set_mode(SynthesisMode::Prove {
+            construct_matrices: false,
+        });
+
+        let synthesis_time = start_timer!(|| "Witness generation");
+        circuit.generate_constraints(cs.clone())?;
+        end_timer!(synthesis_time);
+
+        let prover = cs.into_inner().ok_or(SynthesisError::MissingCS)?;
+        let proof = Self::create_proof_with_index_and_assignment(
+            index,
+            rnd,
+            &prover.instance_assignment,
+            &prover.witness_assignment,
+        )?;
+
+        end_timer!(prover_time);
+
+        Ok(proof)
+    }
+
+    /// Create a Groth16 proof under `rnd` from a full assignment computed
+    /// outside of Rust, e.g. read through [`crate::witness::Assignment`].
+    ///
+    /// `instance` starts with the constant `one` followed by the public inputs,
+    /// and `witness` holds the private values, exactly as the
+    /// `instance_assignment` and `witness_assignment` of a `ConstraintSystem`.
+    /// The assignment is checked against the constraint matrices of `index`
+    /// before proving, and `SynthesisError::Unsatisfiable` is returned if it
+    /// has the wrong shape or violates a constraint.
+    pub fn prove_from_assignment(
+        index: &ProverIndex<E, D>,
+        instance: &[E::ScalarField],
+        witness: &[E::ScalarField],
+        rnd: &PreparedProverRandomizer<E>,
+    ) -> R1CSResult<Proof<E>> {
+        Self::create_proof_with_index_and_assignment(index, rnd, instance, witness)
+    }
+
+    /// Create a Groth16 proof under `rnd` from a full assignment, as
+    /// [`Self::prove_from_assignment`] does, and return it along with a
+    /// [`ProofCache`] from which [`Self::reprove_with_diff`] proves a slightly
+    /// changed assignment.
+    pub fn create_proof_with_cache(
+        index: &ProverIndex<E, D>,
+        instance: &[E::ScalarField],
+        witness: &[E::ScalarField],
+        rnd: &PreparedProverRandomizer<E>,
+    ) -> R1CSResult<(Proof<E>, ProofCache<E>)> {
+        Self::check_no_commitment(&index.pk)?;
+        if instance.len() != index.num_instance_variables() {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let assignment = [instance, witness].concat();
+        Self::check_full_assignment(index, &assignment)?;
+
+        let pk = &index.pk;
+        let config = &ProverConfig::UNBOUNDED;
+        let acc_time = start_timer!(|| "Compute accumulators");
+        let a_acc = Self::chunked_msm::<E::G1, _>(
+            config,
+            pk.a_query.len(),
+            |range| pk.g1_query(Query::A, range),
+            pk.a_query_density.select(&assignment),
+        )?;
+        let b_g1_acc = Self::chunked_msm::<E::G1, _>(
+            config,
+            pk.b_g1_query.len(),
+            |range| pk.g1_query(Query::BG1, range),
+            pk.b_query_density.select(&assignment),
+        )?;
+        let b_g2_acc = Self::chunked_msm::<E::G2, _>(
+            config,
+            pk.b_g2_query.len(),
+            |range| pk.b_g2_query(range),
+            pk.b_query_density.select(&assignment),
+        )?;
+        let l_acc = Self::chunked_msm::<E::G1, _>(
+            config,
+            pk.l_query.len(),
+            |range| pk.g1_query(Query::L, range),
+            &assignment[index.num_instance_variables()..],
+        )?;
+        end_timer!(acc_time);
+
+        let cache = ProofCache {
+            assignment,
+            a_acc,
+            b_g1_acc,
+            b_g2_acc,
+            l_acc,
+        };
+        let proof = Self::create_proof_with_cached_accumulators(index, rnd, &cache)?;
+
+        Ok((proof, cache))
+    }
+
+    /// Create a Groth16 proof under `rnd` of the assignment cached in `cache`
+    /// after applying the sparse `diff`, and return it along with the updated
+    /// cache.
+    ///
+    /// `diff` lists `(variable, value)` pairs indexed as the full assignment,
+    /// so that the public inputs start at `1` and the witness at
+    /// `index.num_instance_variables()`; later pairs override earlier ones. The
+    /// `A`, `B` and `L` accumulators are updated with MSMs over the changed
+    /// variables only, while the witness map and the `H` MSM run in full.
+    /// `SynthesisError::Unsatisfiable` is returned if `diff` touches the
+    /// constant `one` or a missing variable, or if the new assignment violates
+    /// a constraint.
+    pub fn reprove_with_diff(
+        index: &ProverIndex<E, D>,
+        rnd: &PreparedProverRandomizer<E>,
+        cache: &ProofCache<E>,
+        diff: &[(usize, E::ScalarField)],
+    ) -> R1CSResult<(Proof<E>, ProofCache<E>)> {
+        Self::check_no_commitment(&index.pk)?;
+        let mut assignment = cache.assignment.clone();
+        let mut changes = Vec::with_capacity(diff.len());
+        for &(i, value) in diff {
+            if i == 0 || i >= assignment.len() {
+                return Err(SynthesisError::Unsatisfiable);
+            }
+            let delta = value - assignment[i];
+            if !delta.is_zero() {
+                assignment[i] = value;
+                changes.push((i, delta));
+            }
+        }
+        Self::check_full_assignment(index, &assignment)?;
+
+        let pk = &index.pk;
+        let num_instance_variables = index.num_instance_variables();
+        let acc_time = start_timer!(|| "Update accumulators");
+        let cache = ProofCache {
+            a_acc: cache.a_acc
+                + Self::sparse_msm::<E::G1>(&changes, |i| {
+                    Self::query_base(&pk.a_query, &pk.a_query_density, i)
+                })?,
+            b_g1_acc: cache.b_g1_acc
+                + Self::sparse_msm::<E::G1>(&changes, |i| {
+                    Self::query_base(&pk.b_g1_query, &pk.b_query_density, i)
+                })?,
+            b_g2_acc: cache.b_g2_acc
+                + Self::sparse_msm::<E::G2>(&changes, |i| {
+                    Self::query_base(&pk.b_g2_query, &pk.b_query_density, i)
+                })?,
+            l_acc: cache.l_acc
+                + Self::sparse_msm::<E::G1>(&changes, |i| {
+                    match i.checked_sub(num_instance_variables) {
+                        Some(j) => pk
+                            .l_query
+                            .get(j)
+                            .map(|base| Some(*base))
+                            .ok_or(SynthesisError::MalformedVerifyingKey),
+                        None => Ok(None),
+                    }
+                })?,
+            assignment,
+        };
+        end_timer!(acc_time);
+
+        let proof = Self::create_proof_with_cached_accumulators(index, rnd, &cache)?;
+
+        Ok((proof, cache))
+    }
+
+    /// Checks that `full_assignment` has the shape of the circuit of `index`,
+    /// starts with the constant `one`, and satisfies every constraint.
+    fn check_full_assignment(
+        index: &ProverIndex<E, D>,
+        full_assignment: &[E::ScalarField],
+    ) -> R1CSResult<()> {
+        let check_time = start_timer!(|| "Check assignment");
+        let num_variables = index.num_instance_variables() + index.num_witness_variables();
+        let satisfied = full_assignment.len() == num_variables
+            && full_assignment.first().is_some_and(One::is_one)
+            && index.which_is_unsatisfied(full_assignment).is_none();
+        end_timer!(check_time);
+        if satisfied {
+            Ok(())
+        } else {
+            Err(SynthesisError::Unsatisfiable)
+        }
+    }
+
+    /// Finishes a proof from the accumulators in `cache`, computing `H` from
+    /// its assignment.
+    fn create_proof_with_cached_accumulators(
+        index: &ProverIndex<E, D>,
+        rnd: &PreparedProverRandomizer<E>,
+        cache: &ProofCache<E>,
+    ) -> R1CSResult<Proof<E>> {
+        let witness_map_time = start_timer!(|| "R1CS to QAP witness map");
+        let h = QAP::witness_map_from_matrices_with_domain::<E::ScalarField, _>(
+            &index.domain,
+            &index.matrices,
+            index.num_instance_variables(),
+            index.num_constraints(),
+            &cache.assignment,
+        )?;
+        end_timer!(witness_map_time);
+
+        let h_acc = Self::chunked_msm::<E::G1, _>(
+            &ProverConfig::UNBOUNDED,
+            index.pk.h_query.len(),
+            |range| index.pk.g1_query(Query::H, range),
+            h.iter().take(index.pk.h_query.len()),
+        )?;
+
+        Ok(Self::finish_proof(
+            &index.pk,
+            rnd,
+            cache.a_acc,
+            cache.b_g1_acc,
+            cache.b_g2_acc,
+            cache.l_acc + h_acc,
+        ))
+    }
+
+    /// Create a Groth16 proof against a prover index from a raw assignment,
+    /// where `instance_assignment` starts with the constant `one`. Fails with
+    /// `SynthesisError::Unsatisfiable` if the assignment has the wrong shape
+    /// or violates a constraint.
+    fn create_proof_with_index_and_assignment(
+        index: &ProverIndex<E, D>,
+        rnd: &PreparedProverRandomizer<E>,
+        instance_assignment: &[E::ScalarField],
+        witness_assignment: &[E::ScalarField],
+    ) -> R1CSResult<Proof<E>> {
+        if instance_assignment.len() != index.num_instance_variables() {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let full_assignment = [instance_assignment, witness_assignment].concat();
+        Self::check_full_assignment(index, &full_assignment)?;
+
+        let witness_map_time = start_timer!(|| "R1CS to QAP witness map");
+        let h = QAP::witness_map_from_matrices_with_domain::<E::ScalarField, _>(
+            &index.domain,
+            &index.matrices,
+            index.num_instance_variables(),
+            index.num_constraints(),
+            &full_assignment,
+        )?;
+        drop(full_assignment);
+        end_timer!(witness_map_time);
+
+        Self::create_proof_with_assignment(
+            &index.pk,
+            rnd,
+            &h,
+            &instance_assignment[1..],
+            witness_assignment,
+        )
+    }
+
+    /// Create Groth16 proofs for many `circuits` of the same shape under a
+    /// single prepared randomizer `rnd`, using the provided R1CS-to-QAP
+    /// reduction.
+    ///
+    /// Only the first circuit constructs the constraint matrices; the others
+    /// generate their assignments alone, and the evaluation domain is
+    /// constructed once for the whole batch. Witness generation and the
+    /// witness maps run in parallel across circuits. The MSMs of all proofs
+    /// over each query of `pk` share their Pippenger passes, so that every
+    /// base is read once per window for the whole batch; the buckets of each
+    /// window are held for every circuit at once.
+    pub fn create_proofs_with_reduction<C>(
+        circuits: Vec<C>,
+        pk: &ProvingKey<E>,
+        rnd: &PreparedProverRandomizer<E>,
+    ) -> R1CSResult<Vec<Proof<E>>>
+    where
+        E: Pairing,
+        C: ConstraintSynthesizer<E::ScalarField> + Send,
+        QAP: R1CSToQAP,
+    {
+        Self::check_no_commitment(pk)?;
+        let prover_time =
+            start_timer!(|| format!("Groth16::Prover for {} circuits", circuits.len()));
+
+        let synthesis_time = start_timer!(|| "Witness generation");
+        let mut synthesized = cfg_into_iter!(circuits)
+            .enumerate()
+            .map(|(i, circuit)| {
+                let cs = ConstraintSystem::new_ref();
+                cs.set_optimization_goal(OptimizationGoal::Constraints);
+                cs.set_mode(SynthesisMode::Prove {
+                    construct_matrices: i == 0,
+                });
+
+                circuit.generate_constraints(cs.clone())?;
+                cs.finalize();
+
+                let matrices = cs.to_matrices();
+                let prover = cs.into_inner().ok_or(SynthesisError::MissingCS)?;
+                let full_assignment = [
+                    prover.instance_assignment.as_slice(),
+                    prover.witness_assignment.as_slice(),
+                ]
+                .concat();
+
+                Ok((matrices, full_assignment))
+            })
+            .collect::<R1CSResult<Vec<_>>>()?;
+        end_timer!(synthesis_time);
+
+        let matrices = match synthesized.first_mut() {
+            Some((matrices, _)) => matrices.take().ok_or(SynthesisError::MissingCS)?,
+            None => return Ok(Vec::new()),
+        };
+        let num_inputs = matrices.num_instance_variables;
+        let num_constraints = matrices.num_constraints;
+
+        let domain =
+            D::new(num_constraints + num_inputs).ok_or(SynthesisError::PolynomialDegreeTooLarge)?;
+
+        let num_variables = matrices.num_instance_variables + matrices.num_witness_variables;
+        if pk.a_query_density.len() != num_variables || pk.b_query_density.len() != num_variables {
+            return Err(SynthesisError::MalformedVerifyingKey);
+        }
+
+        let witness_map_time = start_timer!(|| "R1CS to QAP witness maps");
+        let hs = cfg_iter!(synthesized)
+            .map(|(_, full_assignment)| {
+                if full_assignment.len() != num_variables {
+                    return Err(SynthesisError::Unsatisfiable);
+                }
+
+                QAP::witness_map_from_matrices_with_domain::<E::ScalarField, _>(
+                    &domain,
+                    &matrices,
+                    num_inputs,
+                    num_constraints,
+                    full_assignment,
+                )
+            })
+            .collect::<R1CSResult<Vec<_>>>()?;
+        end_timer!(witness_map_time);
+
+        let assignments = synthesized
+            .iter()
+            .map(|(_, full_assignment)| full_assignment.as_slice())
+            .collect::<Vec<_>>();
+
+        let c_acc_time = start_timer!(|| "Compute C");
+        let h_accs = Self::batched_msm::<E::G1>(
+            &pk.h_query,
+            hs.iter().map(|h| h.iter().take(pk.h_query.len())),
+        )?;
+        let l_aux_accs =
+            Self::batched_msm::<E::G1>(&pk.l_query, assignments.iter().map(|a| &a[num_inputs..]))?;
+        end_timer!(c_acc_time);
+
+        let a_acc_time = start_timer!(|| "Compute A");
+        let a_accs = Self::batched_msm::<E::G1>(
+            &pk.a_query,
+            assignments.iter().map(|a| pk.a_query_density.select(*a)),
+        )?;
+        end_timer!(a_acc_time);
+
+        let b_g1_accs = if !rnd.rnd.r.expose().is_zero() {
+            let b_g1_acc_time = start_timer!(|| "Compute B in G1");
+            let b_g1_accs = Self::batched_msm::<E::G1>(
+                &pk.b_g1_query,
+                assignments.iter().map(|a| pk.b_query_density.select(*a)),
+            )?;
+            end_timer!(b_g1_acc_time);
+
+            b_g1_accs
+        } else {
+            vec![E::G1::zero(); assignments.len()]
+        };
+
+        let b_g2_acc_time = start_timer!(|| "Compute B in G2");
+        let b_g2_accs = Self::batched_msm::<E::G2>(
+            &pk.b_g2_query,
+            assignments.iter().map(|a| pk.b_query_density.select(*a)),
+        )?;
+        end_timer!(b_g2_acc_time);
+
+        let proofs = a_accs
+            .into_iter()
+            .zip(b_g1_accs)
+            .zip(b_g2_accs)
+            .zip(h_accs.into_iter().zip(l_aux_accs))
+            .map(|(((a_acc, b_g1_acc), b_g2_acc), (h_acc, l_aux_acc))| {
+                Self::finish_proof(pk, rnd, a_acc, b_g1_acc, b_g2_acc, l_aux_acc + h_acc)
+            })
+            .collect::<Vec<_>>();
+
+        end_timer!(prover_time);
+
+        Ok(proofs)
+    }
+
+    /// Create a Groth16 proof under the prepared randomizer `rnd`, reading the
+    /// proving key through `pk` in chunks that fit the memory budget of
+    /// `config`.
+    ///
+    /// The budget only covers the multi-scalar multiplications: their bases
+    /// are read and their scalars converted to integers one chunk at a time.
+    /// With a [`ProvingKeyFile`](crate::streaming::ProvingKeyFile) the proving
+    /// key itself never has to be loaded.
+    ///
+    /// It does not cover the steps before them. Constraint synthesis holds the
+    /// whole constraint system of `circuit`, and the QAP witness map holds
+    /// several vectors of the size of the evaluation domain at once, exactly
+    /// as without a budget; only the assignment and the QAP witness are kept
+    /// for the multiplications. A circuit whose synthesis or witness map does
+    /// not fit in memory cannot be proven with this function.
+    pub fn create_proof_with_budget<C, S>(
+        circuit: C,
+        pk: &S,
+        config: &ProverConfig,
+        rnd: &PreparedProverRandomizer<E>,
+    ) -> Result<Proof<E>, S::Error>
+    where
+        C: ConstraintSynthesizer<E::ScalarField>,
+        S: ProvingKeySource<E>,
+    {
+        let prover_time = start_timer!(|| "Groth16::Prover with memory budget");
+        let cs = ConstraintSystem::new_ref();
+        cs.set_optimization_goal(OptimizationGoal::Constraints);
+
+        let synthesis_time = start_timer!(|| "Constraint synthesis");
+        circuit.generate_constraints(cs.clone())?;
+        debug_assert!(cs.is_satisfied().unwrap());
+        end_timer!(synthesis_time);
+
+        let lc_time = start_timer!(|| "Inlining LCs");
+        cs.finalize();
+        end_timer!(lc_time);
+
+        let witness_map_time = start_timer!(|| "R1CS to QAP witness map");
+        let h = QAP::witness_map::<E::ScalarField, D>(cs.clone())?;
+        end_timer!(witness_map_time);
+
+        // Keep the assignment only, dropping the constraints.
+        let (instance_assignment, witness_assignment) = {
+            let prover = cs.into_inner().ok_or(SynthesisError::MissingCS)?;
+            (prover.instance_assignment, prover.witness_assignment)
+        };
+        let proof = Self::create_proof_with_source(
             pk,
-            r,
-            s,
+            config,
+            rnd,
             &h,
-            &prover.instance_assignment[1..],
-            &prover.witness_assignment,
+            &instance_assignment[1..],
+            &witness_assignment,
         )?;
 
         end_timer!(prover_time);
@@ -88,102 +761,425 @@ impl<E: Pairing, QAP: R1CSToQAP> Groth16<E, QAP> {
     #[inline]
     fn create_proof_with_assignment(
         pk: &ProvingKey<E>,
-        r: E::ScalarField,
-        s: E::ScalarField,
+        rnd: &PreparedProverRandomizer<E>,
         h: &[E::ScalarField],
         input_assignment: &[E::ScalarField],
         aux_assignment: &[E::ScalarField],
     ) -> R1CSResult<Proof<E>> {
-        let c_acc_time = start_timer!(|| "Compute C");
-        let h_assignment = cfg_into_iter!(h)
-            .map(|s| s.into_bigint())
-            .collect::<Vec<_>>();
-        let h_acc = E::G1::msm_bigint(&pk.h_query, &h_assignment[..h_assignment.len() - 1]);
-        drop(h_assignment);
+        Self::create_proof_with_source(
+            pk,
+            &ProverConfig::UNBOUNDED,
+            rnd,
+            h,
+            input_assignment,
+            aux_assignment,
+        )
+    }
 
-        // Compute C
-        let aux_assignment = cfg_iter!(aux_assignment)
-            .map(|s| s.into_bigint())
-            .collect::<Vec<_>>();
+    /// Create a Groth16 proof under `rnd` from the QAP witness `h` and the
+    /// assignment, reading the proving key through `pk`. Fails if `pk`
+    /// commits to some witnesses.
+    fn create_proof_with_source<S: ProvingKeySource<E>>(
+        pk: &S,
+        config: &ProverConfig,
+        rnd: &PreparedProverRandomizer<E>,
+        h: &[E::ScalarField],
+        input_assignment: &[E::ScalarField],
+        aux_assignment: &[E::ScalarField],
+    ) -> Result<Proof<E>, S::Error> {
+        Self::check_no_commitment(pk)?;
+        Self::accumulate_proof(pk, config, rnd, h, input_assignment, aux_assignment)
+    }
 
-        let l_aux_acc = E::G1::msm_bigint(&pk.l_query, &aux_assignment);
+    /// Computes the MSMs of a proof under `rnd` and finishes it, ignoring the
+    /// committed witnesses of `pk`.
+    fn accumulate_proof<S: ProvingKeySource<E>>(
+        pk: &S,
+        config: &ProverConfig,
+        rnd: &PreparedProverRandomizer<E>,
+        h: &[E::ScalarField],
+        input_assignment: &[E::ScalarField],
+        aux_assignment: &[E::ScalarField],
+    ) -> Result<Proof<E>, S::Error> {
+        let acc = Self::accumulate_msms(
+            pk,
+            config,
+            !rnd.rnd.r.expose().is_zero(),
+            h,
+            input_assignment,
+            aux_assignment,
+        )?;
+        Ok(Self::finish_proof(
+            pk, rnd, acc.a, acc.b_g1, acc.b_g2, acc.c,
+        ))
+    }
 
-        let r_sub_s_alpha_g1 = pk.vk.alpha_g1 * (r - s);
+    /// Computes the MSMs of `A`, `B` and `C` of a proof, skipping `B` in
+    /// `E::G1` unless `with_b_g1`, as only `r * B` needs it.
+    pub(crate) fn accumulate_msms<S: ProvingKeySource<E>>(
+        pk: &S,
+        config: &ProverConfig,
+        with_b_g1: bool,
+        h: &[E::ScalarField],
+        input_assignment: &[E::ScalarField],
+        aux_assignment: &[E::ScalarField],
+    ) -> Result<ProofAccumulators<E>, S::Error> {
+        let num_variables = 1 + input_assignment.len() + aux_assignment.len();
+        if pk.a_query_density().len() != num_variables
+            || pk.b_query_density().len() != num_variables
+        {
+            return Err(SynthesisError::MalformedVerifyingKey.into());
+        }
 
-        end_timer!(c_acc_time);
+        // The full assignment, starting with the constant `one`.
+        let one = E::ScalarField::one();
+        let assignment = || {
+            iter::once(&one)
+                .chain(input_assignment)
+                .chain(aux_assignment)
+        };
 
-        let input_assignment = input_assignment
-            .iter()
-            .map(|s| s.into_bigint())
-            .collect::<Vec<_>>();
+        let c_acc_time = start_timer!(|| "Compute C");
+        let h_acc = Self::chunked_msm::<E::G1, _>(
+            config,
+            pk.query_len(Query::H),
+            |range| pk.g1_query(Query::H, range),
+            // The reduction fixes how many of the coefficients of `h` the
+            // key has bases for.
+            h.iter().take(pk.query_len(Query::H)),
+        )?;
 
-        let assignment = [&input_assignment[..], &aux_assignment[..]].concat();
-        drop(aux_assignment);
+        let l_aux_acc = Self::chunked_msm::<E::G1, _>(
+            config,
+            pk.query_len(Query::L),
+            |range| pk.g1_query(Query::L, range),
+            aux_assignment,
+        )?;
+
+        end_timer!(c_acc_time);
 
         // Compute A
         let a_acc_time = start_timer!(|| "Compute A");
-        let r_g1 = pk.delta_g1.mul(r);
-
-        let g_a = Self::calculate_coeff(r_g1, &pk.a_query, pk.vk.alpha_g1, &assignment);
-
-        let s_g_a = g_a.mul_bigint(&s.into_bigint());
+        let a_acc = Self::chunked_msm::<E::G1, _>(
+            config,
+            pk.query_len(Query::A),
+            |range| pk.g1_query(Query::A, range),
+            pk.a_query_density().select(assignment()),
+        )?;
         end_timer!(a_acc_time);
 
         // Compute B in G1 if needed
-        let g1_b = if !r.is_zero() {
+        let b_g1_acc = if with_b_g1 {
             let b_g1_acc_time = start_timer!(|| "Compute B in G1");
-            let s_g1 = pk.delta_g1.mul(s);
-            let g1_b = Self::calculate_coeff(s_g1, &pk.b_g1_query, pk.beta_g1, &assignment);
-
+            let b_g1_acc = Self::chunked_msm::<E::G1, _>(
+                config,
+                pk.query_len(Query::BG1),
+                |range| pk.g1_query(Query::BG1, range),
+                pk.b_query_density().select(assignment()),
+            )?;
             end_timer!(b_g1_acc_time);
 
-            g1_b
+            b_g1_acc
         } else {
             E::G1::zero()
         };
 
         // Compute B in G2
         let b_g2_acc_time = start_timer!(|| "Compute B in G2");
-        let s_g2 = pk.vk.delta_g2.mul(s);
-        let g2_b = Self::calculate_coeff(s_g2, &pk.b_g2_query, pk.vk.beta_g2, &assignment);
-        let r_g1_b = g1_b.mul_bigint(&r.into_bigint());
-        drop(assignment);
-
+        let b_g2_acc = Self::chunked_msm::<E::G2, _>(
+            config,
+            pk.query_len(Query::BG2),
+            |range| pk.b_g2_query(range),
+            pk.b_query_density().select(assignment()),
+        )?;
         end_timer!(b_g2_acc_time);
 
+        Ok(ProofAccumulators {
+            a: a_acc,
+            b_g1: b_g1_acc,
+            b_g2: b_g2_acc,
+            c: l_aux_acc + h_acc,
+        })
+    }
+
+    /// Adds the randomizer and the key elements to the accumulated MSMs of
+    /// `A`, `B` and `C`, where `c_acc` sums the `L` and `H` contributions.
+    fn finish_proof<S: ProvingKeySource<E>>(
+        pk: &S,
+        rnd: &PreparedProverRandomizer<E>,
+        a_acc: E::G1,
+        b_g1_acc: E::G1,
+        b_g2_acc: E::G2,
+        c_acc: E::G1,
+    ) -> Proof<E> {
+        // The secrets are only copied as integers, which are zeroed below.
+        let mut r = rnd.rnd.r.expose().into_bigint();
+        let mut s = rnd.rnd.s.expose().into_bigint();
+
+        let mut g_a = rnd.r_delta_g1.into_group();
+        g_a += a_acc;
+        g_a += pk.vk().alpha_g1;
+        let s_g_a = g_a.mul_bigint(&s);
+
+        let g1_b = if !rnd.rnd.r.expose().is_zero() {
+            let mut g1_b = rnd.s_delta_g1.into_group();
+            g1_b += b_g1_acc;
+            g1_b += pk.beta_g1();
+            g1_b
+        } else {
+            E::G1::zero()
+        };
+
+        let mut g2_b = rnd.s_delta_g2.into_group();
+        g2_b += b_g2_acc;
+        g2_b += pk.vk().beta_g2;
+        let r_g1_b = g1_b.mul_bigint(&r);
+        r.zeroize();
+        s.zeroize();
+
         let c_time = start_timer!(|| "Finish C");
         let mut g_c = s_g_a;
         g_c += &r_g1_b;
         // g_c -= &r_s_delta_g1;
-        g_c += &r_sub_s_alpha_g1;
-        g_c += &l_aux_acc;
-        g_c += &h_acc;
+        g_c += &rnd.rnd.q;
+        g_c += &c_acc;
         end_timer!(c_time);
 
-        Ok(Proof {
+        Proof {
             a: g_a.into_affine(),
             b: g2_b.into_affine(),
             c: g_c.into_affine(),
-        })
+        }
     }
 
-    fn calculate_coeff<G: AffineRepr>(
-        initial: G::Group,
-        query: &[G],
-        vk_param: G,
-        assignment: &[<G::ScalarField as PrimeField>::BigInt],
-    ) -> G::Group
-    where
-        G::Group: VariableBaseMSM<MulBase = G>,
-    {
-        let el = query[0];
-        let acc = G::Group::msm_bigint(&query[1..], assignment);
+    /// Commits to the committed witnesses of `pk` in `proof`, blinded by
+    /// `blinding`, or by the one derived from `rnd` if it is `None`, along
+    /// with the proof of knowledge of the opening, and cancels the blinding
+    /// in `C`.
+    fn commit_witnesses<S: ProvingKeySource<E>>(
+        pk: &S,
+        mut proof: Proof<E>,
+        rnd: &PreparedProverRandomizer<E>,
+        aux_assignment: &[E::ScalarField],
+        blinding: Option<E::ScalarField>,
+    ) -> Result<CommittedProof<E>, S::Error> {
+        let vk = pk.vk();
+        let mut values = pk
+            .committed_witnesses()
+            .iter()
+            .map(|&i| aux_assignment.get(i).copied())
+            .collect::<Option<Vec<_>>>()
+            .ok_or(SynthesisError::MalformedVerifyingKey)?;
+        if values.len() != vk.commitment_bases_g1.len() {
+            return Err(SynthesisError::MalformedVerifyingKey.into());
+        }
+        let mut blinding = blinding.unwrap_or_else(|| Self::commitment_blinding(&rnd.rnd, &values));
+
+        let commit_time = start_timer!(|| "Commit to witnesses");
+        let commit = |values: &[E::ScalarField], blinding: E::ScalarField| {
+            let mut commitment = E::G1::msm_unchecked(&vk.commitment_bases_g1, values);
+            commitment += vk.eta_gamma_inv_g1 * blinding;
+            commitment.into_affine()
+        };
+        let d = commit(&values, blinding);
+        if !blinding.is_zero() {
+            proof.c = (proof.c.into_group() - pk.eta_delta_inv_g1() * blinding).into_affine();
+        }
+
+        // The nonces are derived from the opening and the proof, so that
+        // proving stays deterministic without reusing a nonce for another
+        // challenge.
+        let mut message = Vec::new();
+        values
+            .serialize_compressed(&mut message)
+            .and_then(|_| blinding.serialize_compressed(&mut message))
+            .and_then(|_| proof.a.serialize_compressed(&mut message))
+            .and_then(|_| proof.b.serialize_compressed(&mut message))
+            .and_then(|_| proof.c.serialize_compressed(&mut message))
+            .expect("serializing into a vector cannot fail");
+        let mut nonces =
+            <DefaultFieldHasher<Sha256> as HashToField<E::ScalarField>>::new(OPENING_NONCE_DOMAIN)
+                .hash_to_field(&message, values.len() + 1);
+        message.zeroize();
+        let mut blinding_nonce = nonces.pop().unwrap();
+        let t = commit(&nonces, blinding_nonce);
 
-        let mut res = initial;
-        res.add_assign(&el);
-        res += &acc;
-        res.add_assign(&vk_param);
+        let challenge = CommitmentOpeningProof::challenge(vk, &proof.a, &proof.b, &proof.c, &d, &t);
+        let responses = nonces
+            .iter()
+            .zip(&values)
+            .map(|(nonce, value)| *nonce + challenge * value)
+            .collect();
+        let opening = CommitmentOpeningProof {
+            t,
+            responses,
+            blinding_response: blinding_nonce + challenge * blinding,
+        };
+        values.zeroize();
+        nonces.zeroize();
+        blinding.zeroize();
+        blinding_nonce.zeroize();
+        end_timer!(commit_time);
+
+        Ok(CommittedProof { proof, d, opening })
+    }
+
+    /// Fails with `SynthesisError::MalformedVerifyingKey` if `pk` commits to
+    /// some witnesses, as a plain [`Proof`] cannot carry their commitment.
+    pub(crate) fn check_no_commitment<S: ProvingKeySource<E>>(pk: &S) -> R1CSResult<()> {
+        if pk.committed_witnesses().is_empty() {
+            Ok(())
+        } else {
+            Err(SynthesisError::MalformedVerifyingKey)
+        }
+    }
+
+    /// Returns the base of `query` for the variable `i`, or `None` if
+    /// `density` does not set it. Fails if a malformed key lacks the base.
+    fn query_base<A: Copy>(query: &[A], density: &QueryDensity, i: usize) -> R1CSResult<Option<A>> {
+        if !density.get(i) {
+            return Ok(None);
+        }
+        query
+            .get(density.rank(i))
+            .map(|base| Some(*base))
+            .ok_or(SynthesisError::MalformedVerifyingKey)
+    }
+
+    /// Computes the multi-scalar multiplication of the `(variable, scalar)`
+    /// pairs in `changes` whose variable has a base, as returned by `base`.
+    fn sparse_msm<G: CurveGroup>(
+        changes: &[(usize, G::ScalarField)],
+        base: impl Fn(usize) -> R1CSResult<Option<G::Affine>>,
+    ) -> R1CSResult<G> {
+        let mut bases = Vec::with_capacity(changes.len());
+        let mut scalars = Vec::with_capacity(changes.len());
+        for &(i, scalar) in changes {
+            if let Some(base) = base(i)? {
+                bases.push(base);
+                scalars.push(scalar);
+            }
+        }
+        Ok(G::msm_unchecked(&bases, &scalars))
+    }
+
+    /// Computes the multi-scalar multiplication of the `len` bases read
+    /// through `read` with `scalars`, holding one chunk of bases and scalars
+    /// at a time. Fails if there are not exactly `len` scalars.
+    fn chunked_msm<'a, G: CurveGroup, Err: From<SynthesisError>>(
+        config: &ProverConfig,
+        len: usize,
+        mut read: impl FnMut(Range<usize>) -> Result<Cow<'a, [G::Affine]>, Err>,
+        scalars: impl IntoIterator<Item = &'a G::ScalarField>,
+    ) -> Result<G, Err> {
+        let chunk_len = config.chunk_len::<G::Affine>();
+        let mut scalars = scalars.into_iter();
+        let mut chunk = Vec::with_capacity(chunk_len.min(len));
+        let mut acc = G::zero();
+        let mut start = 0;
+        loop {
+            chunk.clear();
+            chunk.extend(scalars.by_ref().take(chunk_len).copied());
+            if chunk.is_empty() {
+                break;
+            }
+            let end = start + chunk.len();
+            if end > len {
+                return Err(SynthesisError::MalformedVerifyingKey.into());
+            }
+
+            let bigints = cfg_iter!(chunk)
+                .map(|s| s.into_bigint())
+                .collect::<Vec<_>>();
+            let bases = read(start..end)?;
+            acc += G::msm_bigint(&bases, &bigints);
+            start = end;
+        }
+        if start != len {
+            return Err(SynthesisError::MalformedVerifyingKey.into());
+        }
+        Ok(acc)
+    }
+
+    /// Computes the multi-scalar multiplications of `bases` with each of the
+    /// scalar sequences in `scalars` in shared Pippenger passes: every window
+    /// reads each base once and adds it to the buckets of all sequences. Fails
+    /// if a sequence does not hold exactly one scalar per base.
+    fn batched_msm<'a, G: CurveGroup>(
+        bases: &[G::Affine],
+        scalars: impl IntoIterator<Item = impl IntoIterator<Item = &'a G::ScalarField>>,
+    ) -> R1CSResult<Vec<G>> {
+        let bigints = scalars
+            .into_iter()
+            .map(|scalars| {
+                let bigints = scalars
+                    .into_iter()
+                    .map(|s| s.into_bigint())
+                    .collect::<Vec<_>>();
+                if bigints.len() == bases.len() {
+                    Ok(bigints)
+                } else {
+                    Err(SynthesisError::MalformedVerifyingKey)
+                }
+            })
+            .collect::<R1CSResult<Vec<_>>>()?;
+        if bigints.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let c = if bases.len() < 32 {
+            3
+        } else {
+            ark_std::log2(bases.len()) as usize * 69 / 100 + 2
+        };
+        let num_bits = G::ScalarField::MODULUS_BIT_SIZE as usize;
+        let window_starts = (0..num_bits).step_by(c).collect::<Vec<_>>();
+
+        // The sums of every window, for each sequence of scalars.
+        let window_sums = cfg_into_iter!(window_starts)
+            .map(|w_start| {
+                let mut buckets = vec![vec![G::zero(); (1 << c) - 1]; bigints.len()];
+                for (i, base) in bases.iter().enumerate() {
+                    for (buckets, scalars) in buckets.iter_mut().zip(&bigints) {
+                        let mut scalar = scalars[i];
+                        scalar.divn(w_start as u32);
+                        let digit = (scalar.as_ref()[0] % (1 << c)) as usize;
+                        if digit != 0 {
+                            buckets[digit - 1] += base;
+                        }
+                    }
+                }
+
+                buckets
+                    .into_iter()
+                    .map(|buckets| {
+                        let mut sum = G::zero();
+                        let mut running_sum = G::zero();
+                        for bucket in buckets.into_iter().rev() {
+                            running_sum += &bucket;
+                            sum += &running_sum;
+                        }
+                        sum
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
 
-        res
+        Ok((0..bigints.len())
+            .map(|j| {
+                let lowest = window_sums[0][j];
+                window_sums[1..]
+                    .iter()
+                    .rev()
+                    .fold(G::zero(), |mut total, sums| {
+                        total += &sums[j];
+                        for _ in 0..c {
+                            total.double_in_place();
+                        }
+                        total
+                    })
+                    + lowest
+            })
+            .collect())
     }
 }
@@ -87,6 +87,27 @@ pub trait R1CSToQAP {
         full_assignment: &[F],
     ) -> R1CSResult<Vec<F>>;
 
+    /// Computes a QAP witness corresponding to the R1CS witness defined by `cs`
+    /// over an already constructed evaluation `domain`, so that callers mapping
+    /// many assignments of the same circuit build the domain only once. The
+    /// default implementation ignores `domain` and calls
+    /// [`R1CSToQAP::witness_map_from_matrices`].
+    #[inline]
+    fn witness_map_from_matrices_with_domain<F: PrimeField, D: EvaluationDomain<F>>(
+        _domain: &D,
+        matrices: &ConstraintMatrices<F>,
+        num_inputs: usize,
+        num_constraints: usize,
+        full_assignment: &[F],
+    ) -> R1CSResult<Vec<F>> {
+        Self::witness_map_from_matrices::<F, D>(
+            matrices,
+            num_inputs,
+            num_constraints,
+            full_assignment,
+        )
+    }
+
     /// Computes the exponents that the generator uses to calculate base
     /// elements which the prover later uses to compute `h(x)t(x)/delta`.
     fn h_query_scalars<F: PrimeField, D: EvaluationDomain<F>>(
@@ -155,26 +176,33 @@ impl R1CSToQAP for LibsnarkReduction {
     ) -> R1CSResult<Vec<F>> {
         let domain =
             D::new(num_constraints + num_inputs).ok_or(SynthesisError::PolynomialDegreeTooLarge)?;
-        let domain_size = domain.size();
-        let zero = F::zero();
 
-        let mut a = vec![zero; domain_size];
-        let mut b = vec![zero; domain_size];
+        Self::witness_map_from_matrices_with_domain::<F, D>(
+            &domain,
+            matrices,
+            num_inputs,
+            num_constraints,
+            full_assignment,
+        )
+    }
 
-        cfg_iter_mut!(a[..num_constraints])
-            .zip(cfg_iter_mut!(b[..num_constraints]))
-            .zip(cfg_iter!(&matrices.a))
-            .zip(cfg_iter!(&matrices.b))
-            .for_each(|(((a, b), at_i), bt_i)| {
-                *a = evaluate_constraint(&at_i, &full_assignment);
-                *b = evaluate_constraint(&bt_i, &full_assignment);
-            });
+    fn witness_map_from_matrices_with_domain<F: PrimeField, D: EvaluationDomain<F>>(
+        domain: &D,
+        matrices: &ConstraintMatrices<F>,
+        num_inputs: usize,
+        num_constraints: usize,
+        full_assignment: &[F],
+    ) -> R1CSResult<Vec<F>> {
+        let domain_size = domain.size();
+        let zero = F::zero();
 
-        {
-            let start = num_constraints;
-            let end = start + num_inputs;
-            a[start..end].clone_from_slice(&full_assignment[..num_inputs]);
-        }
+        let (mut a, mut b) = evaluate_a_and_b(
+            domain_size,
+            matrices,
+            num_inputs,
+            num_constraints,
+            full_assignment,
+        );
 
         domain.ifft_in_place(&mut a);
         domain.ifft_in_place(&mut b);
@@ -224,3 +252,252 @@ impl R1CSToQAP for LibsnarkReduction {
         Ok(scalars)
     }
 }
+
+/// Evaluates the `A` and `B` matrices of the first `num_constraints`
+/// constraints at `full_assignment`, padded with zeros to `domain_size`. The
+/// `A` evaluations are followed by the `num_inputs` instance values, which
+/// the reductions constrain to ensure the non-malleability of proofs.
+fn evaluate_a_and_b<F: PrimeField>(
+    domain_size: usize,
+    matrices: &ConstraintMatrices<F>,
+    num_inputs: usize,
+    num_constraints: usize,
+    full_assignment: &[F],
+) -> (Vec<F>, Vec<F>) {
+    let zero = F::zero();
+
+    let mut a = vec![zero; domain_size];
+    let mut b = vec![zero; domain_size];
+
+    cfg_iter_mut!(a[..num_constraints])
+        .zip(cfg_iter_mut!(b[..num_constraints]))
+        .zip(cfg_iter!(&matrices.a))
+        .zip(cfg_iter!(&matrices.b))
+        .for_each(|(((a, b), at_i), bt_i)| {
+            *a = evaluate_constraint(&at_i, &full_assignment);
+            *b = evaluate_constraint(&bt_i, &full_assignment);
+        });
+
+    {
+        let start = num_constraints;
+        let end = start + num_inputs;
+        a[start..end].clone_from_slice(&full_assignment[..num_inputs]);
+    }
+
+    (a, b)
+}
+
+/// Computes an R1CS-to-QAP reduction after the one of
+/// [`snarkjs`](https://github.com/iden3/snarkjs/blob/v0.7.0/src/groth16_prove.js).
+///
+/// It is only tested against the other reductions of this crate, not against
+/// keys or proofs produced by snarkjs, so interoperability with circom
+/// tooling is not guaranteed.
+///
+/// Instead of dividing by the vanishing polynomial on a multiplicative coset,
+/// the witness map evaluates `h(x)t(x)` on the coset of the domain by a root
+/// of unity of twice its size, and the `h_query` holds the matching Lagrange
+/// bases divided by `delta`, one per element of the domain.
+pub struct CircomReduction;
+
+impl R1CSToQAP for CircomReduction {
+    #[inline]
+    #[allow(clippy::type_complexity)]
+    fn instance_map_with_evaluation<F: PrimeField, D: EvaluationDomain<F>>(
+        cs: ConstraintSystemRef<F>,
+        t: &F,
+    ) -> R1CSResult<(Vec<F>, Vec<F>, Vec<F>, F, usize, usize)> {
+        LibsnarkReduction::instance_map_with_evaluation::<F, D>(cs, t)
+    }
+
+    fn witness_map_from_matrices<F: PrimeField, D: EvaluationDomain<F>>(
+        matrices: &ConstraintMatrices<F>,
+        num_inputs: usize,
+        num_constraints: usize,
+        full_assignment: &[F],
+    ) -> R1CSResult<Vec<F>> {
+        let domain =
+            D::new(num_constraints + num_inputs).ok_or(SynthesisError::PolynomialDegreeTooLarge)?;
+
+        Self::witness_map_from_matrices_with_domain::<F, D>(
+            &domain,
+            matrices,
+            num_inputs,
+            num_constraints,
+            full_assignment,
+        )
+    }
+
+    fn witness_map_from_matrices_with_domain<F: PrimeField, D: EvaluationDomain<F>>(
+        domain: &D,
+        matrices: &ConstraintMatrices<F>,
+        num_inputs: usize,
+        num_constraints: usize,
+        full_assignment: &[F],
+    ) -> R1CSResult<Vec<F>> {
+        let domain_size = domain.size();
+        let (mut a, mut b) = evaluate_a_and_b(
+            domain_size,
+            matrices,
+            num_inputs,
+            num_constraints,
+            full_assignment,
+        );
+
+        // On a satisfying assignment, `C` evaluates to `A * B` over the domain.
+        let mut c = cfg_iter!(a)
+            .zip(cfg_iter!(b))
+            .map(|(a, b)| *a * b)
+            .collect::<Vec<_>>();
+
+        let root_of_unity = D::new(2 * domain_size)
+            .ok_or(SynthesisError::PolynomialDegreeTooLarge)?
+            .element(1);
+        let coset_domain = domain
+            .get_coset(root_of_unity)
+            .ok_or(SynthesisError::PolynomialDegreeTooLarge)?;
+
+        for evaluations in [&mut a, &mut b, &mut c] {
+            domain.ifft_in_place(evaluations);
+            coset_domain.fft_in_place(evaluations);
+        }
+
+        cfg_iter_mut!(a)
+            .zip(b)
+            .zip(c)
+            .for_each(|((ab_i, b_i), c_i)| {
+                *ab_i *= &b_i;
+                *ab_i -= &c_i;
+            });
+
+        Ok(a)
+    }
+
+    fn h_query_scalars<F: PrimeField, D: EvaluationDomain<F>>(
+        max_power: usize,
+        t: F,
+        _: F,
+        delta_inverse: F,
+    ) -> Result<Vec<F>, SynthesisError> {
+        // `h(x)t(x)` has `2 * max_power + 1` coefficients; its evaluations on
+        // the odd powers of a root of unity of twice the domain size are
+        // those computed by the witness map.
+        let mut scalars = cfg_into_iter!(0..2 * max_power + 1)
+            .map(|i| delta_inverse * t.pow([i as u64]))
+            .collect::<Vec<_>>();
+        let domain = D::new(scalars.len()).ok_or(SynthesisError::PolynomialDegreeTooLarge)?;
+        domain.ifft_in_place(&mut scalars);
+        Ok(scalars.into_iter().skip(1).step_by(2).collect())
+    }
+}
+
+/// Computes the same R1CS-to-QAP reduction as [`LibsnarkReduction`] over
+/// radix-2 domains only, with in-place coset FFTs over two buffers in the
+/// witness map. Fails with `SynthesisError::PolynomialDegreeTooLarge` if the
+/// domain size is not a power of two.
+pub struct Radix2Reduction;
+
+impl Radix2Reduction {
+    fn check_domain_size(domain_size: usize) -> R1CSResult<()> {
+        if domain_size.is_power_of_two() {
+            Ok(())
+        } else {
+            Err(SynthesisError::PolynomialDegreeTooLarge)
+        }
+    }
+}
+
+impl R1CSToQAP for Radix2Reduction {
+    #[inline]
+    #[allow(clippy::type_complexity)]
+    fn instance_map_with_evaluation<F: PrimeField, D: EvaluationDomain<F>>(
+        cs: ConstraintSystemRef<F>,
+        t: &F,
+    ) -> R1CSResult<(Vec<F>, Vec<F>, Vec<F>, F, usize, usize)> {
+        let instance = LibsnarkReduction::instance_map_with_evaluation::<F, D>(cs, t)?;
+        Self::check_domain_size(instance.5)?;
+        Ok(instance)
+    }
+
+    fn witness_map_from_matrices<F: PrimeField, D: EvaluationDomain<F>>(
+        matrices: &ConstraintMatrices<F>,
+        num_inputs: usize,
+        num_constraints: usize,
+        full_assignment: &[F],
+    ) -> R1CSResult<Vec<F>> {
+        let domain =
+            D::new(num_constraints + num_inputs).ok_or(SynthesisError::PolynomialDegreeTooLarge)?;
+
+        Self::witness_map_from_matrices_with_domain::<F, D>(
+            &domain,
+            matrices,
+            num_inputs,
+            num_constraints,
+            full_assignment,
+        )
+    }
+
+    fn witness_map_from_matrices_with_domain<F: PrimeField, D: EvaluationDomain<F>>(
+        domain: &D,
+        matrices: &ConstraintMatrices<F>,
+        num_inputs: usize,
+        num_constraints: usize,
+        full_assignment: &[F],
+    ) -> R1CSResult<Vec<F>> {
+        let domain_size = domain.size();
+        Self::check_domain_size(domain_size)?;
+
+        let (mut ab, mut b) = evaluate_a_and_b(
+            domain_size,
+            matrices,
+            num_inputs,
+            num_constraints,
+            full_assignment,
+        );
+
+        let coset_domain = domain
+            .get_coset(F::GENERATOR)
+            .ok_or(SynthesisError::PolynomialDegreeTooLarge)?;
+
+        domain.ifft_in_place(&mut ab);
+        coset_domain.fft_in_place(&mut ab);
+        domain.ifft_in_place(&mut b);
+        coset_domain.fft_in_place(&mut b);
+
+        cfg_iter_mut!(ab)
+            .zip(cfg_iter!(b))
+            .for_each(|(ab_i, b_i)| *ab_i *= b_i);
+
+        // Reuse the buffer of `B` for the evaluations of `C`.
+        let c = &mut b;
+        cfg_iter_mut!(c[..num_constraints])
+            .zip(cfg_iter!(&matrices.c))
+            .for_each(|(c_i, ct_i)| *c_i = evaluate_constraint(ct_i, full_assignment));
+        cfg_iter_mut!(c[num_constraints..]).for_each(|c_i| *c_i = F::zero());
+
+        domain.ifft_in_place(c);
+        coset_domain.fft_in_place(c);
+
+        let vanishing_polynomial_over_coset = domain
+            .evaluate_vanishing_polynomial(F::GENERATOR)
+            .inverse()
+            .unwrap();
+        cfg_iter_mut!(ab).zip(cfg_iter!(c)).for_each(|(ab_i, c_i)| {
+            *ab_i -= c_i;
+            *ab_i *= &vanishing_polynomial_over_coset;
+        });
+
+        coset_domain.ifft_in_place(&mut ab);
+
+        Ok(ab)
+    }
+
+    fn h_query_scalars<F: PrimeField, D: EvaluationDomain<F>>(
+        max_power: usize,
+        t: F,
+        zt: F,
+        delta_inverse: F,
+    ) -> Result<Vec<F>, SynthesisError> {
+        LibsnarkReduction::h_query_scalars::<F, D>(max_power, t, zt, delta_inverse)
+    }
+}
@@ -0,0 +1,168 @@
+//! Rotate the `delta` of a proving key by a fresh secret factor `k`.
+//!
+//! The `P = r * s * delta * G` of a randomizer is computed from the `delta`
+//! of the key, so the randomizers issued before a refresh stop verifying
+//! under the refreshed key, which retires the rounds that used them. The
+//! refresh is proven by a proof of knowledge of `k` with `delta' = k * delta`
+//! in `E::G1`, and pairing checks, batched by a random linear combination,
+//! that every element over `delta` was divided by `k`.
+
+use crate::{r1cs_to_qap::R1CSToQAP, Groth16, ProvingKey};
+use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup, VariableBaseMSM};
+use ark_ff::{
+    field_hashers::{DefaultFieldHasher, HashToField},
+    Field, UniformRand, Zero,
+};
+use ark_poly::EvaluationDomain;
+use ark_serialize::*;
+use ark_std::{
+    cfg_iter,
+    ops::Neg,
+    rand::{CryptoRng, RngCore},
+    vec::Vec,
+};
+use sha2::Sha256;
+use zeroize::Zeroize;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// The domain separation tag of the challenges of a [`RefreshProof`].
+const REFRESH_DOMAIN: &[u8] = b"polymorphic-groth16-refresh";
+
+/// A proof that a proving key was refreshed into another one by multiplying
+/// its `delta` by a factor `k` known to the refresher.
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct RefreshProof<E: Pairing> {
+    /// The commitment `t * delta * G` of the proof of knowledge of `k`.
+    pub commitment_g1: E::G1Affine,
+    /// The response `t + c * k` to the challenge `c`.
+    pub response: E::ScalarField,
+}
+
+/// The challenge of the proof of knowledge of `k`, and the scalar of the
+/// random linear combination of the elements over `delta`.
+fn challenges<E: Pairing>(
+    old: &ProvingKey<E>,
+    new: &ProvingKey<E>,
+    commitment_g1: &E::G1Affine,
+) -> (E::ScalarField, E::ScalarField) {
+    let mut transcript = old.vk.digest().to_vec();
+    transcript.extend_from_slice(&new.vk.digest());
+    old.delta_g1
+        .serialize_compressed(&mut transcript)
+        .and_then(|_| new.delta_g1.serialize_compressed(&mut transcript))
+        .and_then(|_| commitment_g1.serialize_compressed(&mut transcript))
+        .and_then(|_| new.h_query.serialize_compressed(&mut transcript))
+        .and_then(|_| new.l_query.serialize_compressed(&mut transcript))
+        .and_then(|_| new.eta_delta_inv_g1.serialize_compressed(&mut transcript))
+        .expect("serializing into a vector cannot fail");
+    let hasher = <DefaultFieldHasher<Sha256> as HashToField<E::ScalarField>>::new(REFRESH_DOMAIN);
+    let challenges: Vec<E::ScalarField> = hasher.hash_to_field(&transcript, 2);
+    (challenges[0], challenges[1])
+}
+
+/// The elements of `pk` over `delta`, combined by the powers of `rho`.
+fn combine_over_delta<E: Pairing>(pk: &ProvingKey<E>, rho: E::ScalarField) -> E::G1 {
+    let bases: Vec<_> = pk
+        .h_query
+        .iter()
+        .chain(&pk.l_query)
+        .chain([&pk.eta_delta_inv_g1])
+        .copied()
+        .collect();
+    let scalars: Vec<_> = ark_std::iter::successors(Some(rho), |power| Some(*power * rho))
+        .take(bases.len())
+        .collect();
+    E::G1::msm_unchecked(&bases, &scalars)
+}
+
+impl<E: Pairing, QAP: R1CSToQAP, D: EvaluationDomain<E::ScalarField>> Groth16<E, QAP, D> {
+    /// Refresh `pk` by multiplying its `delta` by a fresh factor, dividing
+    /// the H-query, the L-query and the blinding base of the commitment by
+    /// it, and prove that it was done correctly.
+    ///
+    /// Randomizers and proofs made under `pk` do not verify under the
+    /// refreshed key, and the [`Trapdoor`](crate::Trapdoor) of `pk` no
+    /// longer simulates proofs for it.
+    pub fn refresh_keys<R: RngCore + CryptoRng>(
+        pk: &ProvingKey<E>,
+        rng: &mut R,
+    ) -> (ProvingKey<E>, RefreshProof<E>) {
+        let mut k = E::ScalarField::rand(rng);
+        while k.is_zero() {
+            k = E::ScalarField::rand(rng);
+        }
+        let mut k_inverse = k.inverse().unwrap();
+        let scale = |query: &[E::G1Affine]| -> Vec<E::G1Affine> {
+            let scaled: Vec<E::G1> = cfg_iter!(query).map(|g| *g * k_inverse).collect();
+            E::G1::normalize_batch(&scaled)
+        };
+
+        let mut new = pk.clone();
+        new.delta_g1 = (pk.delta_g1 * k).into_affine();
+        new.delta_g2 = (pk.delta_g2 * k).into_affine();
+        new.vk.delta_g2 = new.delta_g2;
+        new.h_query = scale(&pk.h_query);
+        new.l_query = scale(&pk.l_query);
+        new.eta_delta_inv_g1 = (pk.eta_delta_inv_g1 * k_inverse).into_affine();
+
+        let mut t = E::ScalarField::rand(rng);
+        let commitment_g1 = (pk.delta_g1 * t).into_affine();
+        let (c, _) = challenges(pk, &new, &commitment_g1);
+        let proof = RefreshProof {
+            commitment_g1,
+            response: t + c * k,
+        };
+        k.zeroize();
+        k_inverse.zeroize();
+        t.zeroize();
+        (new, proof)
+    }
+
+    /// Check that `new` is `old` refreshed as by
+    /// [`refresh_keys`](Self::refresh_keys), as proven by `proof`.
+    pub fn verify_key_refresh(
+        old: &ProvingKey<E>,
+        new: &ProvingKey<E>,
+        proof: &RefreshProof<E>,
+    ) -> bool {
+        // everything but the elements over `delta` is unchanged
+        let mut unchanged = new.clone();
+        unchanged.delta_g1 = old.delta_g1;
+        unchanged.delta_g2 = old.delta_g2;
+        unchanged.vk.delta_g2 = old.vk.delta_g2;
+        unchanged.h_query = old.h_query.clone();
+        unchanged.l_query = old.l_query.clone();
+        unchanged.eta_delta_inv_g1 = old.eta_delta_inv_g1;
+        if unchanged != *old
+            || new.vk.delta_g2 != new.delta_g2
+            || new.h_query.len() != old.h_query.len()
+            || new.l_query.len() != old.l_query.len()
+            || new.delta_g1.is_zero()
+        {
+            return false;
+        }
+
+        // the refresher knows `k` with `delta' * G = k * delta * G`
+        let (c, rho) = challenges(old, new, &proof.commitment_g1);
+        if old.delta_g1 * proof.response != proof.commitment_g1 + new.delta_g1 * c {
+            return false;
+        }
+
+        // `delta' * H = k * delta * H`, and the elements over `delta` are
+        // divided by `k`
+        let delta_check = E::multi_pairing(
+            [new.delta_g1, old.delta_g1.into_group().neg().into_affine()],
+            [old.delta_g2, new.delta_g2],
+        );
+        let query_check = E::multi_pairing(
+            [
+                combine_over_delta(new, rho).into_affine(),
+                combine_over_delta(old, rho).neg().into_affine(),
+            ],
+            [new.delta_g2, old.delta_g2],
+        );
+        delta_check.is_zero() && query_check.is_zero()
+    }
+}
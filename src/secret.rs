@@ -0,0 +1,142 @@
+use ark_ff::Field;
+use ark_serialize::{Read, SerializationError, Write};
+use ark_std::rand::RngCore;
+use core::fmt;
+use sha2::{Digest, Sha256};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// A secret scalar, such as the `r` and `s` of a randomizer or the toxic waste
+/// of a setup.
+///
+/// The scalar is zeroed when dropped and hidden from `Debug` output. It does
+/// not implement `CanonicalSerialize`, so that it cannot be written out as
+/// part of a larger structure by accident: [`export`](Self::export) is the
+/// only way to serialize it.
+///
+/// Field elements are `Copy`, so the copies returned by
+/// [`expose`](Self::expose) are the caller's to zero.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct SecretScalar<F: Field>(F);
+
+impl<F: Field> SecretScalar<F> {
+    /// Wrap the secret `value`.
+    pub fn new(value: F) -> Self {
+        Self(value)
+    }
+
+    /// The secret value.
+    pub fn expose(&self) -> &F {
+        &self.0
+    }
+
+    /// Write the secret value to `writer`, in its compressed
+    /// `CanonicalSerialize` encoding.
+    pub fn export<W: Write>(&self, writer: W) -> Result<(), SerializationError> {
+        self.0.serialize_compressed(writer)
+    }
+
+    /// Read a secret value written by [`export`](Self::export).
+    pub fn import<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        F::deserialize_compressed(reader).map(Self)
+    }
+}
+
+impl<F: Field> From<F> for SecretScalar<F> {
+    fn from(value: F) -> Self {
+        Self(value)
+    }
+}
+
+impl<F: Field> fmt::Debug for SecretScalar<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretScalar(<redacted>)")
+    }
+}
+
+impl<F: Field> Zeroize for SecretScalar<F> {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl<F: Field> Drop for SecretScalar<F> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl<F: Field> ZeroizeOnDrop for SecretScalar<F> {}
+
+/// The domain separation tag of [`MasterSecret::derive_child`].
+const CHILD_DOMAIN: &[u8] = b"polymorphic-groth16-master-secret-child";
+
+/// A 32-byte secret from which randomizers are derived deterministically, by
+/// [`Randomizer::derive`](crate::Randomizer::derive).
+///
+/// Like a [`SecretScalar`], it is zeroed when dropped, hidden from `Debug`
+/// output, and only serialized by [`export`](Self::export).
+#[derive(Clone, PartialEq, Eq)]
+pub struct MasterSecret([u8; 32]);
+
+impl MasterSecret {
+    /// Wrap the secret `bytes`.
+    pub fn new(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+
+    /// Sample a uniformly random master secret.
+    pub fn generate<R: RngCore>(rng: &mut R) -> Self {
+        let mut bytes = [0u8; 32];
+        rng.fill_bytes(&mut bytes);
+        Self(bytes)
+    }
+
+    /// The secret bytes.
+    pub fn expose(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// The child secret for `context`, e.g. an epoch, from which the
+    /// randomizers of that context are derived. Children of distinct contexts
+    /// are independent, and reveal nothing of their parent.
+    pub fn derive_child(&self, context: &[u8]) -> Self {
+        let mut hasher = Sha256::new();
+        hasher.update(CHILD_DOMAIN);
+        hasher.update(self.0);
+        hasher.update((context.len() as u64).to_le_bytes());
+        hasher.update(context);
+        Self(hasher.finalize().into())
+    }
+
+    /// Write the secret bytes to `writer`.
+    pub fn export<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        Ok(writer.write_all(&self.0)?)
+    }
+
+    /// Read a master secret written by [`export`](Self::export).
+    pub fn import<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let mut bytes = [0u8; 32];
+        reader.read_exact(&mut bytes)?;
+        Ok(Self(bytes))
+    }
+}
+
+impl fmt::Debug for MasterSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("MasterSecret(<redacted>)")
+    }
+}
+
+impl Zeroize for MasterSecret {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl Drop for MasterSecret {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for MasterSecret {}
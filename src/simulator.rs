@@ -0,0 +1,194 @@
+use crate::{
+    r1cs_to_qap::R1CSToQAP, CommitmentOpeningProof, CommittedProof, Groth16, Proof, Randomizer,
+};
+use crate::{Trapdoor, VerifyingKey};
+use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup, VariableBaseMSM};
+use ark_ff::{
+    field_hashers::{DefaultFieldHasher, HashToField},
+    Field,
+};
+use ark_poly::EvaluationDomain;
+use ark_relations::r1cs::{Result as R1CSResult, SynthesisError};
+use ark_serialize::CanonicalSerialize;
+use ark_std::vec::Vec;
+
+use sha2::Sha256;
+use zeroize::Zeroize;
+
+/// The domain separation tag of the scalars of a simulated proof.
+const SIMULATION_DOMAIN: &[u8] = b"polymorphic-groth16-simulation";
+
+impl<E: Pairing, QAP: R1CSToQAP, D: EvaluationDomain<E::ScalarField>> Groth16<E, QAP, D> {
+    /// Simulate a proof of the instance `public_inputs` under the randomizer
+    /// `rnd`, from the trapdoor of the key `vk` and without a witness.
+    ///
+    /// `A` and `B` are hashed from the trapdoor, `rnd` and the instance, and
+    /// `C` is the unique element with which they verify under `rnd`. So, like
+    /// an honest proof of a given witness, the simulated proof of an instance
+    /// is the same on every call under the same randomizer. Without the
+    /// trapdoor, its `A` and `B` cannot be told apart from uniform elements,
+    /// which honest `A` and `B` are over uniform `r` and `s`. Fails if `vk`
+    /// commits to some witnesses, whose proofs are simulated by
+    /// [`simulate_committed_proof`](Self::simulate_committed_proof).
+    pub fn simulate_proof(
+        trapdoor: &Trapdoor<E>,
+        vk: &VerifyingKey<E>,
+        public_inputs: &[E::ScalarField],
+        rnd: &Randomizer<E>,
+    ) -> R1CSResult<Proof<E>> {
+        if !vk.commitment_bases_g1.is_empty() {
+            return Err(SynthesisError::MalformedVerifyingKey);
+        }
+        let mut scalars = Self::simulation_scalars(trapdoor, vk, public_inputs, rnd, 2);
+        let proof = Self::simulate_with_commitment(
+            trapdoor,
+            vk,
+            public_inputs,
+            rnd,
+            E::G1Affine::zero(),
+            scalars[0],
+            scalars[1],
+        );
+        scalars.zeroize();
+        proof
+    }
+
+    /// Simulate a proof with a commitment of the instance `public_inputs`
+    /// under the randomizer `rnd`, as [`simulate_proof`](Self::simulate_proof)
+    /// does, under a key `vk` that commits to some witnesses.
+    ///
+    /// `D` commits to values and a blinding hashed along with `A` and `B`,
+    /// which cannot be told apart from uniform without the trapdoor, as the
+    /// default blinding of an honest `D` cannot without `r` and `s`. Its
+    /// opening is proven as by an honest prover.
+    pub fn simulate_committed_proof(
+        trapdoor: &Trapdoor<E>,
+        vk: &VerifyingKey<E>,
+        public_inputs: &[E::ScalarField],
+        rnd: &Randomizer<E>,
+    ) -> R1CSResult<CommittedProof<E>> {
+        let num_committed = vk.commitment_bases_g1.len();
+        if num_committed == 0 {
+            return Err(SynthesisError::MalformedVerifyingKey);
+        }
+        let commit = |values: &[E::ScalarField], blinding: E::ScalarField| {
+            let mut commitment = E::G1::msm_unchecked(&vk.commitment_bases_g1, values);
+            commitment += vk.eta_gamma_inv_g1 * blinding;
+            commitment.into_affine()
+        };
+
+        // `A` and `B`, the committed values and their nonces, then the
+        // blinding of `D` and its nonce.
+        let mut scalars =
+            Self::simulation_scalars(trapdoor, vk, public_inputs, rnd, 2 * num_committed + 4);
+        let (ab, rest) = scalars.split_at(2);
+        let (values, rest) = rest.split_at(num_committed);
+        let (nonces, rest) = rest.split_at(num_committed);
+        let (blinding, blinding_nonce) = (rest[0], rest[1]);
+
+        let d = commit(values, blinding);
+        let result =
+            Self::simulate_with_commitment(trapdoor, vk, public_inputs, rnd, d, ab[0], ab[1]).map(
+                |proof| {
+                    let t = commit(nonces, blinding_nonce);
+                    let challenge =
+                        CommitmentOpeningProof::challenge(vk, &proof.a, &proof.b, &proof.c, &d, &t);
+                    let responses = nonces
+                        .iter()
+                        .zip(values)
+                        .map(|(nonce, value)| *nonce + challenge * value)
+                        .collect();
+                    let opening = CommitmentOpeningProof {
+                        t,
+                        responses,
+                        blinding_response: blinding_nonce + challenge * blinding,
+                    };
+                    CommittedProof { proof, d, opening }
+                },
+            );
+        scalars.zeroize();
+        result
+    }
+
+    /// Hashes `count` scalars of the simulated proof of `public_inputs` under
+    /// `rnd` from the trapdoor, so that they are secret without it.
+    fn simulation_scalars(
+        trapdoor: &Trapdoor<E>,
+        vk: &VerifyingKey<E>,
+        public_inputs: &[E::ScalarField],
+        rnd: &Randomizer<E>,
+        count: usize,
+    ) -> Vec<E::ScalarField> {
+        let mut message = Vec::new();
+        trapdoor
+            .alpha
+            .expose()
+            .serialize_compressed(&mut message)
+            .and_then(|_| trapdoor.beta.expose().serialize_compressed(&mut message))
+            .and_then(|_| trapdoor.gamma.expose().serialize_compressed(&mut message))
+            .and_then(|_| trapdoor.delta.expose().serialize_compressed(&mut message))
+            .and_then(|_| trapdoor.t.expose().serialize_compressed(&mut message))
+            .and_then(|_| vk.serialize_compressed(&mut message))
+            .and_then(|_| rnd.p.serialize_compressed(&mut message))
+            .and_then(|_| rnd.q.serialize_compressed(&mut message))
+            .and_then(|_| public_inputs.serialize_compressed(&mut message))
+            .expect("serializing into a vector cannot fail");
+        let scalars =
+            <DefaultFieldHasher<Sha256> as HashToField<E::ScalarField>>::new(SIMULATION_DOMAIN)
+                .hash_to_field(&message, count);
+        message.zeroize();
+        scalars
+    }
+
+    /// Simulates the `A`, `B` and `C` elements of a proof whose commitment is
+    /// `d`, the identity if `vk` commits to no witness, with `A = a * G` and
+    /// `B = b * H`.
+    fn simulate_with_commitment(
+        trapdoor: &Trapdoor<E>,
+        vk: &VerifyingKey<E>,
+        public_inputs: &[E::ScalarField],
+        rnd: &Randomizer<E>,
+        d: E::G1Affine,
+        a: E::ScalarField,
+        b: E::ScalarField,
+    ) -> R1CSResult<Proof<E>> {
+        if (public_inputs.len() + 1) != vk.gamma_abc_g1.len() {
+            return Err(SynthesisError::MalformedVerifyingKey);
+        }
+        let alpha_inverse = trapdoor
+            .alpha
+            .expose()
+            .inverse()
+            .ok_or(SynthesisError::UnexpectedIdentity)?;
+        let beta_inverse = trapdoor
+            .beta
+            .expose()
+            .inverse()
+            .ok_or(SynthesisError::UnexpectedIdentity)?;
+        let delta_inverse = trapdoor
+            .delta
+            .expose()
+            .inverse()
+            .ok_or(SynthesisError::UnexpectedIdentity)?;
+
+        // The generators are recovered from the `alpha` and `beta` of `vk`.
+        let g1_generator = vk.alpha_g1 * alpha_inverse;
+        let g2_generator = vk.beta_g2 * beta_inverse;
+
+        // e(A, B) * e(P + Q - C, delta) = e(alpha, beta) * e(L + D, gamma)
+        let mut inputs = vk.gamma_abc_g1[0].into_group();
+        inputs += E::G1::msm_unchecked(&vk.gamma_abc_g1[1..], public_inputs);
+        inputs += d;
+        let mut c = g1_generator
+            * ((a * b - *trapdoor.alpha.expose() * trapdoor.beta.expose()) * delta_inverse);
+        c += rnd.p;
+        c += rnd.q;
+        c -= inputs * (*trapdoor.gamma.expose() * delta_inverse);
+
+        Ok(Proof {
+            a: (g1_generator * a).into_affine(),
+            b: (g2_generator * b).into_affine(),
+            c: c.into_affine(),
+        })
+    }
+}
@@ -0,0 +1,1050 @@
+use crate::{ProvingKey, QueryDensity, VerifyingKey};
+use ark_ec::{pairing::Pairing, AffineRepr};
+use ark_ff::PrimeField;
+use ark_relations::r1cs::SynthesisError;
+use ark_std::{borrow::Cow, mem::size_of, ops::Range};
+
+#[cfg(feature = "std")]
+use crate::{r1cs_to_qap::R1CSToQAP, secret::SecretScalar, Groth16};
+#[cfg(feature = "std")]
+use ark_ec::{scalar_mul::fixed_base::FixedBase, CurveGroup, Group};
+#[cfg(feature = "std")]
+use ark_ff::{Field, UniformRand, Zero};
+#[cfg(feature = "std")]
+use ark_poly::EvaluationDomain;
+#[cfg(feature = "std")]
+use ark_relations::r1cs::{
+    ConstraintSynthesizer, ConstraintSystem, OptimizationGoal, SynthesisMode,
+};
+#[cfg(feature = "std")]
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, SerializationError, Validate, Write,
+};
+#[cfg(feature = "std")]
+use ark_std::{cfg_iter, cmp::Ordering, rand::Rng};
+#[cfg(feature = "std")]
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{BufReader, BufWriter, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+#[cfg(feature = "std")]
+use zeroize::Zeroizing;
+
+#[cfg(all(feature = "std", unix))]
+use std::os::unix::fs::OpenOptionsExt;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// Bounds the memory the prover holds at once for the bases and scalars of a
+/// multi-scalar multiplication. Constraint synthesis and the QAP witness map
+/// are not bounded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProverConfig {
+    /// The number of bytes of bases and scalars processed at once. Every
+    /// multi-scalar multiplication is split into as many chunks as it takes
+    /// to stay within this budget.
+    pub memory_budget: usize,
+}
+
+impl ProverConfig {
+    /// A configuration that runs every multi-scalar multiplication in one go.
+    pub const UNBOUNDED: Self = Self {
+        memory_budget: usize::MAX,
+    };
+
+    /// A configuration holding at most `memory_budget` bytes of bases and
+    /// scalars at once.
+    pub fn with_memory_budget(memory_budget: usize) -> Self {
+        Self { memory_budget }
+    }
+
+    /// The number of bases of type `G` processed in one chunk. A chunk holds
+    /// the bases, their scalars and the scalars converted to integers.
+    pub fn chunk_len<G: AffineRepr>(&self) -> usize {
+        let per_base = size_of::<G>()
+            + size_of::<G::ScalarField>()
+            + size_of::<<G::ScalarField as PrimeField>::BigInt>();
+        (self.memory_budget / per_base).max(1)
+    }
+}
+
+impl Default for ProverConfig {
+    fn default() -> Self {
+        Self::UNBOUNDED
+    }
+}
+
+/// The queries of a proving key, in the order they are stored in a
+/// [`ProvingKeyFile`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Query {
+    /// The A-query in `E::G1`.
+    A,
+    /// The B-query in `E::G1`.
+    BG1,
+    /// The B-query in `E::G2`.
+    BG2,
+    /// The H-query in `E::G1`.
+    H,
+    /// The L-query in `E::G1`.
+    L,
+}
+
+impl Query {
+    /// All queries, in storage order.
+    pub const ALL: [Self; 5] = [Self::A, Self::BG1, Self::BG2, Self::H, Self::L];
+}
+
+/// A proving key whose queries are read in chunks, so that they need not be
+/// held in memory as a whole.
+pub trait ProvingKeySource<E: Pairing> {
+    /// The error returned when the queries cannot be read.
+    type Error: From<SynthesisError>;
+
+    /// The underlying verification key.
+    fn vk(&self) -> &VerifyingKey<E>;
+
+    /// The element `beta * G` in `E::G1`.
+    fn beta_g1(&self) -> E::G1Affine;
+
+    /// The element `delta * G` in `E::G1`.
+    fn delta_g1(&self) -> E::G1Affine;
+
+    /// The witnesses committed in the `D` element of proofs.
+    fn committed_witnesses(&self) -> &[usize];
+
+    /// The element `eta * delta^{-1} * G` in `E::G1`.
+    fn eta_delta_inv_g1(&self) -> E::G1Affine;
+
+    /// The variables with a base in the A-query.
+    fn a_query_density(&self) -> &QueryDensity;
+
+    /// The variables with a base in the B-queries.
+    fn b_query_density(&self) -> &QueryDensity;
+
+    /// The number of bases in `query`.
+    fn query_len(&self, query: Query) -> usize;
+
+    /// Reads the bases in `range` of a query in `E::G1`. Fails for
+    /// [`Query::BG2`].
+    fn g1_query(
+        &self,
+        query: Query,
+        range: Range<usize>,
+    ) -> Result<Cow<'_, [E::G1Affine]>, Self::Error>;
+
+    /// Reads the bases in `range` of the B-query in `E::G2`.
+    fn b_g2_query(&self, range: Range<usize>) -> Result<Cow<'_, [E::G2Affine]>, Self::Error>;
+}
+
+impl<E: Pairing> ProvingKeySource<E> for ProvingKey<E> {
+    type Error = SynthesisError;
+
+    fn vk(&self) -> &VerifyingKey<E> {
+        &self.vk
+    }
+
+    fn beta_g1(&self) -> E::G1Affine {
+        self.beta_g1
+    }
+
+    fn delta_g1(&self) -> E::G1Affine {
+        self.delta_g1
+    }
+
+    fn committed_witnesses(&self) -> &[usize] {
+        &self.committed_witnesses
+    }
+
+    fn eta_delta_inv_g1(&self) -> E::G1Affine {
+        self.eta_delta_inv_g1
+    }
+
+    fn a_query_density(&self) -> &QueryDensity {
+        &self.a_query_density
+    }
+
+    fn b_query_density(&self) -> &QueryDensity {
+        &self.b_query_density
+    }
+
+    fn query_len(&self, query: Query) -> usize {
+        match query {
+            Query::A => self.a_query.len(),
+            Query::BG1 => self.b_g1_query.len(),
+            Query::BG2 => self.b_g2_query.len(),
+            Query::H => self.h_query.len(),
+            Query::L => self.l_query.len(),
+        }
+    }
+
+    fn g1_query(
+        &self,
+        query: Query,
+        range: Range<usize>,
+    ) -> Result<Cow<'_, [E::G1Affine]>, Self::Error> {
+        let query = match query {
+            Query::A => &self.a_query,
+            Query::BG1 => &self.b_g1_query,
+            Query::H => &self.h_query,
+            Query::L => &self.l_query,
+            Query::BG2 => return Err(SynthesisError::MalformedVerifyingKey),
+        };
+        query
+            .get(range)
+            .map(Cow::Borrowed)
+            .ok_or(SynthesisError::MalformedVerifyingKey)
+    }
+
+    fn b_g2_query(&self, range: Range<usize>) -> Result<Cow<'_, [E::G2Affine]>, Self::Error> {
+        self.b_g2_query
+            .get(range)
+            .map(Cow::Borrowed)
+            .ok_or(SynthesisError::MalformedVerifyingKey)
+    }
+}
+
+/// An error of a prover reading its proving key from a file.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub enum KeyFileError {
+    /// The circuit could not be proven.
+    Synthesis(SynthesisError),
+    /// The proving key file could not be read.
+    Serialization(SerializationError),
+}
+
+#[cfg(feature = "std")]
+impl From<SynthesisError> for KeyFileError {
+    fn from(e: SynthesisError) -> Self {
+        Self::Synthesis(e)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<SerializationError> for KeyFileError {
+    fn from(e: SerializationError) -> Self {
+        Self::Serialization(e)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for KeyFileError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Serialization(e.into())
+    }
+}
+
+#[cfg(feature = "std")]
+impl core::fmt::Display for KeyFileError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Synthesis(e) => e.fmt(f),
+            Self::Serialization(e) => e.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for KeyFileError {}
+
+/// The magic bytes opening a proving key file.
+pub const PROVING_KEY_MAGIC: [u8; 4] = *b"PZKP";
+
+/// The version of the proving key file format written by this crate.
+pub const PROVING_KEY_VERSION: u8 = 2;
+
+/// A proving key stored in a file, whose queries are read on demand.
+///
+/// The crate forbids `unsafe` code, so the file is not memory-mapped: each
+/// read seeks to the requested bases and deserializes them. Bases are stored
+/// uncompressed, so that any range of a query can be located directly.
+///
+/// # Format
+///
+/// All integers are little-endian.
+///
+/// | bytes                | content                                            |
+/// |----------------------|----------------------------------------------------|
+/// | 4                    | magic `PZKP`                                       |
+/// | 1                    | format version, currently `2`                      |
+/// | 8                    | `p`, the byte length of the parameters             |
+/// | 8 × 5                | base counts of the A, B-G1, B-G2, H and L-query    |
+/// | `p`                  | parameters                                         |
+/// | rest                 | bases of the A, B-G1, B-G2, H and L-query          |
+///
+/// The parameters are the uncompressed encodings of `vk`, `beta_g1`,
+/// `delta_g1`, `delta_g2`, `a_query_density`, `b_query_density`,
+/// `committed_witnesses` and `eta_delta_inv_g1` of the [`ProvingKey`], in this
+/// order.
+#[cfg(feature = "std")]
+pub struct ProvingKeyFile<E: Pairing> {
+    vk: VerifyingKey<E>,
+    beta_g1: E::G1Affine,
+    delta_g1: E::G1Affine,
+    delta_g2: E::G2Affine,
+    a_query_density: QueryDensity,
+    b_query_density: QueryDensity,
+    committed_witnesses: Vec<usize>,
+    eta_delta_inv_g1: E::G1Affine,
+    /// The byte offset and number of bases of each query.
+    sections: [(u64, usize); 5],
+    validate: Validate,
+    file: Mutex<File>,
+}
+
+/// The byte length of the fixed-size header of a proving key file.
+#[cfg(feature = "std")]
+const HEADER_LEN: u64 = 4 + 1 + 8 + 8 * 5;
+
+/// The parts of a proving key stored before its queries.
+#[cfg(feature = "std")]
+struct KeyParameters<'a, E: Pairing> {
+    vk: &'a VerifyingKey<E>,
+    beta_g1: E::G1Affine,
+    delta_g1: E::G1Affine,
+    delta_g2: E::G2Affine,
+    a_query_density: &'a QueryDensity,
+    b_query_density: &'a QueryDensity,
+    committed_witnesses: &'a [usize],
+    eta_delta_inv_g1: E::G1Affine,
+}
+
+#[cfg(feature = "std")]
+impl<'a, E: Pairing> KeyParameters<'a, E> {
+    /// Writes the header and the parameters of a proving key file whose
+    /// queries have `query_lens` bases, and returns the byte offset at which
+    /// the queries start.
+    fn write_header<W: Write>(
+        &self,
+        mut writer: W,
+        query_lens: [usize; 5],
+    ) -> Result<u64, SerializationError> {
+        let parameters_len = self.vk.uncompressed_size()
+            + self.beta_g1.uncompressed_size()
+            + self.delta_g1.uncompressed_size()
+            + self.delta_g2.uncompressed_size()
+            + self.a_query_density.uncompressed_size()
+            + self.b_query_density.uncompressed_size()
+            + self.committed_witnesses.uncompressed_size()
+            + self.eta_delta_inv_g1.uncompressed_size();
+
+        writer.write_all(&PROVING_KEY_MAGIC)?;
+        writer.write_all(&[PROVING_KEY_VERSION])?;
+        writer.write_all(&(parameters_len as u64).to_le_bytes())?;
+        for len in query_lens {
+            writer.write_all(&(len as u64).to_le_bytes())?;
+        }
+
+        self.vk.serialize_uncompressed(&mut writer)?;
+        self.beta_g1.serialize_uncompressed(&mut writer)?;
+        self.delta_g1.serialize_uncompressed(&mut writer)?;
+        self.delta_g2.serialize_uncompressed(&mut writer)?;
+        self.a_query_density.serialize_uncompressed(&mut writer)?;
+        self.b_query_density.serialize_uncompressed(&mut writer)?;
+        self.committed_witnesses
+            .serialize_uncompressed(&mut writer)?;
+        self.eta_delta_inv_g1.serialize_uncompressed(&mut writer)?;
+
+        Ok(HEADER_LEN + parameters_len as u64)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: Pairing> ProvingKeyFile<E> {
+    /// Writes `pk` in the proving key file format.
+    pub fn write<W: Write>(pk: &ProvingKey<E>, mut writer: W) -> Result<(), SerializationError> {
+        let parameters = KeyParameters {
+            vk: &pk.vk,
+            beta_g1: pk.beta_g1,
+            delta_g1: pk.delta_g1,
+            delta_g2: pk.delta_g2,
+            a_query_density: &pk.a_query_density,
+            b_query_density: &pk.b_query_density,
+            committed_witnesses: &pk.committed_witnesses,
+            eta_delta_inv_g1: pk.eta_delta_inv_g1,
+        };
+        parameters.write_header(&mut writer, Query::ALL.map(|query| pk.query_len(query)))?;
+
+        let g1_queries = [&pk.a_query, &pk.b_g1_query];
+        for base in g1_queries.into_iter().flatten() {
+            base.serialize_uncompressed(&mut writer)?;
+        }
+        for base in &pk.b_g2_query {
+            base.serialize_uncompressed(&mut writer)?;
+        }
+        for base in pk.h_query.iter().chain(&pk.l_query) {
+            base.serialize_uncompressed(&mut writer)?;
+        }
+        Ok(())
+    }
+
+    /// Opens the proving key file at `path`. The parameters are always
+    /// validated; `validate` decides whether bases are checked as they are
+    /// read, which costs about as much as the proving itself.
+    pub fn open(path: impl AsRef<Path>, validate: Validate) -> Result<Self, SerializationError> {
+        Self::from_file(File::open(path)?, validate)
+    }
+
+    /// Reads the header and parameters of a proving key file, leaving the
+    /// queries in the file.
+    pub fn from_file(mut file: File, validate: Validate) -> Result<Self, SerializationError> {
+        let file_len = file.metadata()?.len();
+        file.seek(SeekFrom::Start(0))?;
+
+        let mut header = [0u8; HEADER_LEN as usize];
+        file.read_exact(&mut header)?;
+        if header[..4] != PROVING_KEY_MAGIC || header[4] != PROVING_KEY_VERSION {
+            return Err(SerializationError::InvalidData);
+        }
+        let read_u64 = |i: usize| {
+            let bytes = header[5 + 8 * i..13 + 8 * i].try_into().unwrap();
+            u64::from_le_bytes(bytes)
+        };
+        let parameters_len = read_u64(0);
+        let parameters_end = HEADER_LEN
+            .checked_add(parameters_len)
+            .filter(|&end| end <= file_len)
+            .ok_or(SerializationError::InvalidData)?;
+        let parameters_len =
+            usize::try_from(parameters_len).map_err(|_| SerializationError::InvalidData)?;
+
+        let mut parameters = vec![0u8; parameters_len];
+        file.read_exact(&mut parameters)?;
+        let mut reader = &parameters[..];
+        let vk = VerifyingKey::deserialize_uncompressed(&mut reader)?;
+        let beta_g1 = E::G1Affine::deserialize_uncompressed(&mut reader)?;
+        let delta_g1 = E::G1Affine::deserialize_uncompressed(&mut reader)?;
+        let delta_g2 = E::G2Affine::deserialize_uncompressed(&mut reader)?;
+        let a_query_density = QueryDensity::deserialize_uncompressed(&mut reader)?;
+        let b_query_density = QueryDensity::deserialize_uncompressed(&mut reader)?;
+        let committed_witnesses = Vec::<usize>::deserialize_uncompressed(&mut reader)?;
+        let eta_delta_inv_g1 = E::G1Affine::deserialize_uncompressed(&mut reader)?;
+        if !reader.is_empty() {
+            return Err(SerializationError::InvalidData);
+        }
+
+        let mut sections = [(0, 0); 5];
+        let mut offset = parameters_end;
+        for (i, query) in Query::ALL.into_iter().enumerate() {
+            let len =
+                usize::try_from(read_u64(i + 1)).map_err(|_| SerializationError::InvalidData)?;
+            sections[i] = (offset, len);
+            offset = (len as u64)
+                .checked_mul(Self::base_size(query) as u64)
+                .and_then(|size| size.checked_add(offset))
+                .ok_or(SerializationError::InvalidData)?;
+        }
+
+        // The bases of every query must match the densities and the number of
+        // witness variables, and fit in the file.
+        let num_witness_variables = a_query_density
+            .len()
+            .checked_sub(vk.gamma_abc_g1.len())
+            .ok_or(SerializationError::InvalidData)?;
+        let [a, b_g1, b_g2, _, l] = sections.map(|(_, len)| len);
+        if a != a_query_density.count_ones()
+            || b_g1 != b_query_density.count_ones()
+            || b_g2 != b_g1
+            || b_query_density.len() != a_query_density.len()
+            || l != num_witness_variables
+            || committed_witnesses.len() != vk.commitment_bases_g1.len()
+            || committed_witnesses
+                .iter()
+                .any(|&i| i >= num_witness_variables)
+            || offset > file_len
+        {
+            return Err(SerializationError::InvalidData);
+        }
+
+        Ok(Self {
+            vk,
+            beta_g1,
+            delta_g1,
+            delta_g2,
+            a_query_density,
+            b_query_density,
+            committed_witnesses,
+            eta_delta_inv_g1,
+            sections,
+            validate,
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Reads the whole proving key into memory.
+    pub fn load(&self) -> Result<ProvingKey<E>, SerializationError> {
+        let g1_query = |query| self.read_bases(query, 0..self.sections[query as usize].1);
+        Ok(ProvingKey {
+            vk: self.vk.clone(),
+            beta_g1: self.beta_g1,
+            delta_g1: self.delta_g1,
+            delta_g2: self.delta_g2,
+            a_query: g1_query(Query::A)?,
+            a_query_density: self.a_query_density.clone(),
+            b_g1_query: g1_query(Query::BG1)?,
+            b_g2_query: self.read_bases(Query::BG2, 0..self.sections[Query::BG2 as usize].1)?,
+            b_query_density: self.b_query_density.clone(),
+            h_query: g1_query(Query::H)?,
+            l_query: g1_query(Query::L)?,
+            committed_witnesses: self.committed_witnesses.clone(),
+            eta_delta_inv_g1: self.eta_delta_inv_g1,
+        })
+    }
+
+    /// The byte length of one uncompressed base of `query`.
+    fn base_size(query: Query) -> usize {
+        match query {
+            Query::BG2 => E::G2Affine::zero().uncompressed_size(),
+            _ => E::G1Affine::zero().uncompressed_size(),
+        }
+    }
+
+    fn read_bases<G: AffineRepr>(
+        &self,
+        query: Query,
+        range: Range<usize>,
+    ) -> Result<Vec<G>, SerializationError> {
+        let (offset, len) = self.sections[query as usize];
+        if range.start > range.end || range.end > len {
+            return Err(SerializationError::InvalidData);
+        }
+        let base_size = Self::base_size(query) as u64;
+
+        // A poisoned lock leaves the file in a usable state, as every read
+        // seeks to its own position first.
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        file.seek(SeekFrom::Start(offset + range.start as u64 * base_size))?;
+        let mut reader = BufReader::new((&mut *file).take(range.len() as u64 * base_size));
+        range
+            .map(|_| G::deserialize_with_mode(&mut reader, Compress::No, self.validate))
+            .collect()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: Pairing> ProvingKeySource<E> for ProvingKeyFile<E> {
+    type Error = KeyFileError;
+
+    fn vk(&self) -> &VerifyingKey<E> {
+        &self.vk
+    }
+
+    fn beta_g1(&self) -> E::G1Affine {
+        self.beta_g1
+    }
+
+    fn delta_g1(&self) -> E::G1Affine {
+        self.delta_g1
+    }
+
+    fn committed_witnesses(&self) -> &[usize] {
+        &self.committed_witnesses
+    }
+
+    fn eta_delta_inv_g1(&self) -> E::G1Affine {
+        self.eta_delta_inv_g1
+    }
+
+    fn a_query_density(&self) -> &QueryDensity {
+        &self.a_query_density
+    }
+
+    fn b_query_density(&self) -> &QueryDensity {
+        &self.b_query_density
+    }
+
+    fn query_len(&self, query: Query) -> usize {
+        self.sections[query as usize].1
+    }
+
+    fn g1_query(
+        &self,
+        query: Query,
+        range: Range<usize>,
+    ) -> Result<Cow<'_, [E::G1Affine]>, Self::Error> {
+        if query == Query::BG2 {
+            return Err(SynthesisError::MalformedVerifyingKey.into());
+        }
+        Ok(Cow::Owned(self.read_bases(query, range)?))
+    }
+
+    fn b_g2_query(&self, range: Range<usize>) -> Result<Cow<'_, [E::G2Affine]>, Self::Error> {
+        Ok(Cow::Owned(self.read_bases(Query::BG2, range)?))
+    }
+}
+
+/// Bounds the memory an out-of-core setup holds at once for the group
+/// elements of a query.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SetupConfig {
+    /// The number of bytes of group elements computed at once. Every query is
+    /// computed, normalized and written in as many chunks as it takes to stay
+    /// within this budget, and a checkpoint is written after each chunk.
+    pub memory_budget: usize,
+}
+
+#[cfg(feature = "std")]
+impl SetupConfig {
+    /// A configuration holding at most `memory_budget` bytes of group
+    /// elements at once.
+    pub fn with_memory_budget(memory_budget: usize) -> Self {
+        Self { memory_budget }
+    }
+
+    /// The number of elements of `G` computed in one chunk. A chunk holds the
+    /// scalars, the projective elements and their affine form.
+    pub fn chunk_len<G: CurveGroup>(&self) -> usize {
+        let per_element = size_of::<G>() + size_of::<G::Affine>() + size_of::<G::ScalarField>();
+        (self.memory_budget / per_element).max(1)
+    }
+}
+
+/// The progress of an out-of-core setup, reported after every chunk.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SetupProgress {
+    /// The query being written.
+    pub query: Query,
+    /// The number of bases of `query` written so far.
+    pub bases_written: usize,
+    /// The number of bases of `query`.
+    pub num_bases: usize,
+}
+
+/// What an out-of-core setup needs to resume: the toxic waste, unencrypted,
+/// the shape of the circuit and the position reached in the key file.
+#[cfg(feature = "std")]
+struct SetupCheckpoint<E: Pairing> {
+    alpha: SecretScalar<E::ScalarField>,
+    beta: SecretScalar<E::ScalarField>,
+    gamma: SecretScalar<E::ScalarField>,
+    delta: SecretScalar<E::ScalarField>,
+    g1_generator: E::G1,
+    g2_generator: E::G2,
+    t: SecretScalar<E::ScalarField>,
+    num_instance_variables: usize,
+    num_witness_variables: usize,
+    num_constraints: usize,
+    /// The index in [`Query::ALL`] of the query being written.
+    query: usize,
+    /// The number of bases of that query already written.
+    bases_written: usize,
+}
+
+#[cfg(feature = "std")]
+impl<E: Pairing> SetupCheckpoint<E> {
+    /// Replaces the checkpoint at `path`, so that a crash leaves either the
+    /// previous or the new checkpoint behind.
+    fn save(&self, path: &Path) -> Result<(), SerializationError> {
+        let tmp_path = with_suffix(path, ".tmp");
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        let mut file = options.open(&tmp_path)?;
+        for secret in [&self.alpha, &self.beta, &self.gamma, &self.delta] {
+            secret.export(&mut file)?;
+        }
+        self.g1_generator.serialize_uncompressed(&mut file)?;
+        self.g2_generator.serialize_uncompressed(&mut file)?;
+        self.t.export(&mut file)?;
+        for n in [
+            self.num_instance_variables,
+            self.num_witness_variables,
+            self.num_constraints,
+            self.query,
+            self.bases_written,
+        ] {
+            n.serialize_uncompressed(&mut file)?;
+        }
+        file.sync_all()?;
+        fs::rename(tmp_path, path)?;
+        Ok(())
+    }
+
+    /// Reads the checkpoint written by [`save`](Self::save) at `path`. Fails
+    /// if its position lies past the last query.
+    fn load(path: &Path) -> Result<Self, SerializationError> {
+        let mut file = BufReader::new(File::open(path)?);
+        let checkpoint = Self {
+            alpha: SecretScalar::import(&mut file)?,
+            beta: SecretScalar::import(&mut file)?,
+            gamma: SecretScalar::import(&mut file)?,
+            delta: SecretScalar::import(&mut file)?,
+            g1_generator: E::G1::deserialize_uncompressed(&mut file)?,
+            g2_generator: E::G2::deserialize_uncompressed(&mut file)?,
+            t: SecretScalar::import(&mut file)?,
+            num_instance_variables: usize::deserialize_uncompressed(&mut file)?,
+            num_witness_variables: usize::deserialize_uncompressed(&mut file)?,
+            num_constraints: usize::deserialize_uncompressed(&mut file)?,
+            query: usize::deserialize_uncompressed(&mut file)?,
+            bases_written: usize::deserialize_uncompressed(&mut file)?,
+        };
+        if checkpoint.query > Query::ALL.len() {
+            return Err(SerializationError::InvalidData);
+        }
+        Ok(checkpoint)
+    }
+}
+
+#[cfg(feature = "std")]
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(suffix);
+    path.into()
+}
+
+#[cfg(feature = "std")]
+impl<E: Pairing, QAP: R1CSToQAP, D: EvaluationDomain<E::ScalarField>> Groth16<E, QAP, D> {
+    /// Generates a random proving key for a circuit using the provided
+    /// R1CS-to-QAP reduction, writing it to a [`ProvingKeyFile`] at `path`,
+    /// and returns the verifying key.
+    ///
+    /// See [`generate_key_file_with_progress`](Self::generate_key_file_with_progress)
+    /// for the memory use and the checkpoints. The key commits to no witness.
+    pub fn generate_key_file_with_reduction<C>(
+        circuit: C,
+        path: impl AsRef<Path>,
+        config: &SetupConfig,
+        rng: &mut impl Rng,
+    ) -> Result<VerifyingKey<E>, KeyFileError>
+    where
+        C: ConstraintSynthesizer<E::ScalarField>,
+    {
+        let vk = Self::generate_key_file_with_progress(circuit, path, config, rng, |_| true)?;
+        Ok(vk.expect("the setup is never stopped"))
+    }
+
+    /// Generates a random proving key for a circuit using the provided
+    /// R1CS-to-QAP reduction, writing it to a [`ProvingKeyFile`] at `path`.
+    ///
+    /// The queries are computed, normalized and written one chunk at a time,
+    /// so that at most one chunk of group elements of `config` is held in
+    /// memory. The budget only bounds the group elements: the scalars of
+    /// every query are computed up front and held in memory for the whole
+    /// setup, which takes a few field elements per variable and constraint.
+    /// `progress` is called after every chunk, and returning `false` from it
+    /// stops the setup with `Ok(None)`.
+    ///
+    /// A checkpoint is kept at `path` with `.checkpoint` appended, and updated
+    /// after every chunk. If it exists when the setup starts, the setup
+    /// resumes from it instead of starting over, without using `rng`; the
+    /// circuit must then have the same shape. It is removed once the key file
+    /// is complete.
+    ///
+    /// # Security
+    ///
+    /// **The checkpoint holds the toxic waste in plaintext.** Whoever reads it
+    /// can forge proofs under the key. It is only readable by its owner on
+    /// Unix, but it is not encrypted: keep it on storage as trusted as the
+    /// toxic waste itself, and destroy it securely if the setup is abandoned.
+    /// Removing it at the end of the setup does not erase it from the disk.
+    ///
+    /// For the same `rng`, the key is the one generated by
+    /// [`generate_random_parameters_with_reduction`](Self::generate_random_parameters_with_reduction).
+    pub fn generate_key_file_with_progress<C>(
+        circuit: C,
+        path: impl AsRef<Path>,
+        config: &SetupConfig,
+        rng: &mut impl Rng,
+        mut progress: impl FnMut(&SetupProgress) -> bool,
+    ) -> Result<Option<VerifyingKey<E>>, KeyFileError>
+    where
+        C: ConstraintSynthesizer<E::ScalarField>,
+    {
+        let setup_time = start_timer!(|| "Groth16::Generator to file");
+        let path = path.as_ref();
+        let checkpoint_path = with_suffix(path, ".checkpoint");
+
+        let cs = ConstraintSystem::new_ref();
+        cs.set_optimization_goal(OptimizationGoal::Constraints);
+        cs.set_mode(SynthesisMode::Setup);
+
+        // Synthesize the circuit.
+        let synthesis_time = start_timer!(|| "Constraint synthesis");
+        circuit.generate_constraints(cs.clone())?;
+        end_timer!(synthesis_time);
+
+        let lc_time = start_timer!(|| "Inlining LCs");
+        cs.finalize();
+        end_timer!(lc_time);
+
+        let num_instance_variables = cs.num_instance_variables();
+        let num_witness_variables = cs.num_witness_variables();
+        let num_constraints = cs.num_constraints();
+
+        // Resume from the checkpoint, or sample the toxic waste in the order
+        // of `generate_random_parameters_with_reduction`.
+        let resumed = checkpoint_path.exists();
+        let mut checkpoint = if resumed {
+            let checkpoint = SetupCheckpoint::<E>::load(&checkpoint_path)?;
+            if (
+                checkpoint.num_instance_variables,
+                checkpoint.num_witness_variables,
+                checkpoint.num_constraints,
+            ) != (
+                num_instance_variables,
+                num_witness_variables,
+                num_constraints,
+            ) {
+                return Err(SerializationError::InvalidData.into());
+            }
+            checkpoint
+        } else {
+            let alpha = SecretScalar::new(E::ScalarField::rand(rng));
+            let beta = SecretScalar::new(E::ScalarField::rand(rng));
+            let gamma = SecretScalar::new(E::ScalarField::rand(rng));
+            let delta = SecretScalar::new(E::ScalarField::rand(rng));
+
+            let g1_generator = E::G1::rand(rng);
+            let g2_generator = E::G2::rand(rng);
+
+            let domain = D::new(num_constraints + num_instance_variables)
+                .ok_or(SynthesisError::PolynomialDegreeTooLarge)?;
+            let t = SecretScalar::new(domain.sample_element_outside_domain(rng));
+
+            let checkpoint = SetupCheckpoint {
+                alpha,
+                beta,
+                gamma,
+                delta,
+                g1_generator,
+                g2_generator,
+                t,
+                num_instance_variables,
+                num_witness_variables,
+                num_constraints,
+                query: 0,
+                bases_written: 0,
+            };
+            checkpoint.save(&checkpoint_path)?;
+            checkpoint
+        };
+        // The toxic waste stays in the checkpoint, and every scalar revealing
+        // it is zeroed on drop, whichever way the setup returns.
+        let (alpha, beta, gamma, delta, t) = (
+            checkpoint.alpha.expose(),
+            checkpoint.beta.expose(),
+            checkpoint.gamma.expose(),
+            checkpoint.delta.expose(),
+            checkpoint.t.expose(),
+        );
+        let (g1_generator, g2_generator) = (checkpoint.g1_generator, checkpoint.g2_generator);
+
+        let reduction_time = start_timer!(|| "R1CS to QAP Instance Map with Evaluation");
+        let (a, b, c, zt, _, m_raw) =
+            QAP::instance_map_with_evaluation::<E::ScalarField, D>(cs, t)?;
+        let (a, b, c, zt) = (
+            Zeroizing::new(a),
+            Zeroizing::new(b),
+            Zeroizing::new(c),
+            SecretScalar::new(zt),
+        );
+        end_timer!(reduction_time);
+
+        let a_query_density = a.iter().map(|a_i| !a_i.is_zero()).collect::<QueryDensity>();
+        let b_query_density = b.iter().map(|b_i| !b_i.is_zero()).collect::<QueryDensity>();
+
+        let gamma_inverse =
+            SecretScalar::new(gamma.inverse().ok_or(SynthesisError::UnexpectedIdentity)?);
+        let delta_inverse =
+            SecretScalar::new(delta.inverse().ok_or(SynthesisError::UnexpectedIdentity)?);
+
+        let gamma_abc = Zeroizing::new(
+            cfg_iter!(a[..num_instance_variables])
+                .zip(&b[..num_instance_variables])
+                .zip(&c[..num_instance_variables])
+                .map(|((a, b), c)| (*beta * a + &(*alpha * b) + c) * gamma_inverse.expose())
+                .collect::<Vec<_>>(),
+        );
+
+        let l = Zeroizing::new(
+            cfg_iter!(a[num_instance_variables..])
+                .zip(&b[num_instance_variables..])
+                .zip(&c[num_instance_variables..])
+                .map(|((a, b), c)| (*beta * a + &(*alpha * b) + c) * delta_inverse.expose())
+                .collect::<Vec<_>>(),
+        );
+
+        drop(c);
+
+        let a = Zeroizing::new(a_query_density.filter(0, &a));
+        let b = Zeroizing::new(b_query_density.filter(0, &b));
+        let h = Zeroizing::new(QAP::h_query_scalars::<_, D>(
+            m_raw - 1,
+            *t,
+            *zt.expose(),
+            *delta_inverse.expose(),
+        )?);
+
+        // The window tables are sized for a chunk, not for the whole key.
+        let scalar_bits = E::ScalarField::MODULUS_BIT_SIZE as usize;
+        let g1_chunk_len = config.chunk_len::<E::G1>();
+        let g2_chunk_len = config.chunk_len::<E::G2>();
+        let largest_g1_query = [a.len(), b.len(), h.len(), l.len(), gamma_abc.len()]
+            .into_iter()
+            .max()
+            .unwrap_or(0);
+        let g1_window = FixedBase::get_mul_window_size(g1_chunk_len.min(largest_g1_query));
+        let g1_table_time = start_timer!(|| "Compute G1 window table");
+        let g1_table = FixedBase::get_window_table::<E::G1>(scalar_bits, g1_window, g1_generator);
+        end_timer!(g1_table_time);
+        let g1_bases = |scalars: &[E::ScalarField]| {
+            let bases = FixedBase::msm::<E::G1>(scalar_bits, g1_window, &g1_table, scalars);
+            E::G1::normalize_batch(&bases)
+        };
+
+        let verifying_key_time = start_timer!(|| "Generate the R1CS verification key");
+        let alpha_g1 = g1_generator.mul_bigint(alpha.into_bigint());
+        let beta_g1 = g1_generator.mul_bigint(beta.into_bigint());
+        let beta_g2 = g2_generator.mul_bigint(beta.into_bigint());
+        let delta_g1 = g1_generator.mul_bigint(delta.into_bigint());
+        let delta_g2 = g2_generator.mul_bigint(delta.into_bigint());
+        let gamma_g2 = g2_generator.mul_bigint(gamma.into_bigint());
+        let vk = VerifyingKey::<E> {
+            alpha_g1: alpha_g1.into_affine(),
+            beta_g2: beta_g2.into_affine(),
+            gamma_g2: gamma_g2.into_affine(),
+            delta_g2: delta_g2.into_affine(),
+            gamma_abc_g1: gamma_abc.chunks(g1_chunk_len).flat_map(g1_bases).collect(),
+            commitment_bases_g1: Vec::new(),
+            eta_gamma_inv_g1: E::G1Affine::zero(),
+        };
+        drop(gamma_abc);
+        end_timer!(verifying_key_time);
+
+        // The header and parameters are rewritten on every run, as they are
+        // cheap to recompute. A resumed setup needs the key file it left
+        // behind.
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(!resumed)
+            .truncate(!resumed)
+            .open(path)?;
+        let existing_len = file.metadata()?.len();
+        let parameters = KeyParameters {
+            vk: &vk,
+            beta_g1: beta_g1.into_affine(),
+            delta_g1: delta_g1.into_affine(),
+            delta_g2: delta_g2.into_affine(),
+            a_query_density: &a_query_density,
+            b_query_density: &b_query_density,
+            committed_witnesses: &[],
+            eta_delta_inv_g1: E::G1Affine::zero(),
+        };
+        let query_lens = [a.len(), b.len(), b.len(), h.len(), l.len()];
+        let mut header = Vec::new();
+        let mut offset = parameters.write_header(&mut header, query_lens)?;
+
+        // The checkpoint must point within its query, and the key file must
+        // hold every base written before it.
+        if resumed {
+            let mut written_len = offset;
+            for (i, query) in Query::ALL.into_iter().enumerate() {
+                let base_size = ProvingKeyFile::<E>::base_size(query) as u64;
+                let num_bases = match i.cmp(&checkpoint.query) {
+                    Ordering::Less => query_lens[i],
+                    Ordering::Equal => checkpoint.bases_written,
+                    Ordering::Greater => break,
+                };
+                if num_bases > query_lens[i] {
+                    return Err(SerializationError::InvalidData.into());
+                }
+                written_len += num_bases as u64 * base_size;
+            }
+            if (checkpoint.query == Query::ALL.len() && checkpoint.bases_written > 0)
+                || existing_len < written_len
+            {
+                return Err(SerializationError::InvalidData.into());
+            }
+        }
+        file.write_all(&header)?;
+        drop(header);
+
+        let mut g2_table = None;
+        for (i, query) in Query::ALL.into_iter().enumerate() {
+            let num_bases = query_lens[i];
+            let base_size = ProvingKeyFile::<E>::base_size(query) as u64;
+            let section_offset = offset;
+            offset += num_bases as u64 * base_size;
+
+            let mut start = match i.cmp(&checkpoint.query) {
+                Ordering::Less => continue,
+                Ordering::Equal => checkpoint.bases_written,
+                Ordering::Greater => 0,
+            };
+            let query_time =
+                start_timer!(|| format!("Write {:?}-query of size {}", query, num_bases));
+            while start < num_bases {
+                let end = match query {
+                    Query::BG2 => start + g2_chunk_len,
+                    _ => start + g1_chunk_len,
+                }
+                .min(num_bases);
+
+                file.seek(SeekFrom::Start(section_offset + start as u64 * base_size))?;
+                let mut writer = BufWriter::new(&file);
+                if query == Query::BG2 {
+                    let g2_table = g2_table.get_or_insert_with(|| {
+                        let g2_window = FixedBase::get_mul_window_size(g2_chunk_len.min(num_bases));
+                        let table = FixedBase::get_window_table::<E::G2>(
+                            scalar_bits,
+                            g2_window,
+                            g2_generator,
+                        );
+                        (g2_window, table)
+                    });
+                    let bases = FixedBase::msm::<E::G2>(
+                        scalar_bits,
+                        g2_table.0,
+                        &g2_table.1,
+                        &b[start..end],
+                    );
+                    for base in E::G2::normalize_batch(&bases) {
+                        base.serialize_uncompressed(&mut writer)?;
+                    }
+                } else {
+                    let scalars = match query {
+                        Query::A => &a,
+                        Query::BG1 => &b,
+                        Query::H => &h,
+                        _ => &l,
+                    };
+                    for base in g1_bases(&scalars[start..end]) {
+                        base.serialize_uncompressed(&mut writer)?;
+                    }
+                }
+                writer.flush()?;
+                drop(writer);
+                file.sync_data()?;
+
+                checkpoint.query = i;
+                checkpoint.bases_written = end;
+                checkpoint.save(&checkpoint_path)?;
+                start = end;
+
+                let progress = progress(&SetupProgress {
+                    query,
+                    bases_written: end,
+                    num_bases,
+                });
+                if !progress {
+                    return Ok(None);
+                }
+            }
+            end_timer!(query_time);
+        }
+
+        file.set_len(offset)?;
+        file.sync_all()?;
+        fs::remove_file(&checkpoint_path)?;
+        end_timer!(setup_time);
+
+        Ok(Some(vk))
+    }
+}
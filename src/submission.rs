@@ -0,0 +1,342 @@
+//! A commit-reveal protocol for contests in which the first solver of each
+//! distinct solution wins.
+//!
+//! A proof posted in the clear can be copied or front-run before the
+//! organizer records it. Instead, provers first submit a binding and hiding
+//! commitment to their proof and id, which the organizer records with a
+//! timestamp. Once the commitments are closed, provers reveal them, and the
+//! organizer checks every reveal against its commitment and verifies the
+//! proof under the randomizer of the contest.
+//!
+//! Each distinct solution is awarded to its earliest commitment. Solutions
+//! are told apart by a nullifier, which the circuit of the contest must
+//! expose as its last public input, after those of the contest, and compute
+//! from the solution alone, e.g. as a collision-resistant hash of it. Unlike
+//! the proof elements, which anyone can rescale into another valid proof,
+//! the nullifier is fixed by the solution, so the same solution cannot be
+//! awarded twice.
+
+use crate::{
+    prepare_verifying_key, Groth16, PreparedVerifyingKey, Proof, Randomizer, VerifyingKey,
+};
+use ark_ec::pairing::Pairing;
+use ark_relations::r1cs::SynthesisError;
+use ark_serialize::*;
+use ark_std::{rand::RngCore, vec::Vec};
+use sha2::{Digest, Sha256};
+
+/// The domain separation tag of submission commitments.
+const SUBMISSION_DOMAIN: &[u8] = b"polymorphic-groth16-submission";
+
+/// An error of the submission protocol.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SubmissionError {
+    /// The message is not accepted in the current phase of the contest.
+    WrongPhase(ContestPhase),
+    /// The commitment was already submitted.
+    DuplicateCommitment,
+    /// The reveal opens no submitted commitment.
+    UnknownCommitment,
+    /// The commitment was already revealed.
+    AlreadyRevealed,
+    /// The revealed proof does not verify.
+    InvalidProof,
+    /// The public inputs do not match the verifying key.
+    Synthesis(SynthesisError),
+}
+
+impl From<SynthesisError> for SubmissionError {
+    fn from(e: SynthesisError) -> Self {
+        Self::Synthesis(e)
+    }
+}
+
+impl core::fmt::Display for SubmissionError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::WrongPhase(phase) => write!(f, "not accepted in the {:?} phase", phase),
+            Self::DuplicateCommitment => write!(f, "the commitment was already submitted"),
+            Self::UnknownCommitment => write!(f, "the reveal opens no submitted commitment"),
+            Self::AlreadyRevealed => write!(f, "the commitment was already revealed"),
+            Self::InvalidProof => write!(f, "the revealed proof is invalid"),
+            Self::Synthesis(e) => e.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SubmissionError {}
+
+/// The phase of a [`Contest`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContestPhase {
+    /// Commitments are accepted.
+    Commit,
+    /// Commitments are closed, and reveals are accepted.
+    Reveal,
+    /// Reveals are closed, and the awards are final.
+    Closed,
+}
+
+impl ContestPhase {
+    fn to_u8(self) -> u8 {
+        match self {
+            Self::Commit => 0,
+            Self::Reveal => 1,
+            Self::Closed => 2,
+        }
+    }
+}
+
+impl CanonicalSerialize for ContestPhase {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        self.to_u8().serialize_with_mode(writer, compress)
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        self.to_u8().serialized_size(compress)
+    }
+}
+
+impl Valid for ContestPhase {
+    fn check(&self) -> Result<(), SerializationError> {
+        Ok(())
+    }
+}
+
+impl CanonicalDeserialize for ContestPhase {
+    fn deserialize_with_mode<R: Read>(
+        reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        match u8::deserialize_with_mode(reader, compress, validate)? {
+            0 => Ok(Self::Commit),
+            1 => Ok(Self::Reveal),
+            2 => Ok(Self::Closed),
+            _ => Err(SerializationError::InvalidData),
+        }
+    }
+}
+
+/// A commitment to a proof and the id of its prover.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, CanonicalSerialize, CanonicalDeserialize)]
+pub struct SubmissionCommitment(pub [u8; 32]);
+
+/// The opening of a [`SubmissionCommitment`].
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Reveal<E: Pairing> {
+    /// The proof of the solution.
+    pub proof: Proof<E>,
+    /// The nullifier of the solution, the last public input of the proof.
+    pub nullifier: E::ScalarField,
+    /// The id of the prover, to whom the solution is awarded.
+    pub prover_id: Vec<u8>,
+    /// The random salt hiding the proof in the commitment.
+    pub salt: [u8; 32],
+}
+
+impl<E: Pairing> Reveal<E> {
+    /// Salt the submission of `proof`, whose solution has the nullifier
+    /// `nullifier`, by `prover_id`.
+    pub fn new<R: RngCore>(
+        proof: Proof<E>,
+        nullifier: E::ScalarField,
+        prover_id: Vec<u8>,
+        rng: &mut R,
+    ) -> Self {
+        let mut salt = [0u8; 32];
+        rng.fill_bytes(&mut salt);
+        Self {
+            proof,
+            nullifier,
+            prover_id,
+            salt,
+        }
+    }
+
+    /// The commitment to submit in the first phase.
+    pub fn commitment(&self) -> SubmissionCommitment {
+        let mut proof = Vec::new();
+        self.proof
+            .serialize_compressed(&mut proof)
+            .and_then(|_| self.nullifier.serialize_compressed(&mut proof))
+            .expect("serializing into a vector cannot fail");
+        let digest = Sha256::new()
+            .chain_update(SUBMISSION_DOMAIN)
+            .chain_update(self.salt)
+            .chain_update((self.prover_id.len() as u64).to_le_bytes())
+            .chain_update(&self.prover_id)
+            .chain_update(proof)
+            .finalize();
+        SubmissionCommitment(digest.into())
+    }
+}
+
+/// A commitment recorded by the organizer, and its opening once revealed.
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Submission<E: Pairing> {
+    /// The commitment.
+    pub commitment: SubmissionCommitment,
+    /// The time at which the commitment was recorded.
+    pub timestamp: u64,
+    /// The opening of the commitment, once revealed and verified.
+    pub reveal: Option<Reveal<E>>,
+}
+
+/// A solution awarded to the prover of its earliest commitment.
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Award<E: Pairing> {
+    /// The id of the winning prover.
+    pub prover_id: Vec<u8>,
+    /// The time at which the winning commitment was recorded.
+    pub timestamp: u64,
+    /// The proof of the solution.
+    pub proof: Proof<E>,
+    /// The nullifier of the solution.
+    pub nullifier: E::ScalarField,
+}
+
+/// The state of a contest kept by its organizer: the statement and the public
+/// part of the randomizer under which proofs are verified, and the
+/// submissions in the order in which they were committed.
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Contest<E: Pairing> {
+    pvk: PreparedVerifyingKey<E>,
+    p: E::G1Affine,
+    q: E::G1Affine,
+    public_inputs: Vec<E::ScalarField>,
+    phase: ContestPhase,
+    submissions: Vec<Submission<E>>,
+}
+
+impl<E: Pairing> Contest<E> {
+    /// Open the commitments of a contest for proofs of `public_inputs`, then
+    /// the nullifier of their solution, under `vk` and the randomizer `rnd`,
+    /// of which only `P` and `Q` are kept.
+    pub fn new(
+        vk: &VerifyingKey<E>,
+        rnd: &Randomizer<E>,
+        public_inputs: Vec<E::ScalarField>,
+    ) -> Self {
+        Self {
+            pvk: prepare_verifying_key(vk),
+            p: rnd.p,
+            q: rnd.q,
+            public_inputs,
+            phase: ContestPhase::Commit,
+            submissions: Vec::new(),
+        }
+    }
+
+    /// The current phase.
+    pub fn phase(&self) -> ContestPhase {
+        self.phase
+    }
+
+    /// The submissions, in the order in which they were committed.
+    pub fn submissions(&self) -> &[Submission<E>] {
+        &self.submissions
+    }
+
+    fn expect_phase(&self, phase: ContestPhase) -> Result<(), SubmissionError> {
+        if self.phase != phase {
+            return Err(SubmissionError::WrongPhase(self.phase));
+        }
+        Ok(())
+    }
+
+    /// Record `commitment` at `timestamp`.
+    pub fn commit(
+        &mut self,
+        commitment: SubmissionCommitment,
+        timestamp: u64,
+    ) -> Result<(), SubmissionError> {
+        self.expect_phase(ContestPhase::Commit)?;
+        if self.submissions.iter().any(|s| s.commitment == commitment) {
+            return Err(SubmissionError::DuplicateCommitment);
+        }
+        self.submissions.push(Submission {
+            commitment,
+            timestamp,
+            reveal: None,
+        });
+        Ok(())
+    }
+
+    /// Close the commitments and open the reveals.
+    pub fn close_commitments(&mut self) -> Result<(), SubmissionError> {
+        self.expect_phase(ContestPhase::Commit)?;
+        self.phase = ContestPhase::Reveal;
+        Ok(())
+    }
+
+    /// Check `reveal` against its commitment, verify its proof, and record it.
+    pub fn reveal(&mut self, reveal: Reveal<E>) -> Result<(), SubmissionError> {
+        self.expect_phase(ContestPhase::Reveal)?;
+        let commitment = reveal.commitment();
+        let position = self
+            .submissions
+            .iter()
+            .position(|s| s.commitment == commitment)
+            .ok_or(SubmissionError::UnknownCommitment)?;
+        if self.submissions[position].reveal.is_some() {
+            return Err(SubmissionError::AlreadyRevealed);
+        }
+
+        let rnd = Randomizer {
+            p: self.p,
+            q: self.q,
+            ..Randomizer::default()
+        };
+        let mut public_inputs = self.public_inputs.clone();
+        public_inputs.push(reveal.nullifier);
+        if !Groth16::<E>::verify_proof(&self.pvk, &reveal.proof, &rnd, &public_inputs)? {
+            return Err(SubmissionError::InvalidProof);
+        }
+        self.submissions[position].reveal = Some(reveal);
+        Ok(())
+    }
+
+    /// Close the reveals, and award each distinct nullifier to its earliest
+    /// revealed commitment, ties broken by the order of commitment.
+    pub fn close(&mut self) -> Result<Vec<Award<E>>, SubmissionError> {
+        self.expect_phase(ContestPhase::Reveal)?;
+        self.phase = ContestPhase::Closed;
+        Ok(self.awards())
+    }
+
+    /// The awards of a closed contest, or none before it is closed.
+    pub fn awards(&self) -> Vec<Award<E>> {
+        if self.phase != ContestPhase::Closed {
+            return Vec::new();
+        }
+        let mut revealed: Vec<_> = self
+            .submissions
+            .iter()
+            .filter_map(|s| s.reveal.as_ref().map(|reveal| (s.timestamp, reveal)))
+            .collect();
+        // stable, so that ties keep the order of commitment
+        revealed.sort_by_key(|(timestamp, _)| *timestamp);
+
+        let mut awards: Vec<Award<E>> = Vec::new();
+        for (timestamp, reveal) in revealed {
+            let duplicate = awards
+                .iter()
+                .any(|award| award.nullifier == reveal.nullifier);
+            if !duplicate {
+                awards.push(Award {
+                    prover_id: reveal.prover_id.clone(),
+                    timestamp,
+                    proof: reveal.proof.clone(),
+                    nullifier: reveal.nullifier,
+                });
+            }
+        }
+        awards
+    }
+}
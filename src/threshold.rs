@@ -0,0 +1,501 @@
+//! Generate a randomizer jointly, so that no single party knows `r` or `s`.
+//!
+//! Each of the `n` parties samples additive shares `r_i` and `s_i`, and the
+//! public part of the randomizer is computed in the exponent, in three
+//! rounds of broadcast messages:
+//!
+//! 1. Every party commits to the message of the second round, so that no
+//!    party can choose its shares after seeing those of the others.
+//! 2. Every party opens its commitment, revealing `s_i * delta * G`,
+//!    `(r_i - s_i) * alpha * G`, and `r_i * H` and `s_i * H` in `E::G2`, by
+//!    which the others check the first two by pairings. The sum of the
+//!    first is `S = s * delta * G`, and that of the second is `Q`.
+//! 3. Every party sends `r_i * S`, checked against `r_i * H`, whose sum is
+//!    `P = r * s * delta * G`.
+//!
+//! The output is the [`PublicRandomizer`], with which verifiers check proofs
+//! and issuers sign it. The shares never leave their parties, so none of
+//! them can prove under it alone.
+//!
+//! Instead, a [`ThresholdProver`] holding a witness proves under it jointly
+//! with all the parties, in one more round. It computes `A` and `B` over the
+//! witness before randomization, `A_0` and `B_0`, and sends them in a
+//! [`ProofRequest`]. Every party replies with `r_i * delta * G`,
+//! `s_i * delta * H` and `s_i * A_0 + r_i * B_0` in a [`ProofShare`], which
+//! the prover checks by pairings against the messages of the second round.
+//! The sums of the first two randomize `A` and `B`, and as
+//! `s * A + r * B = s * A_0 + r * B_0 + 2 * P`, that of the third finishes
+//! `C`. The parties learn `A_0` and `B_0`, by which they could test guesses
+//! of the witness, but neither `r` nor `s` is ever known to anyone.
+
+use crate::{
+    r1cs_to_qap::R1CSToQAP, secret::SecretScalar, streaming::ProverConfig, Groth16, Proof,
+    ProvingKey, PublicRandomizer,
+};
+use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup, Group};
+use ark_ff::{UniformRand, Zero};
+use ark_poly::EvaluationDomain;
+use ark_relations::r1cs::{ConstraintSynthesizer, Result as R1CSResult};
+use ark_serialize::*;
+use ark_std::{
+    ops::Neg,
+    rand::{CryptoRng, RngCore},
+    vec::Vec,
+};
+use sha2::{Digest, Sha256};
+
+/// The domain separation tag of the commitments of the first round.
+const THRESHOLD_DOMAIN: &[u8] = b"polymorphic-groth16-threshold";
+
+/// An error of the joint generation of a randomizer.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ThresholdError {
+    /// There are fewer than two parties, or a party index is out of range.
+    InvalidParties,
+    /// The messages of a round are not exactly one per party.
+    MissingMessages,
+    /// A message was sent out of order.
+    UnexpectedRound,
+    /// The message of the second round of the party does not open its
+    /// commitment.
+    CommitmentMismatch(u32),
+    /// The shares revealed by the party are inconsistent.
+    InvalidShare(u32),
+    /// `P` or `Q` is the identity.
+    UnexpectedIdentity,
+    /// The messages of the second round are not those of the randomizer.
+    RandomizerMismatch,
+}
+
+impl core::fmt::Display for ThresholdError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::InvalidParties => write!(f, "invalid number of parties or party index"),
+            Self::MissingMessages => write!(f, "expected exactly one message per party"),
+            Self::UnexpectedRound => write!(f, "message sent out of order"),
+            Self::CommitmentMismatch(i) => write!(f, "party {} did not open its commitment", i),
+            Self::InvalidShare(i) => write!(f, "party {} sent inconsistent shares", i),
+            Self::UnexpectedIdentity => write!(f, "the randomizer is the identity"),
+            Self::RandomizerMismatch => write!(f, "the shares are not those of the randomizer"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ThresholdError {}
+
+/// The message of the first round: a commitment to the [`ShareMessage`] of
+/// the party.
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct CommitMessage {
+    /// The index of the sender.
+    pub party: u32,
+    /// The SHA-256 commitment to the share message.
+    pub commitment: [u8; 32],
+}
+
+/// The message of the second round, opening the commitment of the first.
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct ShareMessage<E: Pairing> {
+    /// The index of the sender.
+    pub party: u32,
+    /// The element `s_i * delta * G` in `E::G1`.
+    pub s_delta_g1: E::G1Affine,
+    /// The element `(r_i - s_i) * alpha * G` in `E::G1`.
+    pub q_share: E::G1Affine,
+    /// The element `r_i * H` in `E::G2`.
+    pub r_g2: E::G2Affine,
+    /// The element `s_i * H` in `E::G2`.
+    pub s_g2: E::G2Affine,
+}
+
+impl<E: Pairing> ShareMessage<E> {
+    fn commitment(&self) -> [u8; 32] {
+        let mut bytes = Vec::new();
+        self.serialize_compressed(&mut bytes)
+            .expect("serializing into a vector cannot fail");
+        Sha256::new()
+            .chain_update(THRESHOLD_DOMAIN)
+            .chain_update(bytes)
+            .finalize()
+            .into()
+    }
+}
+
+/// The message of the third round.
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct ProductMessage<E: Pairing> {
+    /// The index of the sender.
+    pub party: u32,
+    /// The element `r_i * s * delta * G` in `E::G1`.
+    pub p_share: E::G1Affine,
+}
+
+/// The request of a [`ThresholdProver`] to the parties: `A` and `B` over the
+/// witness, before randomization.
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct ProofRequest<E: Pairing> {
+    /// The element `A_0 = alpha * G + sum_i a_i * a_query[i]` in `E::G1`.
+    pub a: E::G1Affine,
+    /// The element `B_0 = beta * G + sum_i b_i * b_g1_query[i]` in `E::G1`.
+    pub b_g1: E::G1Affine,
+}
+
+/// The reply of a party to a [`ProofRequest`].
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct ProofShare<E: Pairing> {
+    /// The index of the sender.
+    pub party: u32,
+    /// The element `r_i * delta * G` in `E::G1`.
+    pub r_delta_g1: E::G1Affine,
+    /// The element `s_i * delta * H` in `E::G2`.
+    pub s_delta_g2: E::G2Affine,
+    /// The element `s_i * A_0 + r_i * B_0` in `E::G1`.
+    pub c_share: E::G1Affine,
+}
+
+/// Sort `messages` by party, checking that there is exactly one per party.
+fn sorted<M: Clone>(
+    messages: &[M],
+    parties: u32,
+    party: impl Fn(&M) -> u32,
+) -> Result<Vec<M>, ThresholdError> {
+    let mut sorted = messages.to_vec();
+    sorted.sort_by_key(&party);
+    if sorted.len() != parties as usize
+        || sorted.iter().enumerate().any(|(i, m)| party(m) != i as u32)
+    {
+        return Err(ThresholdError::MissingMessages);
+    }
+    Ok(sorted)
+}
+
+/// One of the parties jointly generating a randomizer, holding its shares
+/// `r_i` and `s_i` and the messages received so far.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ThresholdParty<E: Pairing> {
+    party: u32,
+    parties: u32,
+    alpha_g1: E::G1Affine,
+    delta_g1: E::G1Affine,
+    delta_g2: E::G2Affine,
+    r: SecretScalar<E::ScalarField>,
+    s: SecretScalar<E::ScalarField>,
+    commitments: Vec<CommitMessage>,
+    shares: Vec<ShareMessage<E>>,
+    s_delta_g1: E::G1Affine,
+}
+
+impl<E: Pairing> ThresholdParty<E> {
+    /// Sample the shares of the party with index `party` out of `parties`,
+    /// for the proving key `pk`.
+    pub fn new<R: RngCore + CryptoRng>(
+        pk: &ProvingKey<E>,
+        party: u32,
+        parties: u32,
+        rng: &mut R,
+    ) -> Result<Self, ThresholdError> {
+        if parties < 2 || party >= parties {
+            return Err(ThresholdError::InvalidParties);
+        }
+        Ok(Self {
+            party,
+            parties,
+            alpha_g1: pk.vk.alpha_g1,
+            delta_g1: pk.delta_g1,
+            delta_g2: pk.vk.delta_g2,
+            r: E::ScalarField::rand(rng).into(),
+            s: E::ScalarField::rand(rng).into(),
+            commitments: Vec::new(),
+            shares: Vec::new(),
+            s_delta_g1: E::G1Affine::zero(),
+        })
+    }
+
+    /// The index of the party.
+    pub fn party(&self) -> u32 {
+        self.party
+    }
+
+    /// The checked messages of the second round of all the parties, once
+    /// this one has multiplied, which a [`ThresholdProver`] checks the
+    /// [`ProofShare`]s against.
+    pub fn shares(&self) -> &[ShareMessage<E>] {
+        &self.shares
+    }
+
+    fn share_message(&self) -> ShareMessage<E> {
+        let h = E::G2Affine::generator();
+        ShareMessage {
+            party: self.party,
+            s_delta_g1: (self.delta_g1 * self.s.expose()).into_affine(),
+            q_share: (self.alpha_g1 * (*self.r.expose() - self.s.expose())).into_affine(),
+            r_g2: (h * self.r.expose()).into_affine(),
+            s_g2: (h * self.s.expose()).into_affine(),
+        }
+    }
+
+    /// The first round: commit to the shares.
+    pub fn commit(&self) -> CommitMessage {
+        CommitMessage {
+            party: self.party,
+            commitment: self.share_message().commitment(),
+        }
+    }
+
+    /// The second round: open the shares, given the commitments of all the
+    /// parties, this one included.
+    pub fn reveal(
+        &mut self,
+        commitments: &[CommitMessage],
+    ) -> Result<ShareMessage<E>, ThresholdError> {
+        if !self.commitments.is_empty() {
+            return Err(ThresholdError::UnexpectedRound);
+        }
+        self.commitments = sorted(commitments, self.parties, |m| m.party)?;
+        Ok(self.share_message())
+    }
+
+    /// The third round: check the shares of all the parties against their
+    /// commitments, and multiply their sum `s * delta * G` by `r_i`.
+    pub fn multiply(
+        &mut self,
+        shares: &[ShareMessage<E>],
+    ) -> Result<ProductMessage<E>, ThresholdError> {
+        if self.commitments.is_empty() || !self.shares.is_empty() {
+            return Err(ThresholdError::UnexpectedRound);
+        }
+        let shares = sorted(shares, self.parties, |m| m.party)?;
+        let h = E::G2Affine::generator();
+        for (share, commitment) in shares.iter().zip(&self.commitments) {
+            if share.commitment() != commitment.commitment {
+                return Err(ThresholdError::CommitmentMismatch(share.party));
+            }
+            let r_sub_s_g2 = (share.r_g2.into_group() - share.s_g2).into_affine();
+            let consistent = E::multi_pairing(
+                [
+                    share.s_delta_g1,
+                    self.delta_g1.into_group().neg().into_affine(),
+                ],
+                [h, share.s_g2],
+            )
+            .is_zero()
+                && E::multi_pairing(
+                    [
+                        share.q_share,
+                        self.alpha_g1.into_group().neg().into_affine(),
+                    ],
+                    [h, r_sub_s_g2],
+                )
+                .is_zero();
+            if !consistent {
+                return Err(ThresholdError::InvalidShare(share.party));
+            }
+        }
+
+        self.s_delta_g1 = shares
+            .iter()
+            .map(|share| share.s_delta_g1)
+            .sum::<E::G1>()
+            .into_affine();
+        self.shares = shares;
+        Ok(ProductMessage {
+            party: self.party,
+            p_share: (self.s_delta_g1 * self.r.expose()).into_affine(),
+        })
+    }
+
+    /// Check the products of all the parties, and sum them and the shares of
+    /// `Q` into the public part of the randomizer.
+    pub fn finish(
+        &self,
+        products: &[ProductMessage<E>],
+    ) -> Result<PublicRandomizer<E>, ThresholdError> {
+        if self.shares.is_empty() {
+            return Err(ThresholdError::UnexpectedRound);
+        }
+        let products = sorted(products, self.parties, |m| m.party)?;
+        let h = E::G2Affine::generator();
+        for (product, share) in products.iter().zip(&self.shares) {
+            if !E::multi_pairing(
+                [
+                    product.p_share,
+                    self.s_delta_g1.into_group().neg().into_affine(),
+                ],
+                [h, share.r_g2],
+            )
+            .is_zero()
+            {
+                return Err(ThresholdError::InvalidShare(product.party));
+            }
+        }
+
+        let p = products.iter().map(|m| m.p_share).sum::<E::G1>();
+        let q = self.shares.iter().map(|m| m.q_share).sum::<E::G1>();
+        if p.is_zero() || q.is_zero() {
+            return Err(ThresholdError::UnexpectedIdentity);
+        }
+        Ok(PublicRandomizer {
+            p: p.into_affine(),
+            q: q.into_affine(),
+        })
+    }
+
+    /// The round of joint proving: reply to the `request` of a
+    /// [`ThresholdProver`] with the contributions of the shares, once this
+    /// party has multiplied.
+    pub fn prove_share(&self, request: &ProofRequest<E>) -> Result<ProofShare<E>, ThresholdError> {
+        if self.shares.is_empty() {
+            return Err(ThresholdError::UnexpectedRound);
+        }
+        let mut c_share = request.a * self.s.expose();
+        c_share += request.b_g1 * self.r.expose();
+        Ok(ProofShare {
+            party: self.party,
+            r_delta_g1: (self.delta_g1 * self.r.expose()).into_affine(),
+            s_delta_g2: (self.delta_g2 * self.s.expose()).into_affine(),
+            c_share: c_share.into_affine(),
+        })
+    }
+}
+
+/// A prover holding a witness, which proves under a jointly generated
+/// randomizer with the [`ProofShare`]s of all its parties.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ThresholdProver<E: Pairing> {
+    rnd: PublicRandomizer<E>,
+    delta_g1: E::G1Affine,
+    delta_g2: E::G2Affine,
+    a: E::G1Affine,
+    b_g1: E::G1Affine,
+    b_g2: E::G2Affine,
+    c: E::G1,
+}
+
+impl<E: Pairing> ThresholdProver<E> {
+    /// The request to send to every party.
+    pub fn request(&self) -> ProofRequest<E> {
+        ProofRequest {
+            a: self.a,
+            b_g1: self.b_g1,
+        }
+    }
+
+    /// Check the replies of all the parties against their messages `shares`
+    /// of the second round, and finish the proof.
+    pub fn finish(
+        &self,
+        shares: &[ShareMessage<E>],
+        replies: &[ProofShare<E>],
+    ) -> Result<Proof<E>, ThresholdError> {
+        if shares.len() < 2 {
+            return Err(ThresholdError::InvalidParties);
+        }
+        let parties = shares.len() as u32;
+        let shares = sorted(shares, parties, |m| m.party)?;
+        let replies = sorted(replies, parties, |m| m.party)?;
+        if shares.iter().map(|m| m.q_share).sum::<E::G1>() != self.rnd.q.into_group() {
+            return Err(ThresholdError::RandomizerMismatch);
+        }
+
+        let h = E::G2Affine::generator();
+        let neg_delta_g1 = self.delta_g1.into_group().neg().into_affine();
+        for (reply, share) in replies.iter().zip(&shares) {
+            let consistent = E::multi_pairing([reply.r_delta_g1, neg_delta_g1], [h, share.r_g2])
+                .is_zero()
+                && E::multi_pairing(
+                    [
+                        self.delta_g1,
+                        share.s_delta_g1.into_group().neg().into_affine(),
+                    ],
+                    [reply.s_delta_g2, self.delta_g2],
+                )
+                .is_zero()
+                && E::multi_pairing(
+                    [
+                        reply.c_share,
+                        self.a.into_group().neg().into_affine(),
+                        self.b_g1.into_group().neg().into_affine(),
+                    ],
+                    [h, share.s_g2, share.r_g2],
+                )
+                .is_zero();
+            if !consistent {
+                return Err(ThresholdError::InvalidShare(reply.party));
+            }
+        }
+
+        let a = self.a + replies.iter().map(|m| m.r_delta_g1).sum::<E::G1>();
+        let b = self.b_g2 + replies.iter().map(|m| m.s_delta_g2).sum::<E::G2>();
+        let mut c = replies.iter().map(|m| m.c_share).sum::<E::G1>();
+        c += self.rnd.p.into_group().double();
+        c += self.rnd.q;
+        c += self.c;
+        Ok(Proof {
+            a: a.into_affine(),
+            b: b.into_affine(),
+            c: c.into_affine(),
+        })
+    }
+}
+
+impl<E: Pairing, QAP: R1CSToQAP, D: EvaluationDomain<E::ScalarField>> Groth16<E, QAP, D> {
+    /// Run the joint generation of a randomizer for `pk` among `parties`
+    /// parties in process, e.g. for tests, returning the randomizer and the
+    /// parties, which hold the shares.
+    pub fn create_threshold_randomizer<R: RngCore + CryptoRng>(
+        pk: &ProvingKey<E>,
+        parties: u32,
+        rng: &mut R,
+    ) -> Result<(PublicRandomizer<E>, Vec<ThresholdParty<E>>), ThresholdError> {
+        if parties < 2 {
+            return Err(ThresholdError::InvalidParties);
+        }
+        let mut parties = (0..parties)
+            .map(|i| ThresholdParty::new(pk, i, parties, rng))
+            .collect::<Result<Vec<_>, _>>()?;
+        let commitments: Vec<_> = parties.iter().map(ThresholdParty::commit).collect();
+        let shares = parties
+            .iter_mut()
+            .map(|party| party.reveal(&commitments))
+            .collect::<Result<Vec<_>, _>>()?;
+        let products = parties
+            .iter_mut()
+            .map(|party| party.multiply(&shares))
+            .collect::<Result<Vec<_>, _>>()?;
+        let rnd = parties[0].finish(&products)?;
+        Ok((rnd, parties))
+    }
+
+    /// Start proving `circuit` under the jointly generated randomizer `rnd`,
+    /// whose shares are held by the parties. Fails if `pk` commits to some
+    /// witnesses.
+    pub fn create_threshold_prover<C>(
+        circuit: C,
+        pk: &ProvingKey<E>,
+        rnd: &PublicRandomizer<E>,
+    ) -> R1CSResult<ThresholdProver<E>>
+    where
+        C: ConstraintSynthesizer<E::ScalarField>,
+    {
+        Self::check_no_commitment(pk)?;
+        let (h, instance_assignment, witness_assignment) = Self::synthesize::<C>(circuit)?;
+        let acc = Self::accumulate_msms(
+            pk,
+            &ProverConfig::UNBOUNDED,
+            true,
+            &h,
+            &instance_assignment[1..],
+            &witness_assignment,
+        )?;
+        Ok(ThresholdProver {
+            rnd: rnd.clone(),
+            delta_g1: pk.delta_g1,
+            delta_g2: pk.vk.delta_g2,
+            a: (acc.a + pk.vk.alpha_g1).into_affine(),
+            b_g1: (acc.b_g1 + pk.beta_g1).into_affine(),
+            b_g2: (acc.b_g2 + pk.vk.beta_g2).into_affine(),
+            c: acc.c,
+        })
+    }
+}
@@ -1,7 +1,14 @@
-use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup};
+use ark_ec::{
+    pairing::Pairing, scalar_mul::fixed_base::FixedBase, AffineRepr, CurveGroup, VariableBaseMSM,
+};
 use ark_ff::PrimeField;
+use ark_poly::EvaluationDomain;
+use ark_std::cfg_iter;
 
-use crate::{r1cs_to_qap::R1CSToQAP, Groth16, Randomizer};
+use crate::{
+    r1cs_to_qap::R1CSToQAP, CommitmentOpeningProof, CommittedProof, GammaAbcTables, Groth16,
+    Randomizer,
+};
 
 use super::{PreparedVerifyingKey, Proof, VerifyingKey};
 
@@ -10,6 +17,12 @@ use ark_relations::r1cs::{Result as R1CSResult, SynthesisError};
 use core::ops::{AddAssign, Neg};
 use std::collections::HashSet;
 
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// The widest window of the tables of a [`PreparedVerifyingKey`].
+const MAX_TABLE_WINDOW: usize = 16;
+
 /// Prepare the verifying key `vk` for use in proof verification.
 pub fn prepare_verifying_key<E: Pairing>(vk: &VerifyingKey<E>) -> PreparedVerifyingKey<E> {
     PreparedVerifyingKey {
@@ -17,10 +30,66 @@ pub fn prepare_verifying_key<E: Pairing>(vk: &VerifyingKey<E>) -> PreparedVerify
         alpha_g1_beta_g2: E::pairing(vk.alpha_g1, vk.beta_g2).0,
         gamma_g2_neg_pc: vk.gamma_g2.into_group().neg().into_affine().into(),
         delta_g2_neg_pc: vk.delta_g2.into_group().neg().into_affine().into(),
+        gamma_abc_g1_tables: None,
     }
 }
 
-impl<E: Pairing, QAP: R1CSToQAP> Groth16<E, QAP> {
+/// Prepare the verifying key `vk` for use in proof verification, with window
+/// tables of `window` bits for every public input.
+///
+/// # Panics
+/// Panics if `window` is zero or wider than 16 bits.
+pub fn prepare_verifying_key_with_tables<E: Pairing>(
+    vk: &VerifyingKey<E>,
+    window: usize,
+) -> PreparedVerifyingKey<E> {
+    assert!(window > 0, "window tables need a non-empty window");
+    assert!(
+        window <= MAX_TABLE_WINDOW,
+        "window tables are at most 16 bits wide"
+    );
+    let scalar_bits = E::ScalarField::MODULUS_BIT_SIZE as usize;
+    let bases = vk.gamma_abc_g1.get(1..).unwrap_or_default();
+    let tables = cfg_iter!(bases)
+        .map(|base| FixedBase::get_window_table(scalar_bits, window, base.into_group()))
+        .collect();
+
+    PreparedVerifyingKey {
+        gamma_abc_g1_tables: Some(GammaAbcTables { window, tables }),
+        ..prepare_verifying_key(vk)
+    }
+}
+
+/// Prepare the verifying key `vk` for use in proof verification, building
+/// window tables for the public inputs if they fit in `memory_budget` bytes
+/// and are faster than a single MSM over the inputs.
+pub fn prepare_verifying_key_with_memory_budget<E: Pairing>(
+    vk: &VerifyingKey<E>,
+    memory_budget: usize,
+) -> PreparedVerifyingKey<E> {
+    let num_inputs = vk.gamma_abc_g1.len().saturating_sub(1);
+
+    // An MSM over `n` bases adds each of them once per window of about
+    // `ln(n) + 2` bits, and pays for its buckets and doublings on top, so the
+    // tables win as soon as their windows are wider.
+    let msm_window = if num_inputs < 32 {
+        3
+    } else {
+        ark_std::log2(num_inputs) as usize * 69 / 100 + 2
+    };
+    let window = (1..=MAX_TABLE_WINDOW)
+        .rev()
+        .find(|window| GammaAbcTables::<E>::size_in_bytes(num_inputs, *window) <= memory_budget);
+
+    match window {
+        Some(window) if num_inputs > 0 && window > msm_window => {
+            prepare_verifying_key_with_tables(vk, window)
+        },
+        _ => prepare_verifying_key(vk),
+    }
+}
+
+impl<E: Pairing, QAP: R1CSToQAP, D: EvaluationDomain<E::ScalarField>> Groth16<E, QAP, D> {
     /// Prepare proof inputs for use with [`verify_proof_with_prepared_inputs`], wrt the prepared
     /// verification key `pvk` and instance public inputs.
     pub fn prepare_inputs(
@@ -32,8 +101,32 @@ impl<E: Pairing, QAP: R1CSToQAP> Groth16<E, QAP> {
         }
 
         let mut g_ic = pvk.vk.gamma_abc_g1[0].into_group();
-        for (i, b) in public_inputs.iter().zip(pvk.vk.gamma_abc_g1.iter().skip(1)) {
-            g_ic.add_assign(&b.mul_bigint(i.into_bigint()));
+        match &pvk.gamma_abc_g1_tables {
+            Some(GammaAbcTables { window, tables }) => {
+                // The tables may come from a deserialized key, so their shape
+                // is checked before they are indexed.
+                if *window == 0 || *window > MAX_TABLE_WINDOW || tables.len() != public_inputs.len()
+                {
+                    return Err(SynthesisError::MalformedVerifyingKey);
+                }
+                let scalar_bits = E::ScalarField::MODULUS_BIT_SIZE as usize;
+                let outerc = scalar_bits.div_ceil(*window);
+                if tables.iter().any(|table| {
+                    table.len() != outerc || table.iter().any(|row| row.len() != 1 << window)
+                }) {
+                    return Err(SynthesisError::MalformedVerifyingKey);
+                }
+                g_ic += cfg_iter!(tables)
+                    .zip(public_inputs)
+                    .map(|(table, i)| FixedBase::windowed_mul::<E::G1>(outerc, *window, table, i))
+                    .sum::<E::G1>();
+            },
+            None => {
+                g_ic.add_assign(E::G1::msm_unchecked(
+                    &pvk.vk.gamma_abc_g1[1..],
+                    public_inputs,
+                ));
+            },
         }
 
         Ok(g_ic)
@@ -42,11 +135,69 @@ impl<E: Pairing, QAP: R1CSToQAP> Groth16<E, QAP> {
     /// Verify a Groth16 proof `proof` against the prepared verification key `pvk` and prepared public
     /// inputs. This should be preferred over [`verify_proof`] if the instance's public inputs are
     /// known in advance.
+    ///
+    /// Rejects every proof under a key that commits to some witnesses, whose
+    /// proofs are checked by [`Self::verify_committed_proof_with_prepared_inputs`].
     pub fn verify_proof_with_prepared_inputs(
         pvk: &PreparedVerifyingKey<E>,
         proof: &Proof<E>,
         rnd: &Randomizer<E>,
         prepared_inputs: &E::G1,
+    ) -> R1CSResult<bool> {
+        if !pvk.vk.commitment_bases_g1.is_empty() {
+            return Ok(false);
+        }
+        Self::verify_pairing(pvk, proof, rnd, prepared_inputs)
+    }
+
+    /// Verify a Groth16 proof `proof` against the prepared verification key `pvk`,
+    /// with respect to the instance `public_inputs`.
+    pub fn verify_proof(
+        pvk: &PreparedVerifyingKey<E>,
+        proof: &Proof<E>,
+        rnd: &Randomizer<E>,
+        public_inputs: &[E::ScalarField],
+    ) -> R1CSResult<bool> {
+        let prepared_inputs = Self::prepare_inputs(pvk, public_inputs)?;
+        Self::verify_proof_with_prepared_inputs(pvk, proof, rnd, &prepared_inputs)
+    }
+
+    /// Verify a proof `proof` with a commitment against the prepared
+    /// verification key `pvk` and prepared public inputs, along with the
+    /// proof of knowledge of the opening of its commitment.
+    pub fn verify_committed_proof_with_prepared_inputs(
+        pvk: &PreparedVerifyingKey<E>,
+        proof: &CommittedProof<E>,
+        rnd: &Randomizer<E>,
+        prepared_inputs: &E::G1,
+    ) -> R1CSResult<bool> {
+        if !Self::verify_commitment_opening(&pvk.vk, proof) {
+            return Ok(false);
+        }
+
+        // The commitment `D` pairs with `gamma` like the public inputs.
+        Self::verify_pairing(pvk, &proof.proof, rnd, &(*prepared_inputs + proof.d))
+    }
+
+    /// Verify a proof `proof` with a commitment against the prepared
+    /// verification key `pvk`, with respect to the instance `public_inputs`.
+    pub fn verify_committed_proof(
+        pvk: &PreparedVerifyingKey<E>,
+        proof: &CommittedProof<E>,
+        rnd: &Randomizer<E>,
+        public_inputs: &[E::ScalarField],
+    ) -> R1CSResult<bool> {
+        let prepared_inputs = Self::prepare_inputs(pvk, public_inputs)?;
+        Self::verify_committed_proof_with_prepared_inputs(pvk, proof, rnd, &prepared_inputs)
+    }
+
+    /// Checks the pairing equation of `proof` under `rnd`, where
+    /// `prepared_inputs` sums every element paired with `gamma`.
+    fn verify_pairing(
+        pvk: &PreparedVerifyingKey<E>,
+        proof: &Proof<E>,
+        rnd: &Randomizer<E>,
+        prepared_inputs: &E::G1,
     ) -> R1CSResult<bool> {
         let qap = E::multi_miller_loop(
             [
@@ -70,16 +221,38 @@ impl<E: Pairing, QAP: R1CSToQAP> Groth16<E, QAP> {
         Ok(test.0 == pvk.alpha_g1_beta_g2)
     }
 
-    /// Verify a Groth16 proof `proof` against the prepared verification key `pvk`,
-    /// with respect to the instance `public_inputs`.
-    pub fn verify_proof(
-        pvk: &PreparedVerifyingKey<E>,
-        proof: &Proof<E>,
-        rnd: &Randomizer<E>,
-        public_inputs: &[E::ScalarField],
-    ) -> R1CSResult<bool> {
-        let prepared_inputs = Self::prepare_inputs(pvk, public_inputs)?;
-        Self::verify_proof_with_prepared_inputs(pvk, proof, rnd, &prepared_inputs)
+    /// Verify the proof of knowledge of the opening of the commitment `D` of
+    /// `proof`.
+    pub fn verify_commitment_opening(vk: &VerifyingKey<E>, proof: &CommittedProof<E>) -> bool {
+        let opening = &proof.opening;
+        if vk.commitment_bases_g1.is_empty()
+            || opening.responses.len() != vk.commitment_bases_g1.len()
+        {
+            return false;
+        }
+
+        let Proof { a, b, c } = &proof.proof;
+        let challenge = CommitmentOpeningProof::challenge(vk, a, b, c, &proof.d, &opening.t);
+        let mut lhs = E::G1::msm_unchecked(&vk.commitment_bases_g1, &opening.responses);
+        lhs += vk.eta_gamma_inv_g1 * opening.blinding_response;
+        lhs == opening.t + proof.d * challenge
+    }
+
+    /// Check that the commitment `D` of `proof` opens to the committed
+    /// witnesses `values`, in the order of `vk.commitment_bases_g1`, under
+    /// `blinding`.
+    pub fn verify_commitment(
+        vk: &VerifyingKey<E>,
+        proof: &CommittedProof<E>,
+        values: &[E::ScalarField],
+        blinding: E::ScalarField,
+    ) -> bool {
+        if values.len() != vk.commitment_bases_g1.len() {
+            return false;
+        }
+        let mut commitment = E::G1::msm_unchecked(&vk.commitment_bases_g1, values);
+        commitment += vk.eta_gamma_inv_g1 * blinding;
+        commitment.into_affine() == proof.d
     }
 
     /// Comapre proofs
@@ -98,4 +271,21 @@ impl<E: Pairing, QAP: R1CSToQAP> Groth16<E, QAP> {
         Ok(result_a && result_b && result_c)
     }
 
+    /// Compare proofs with commitments by their elements, as
+    /// [`compare_proofs`](Self::compare_proofs) does, and by their
+    /// commitments `D`. Two proofs committing to the same witnesses are only
+    /// detected across randomizers if both commitments are unblinded, i.e.
+    /// made by [`create_committed_proof_with_blinding`](Self::create_committed_proof_with_blinding)
+    /// with a zero blinding.
+    pub fn compare_proofs_by_commitment(proofs: &[CommittedProof<E>]) -> R1CSResult<bool> {
+        let elements = proofs
+            .iter()
+            .map(|proof| proof.proof.clone())
+            .collect::<Vec<_>>();
+        let mut uniq = HashSet::new();
+        let result_d = proofs.iter().all(move |proof| uniq.insert(proof.d));
+
+        Ok(Self::compare_proofs(&elements)? && result_d)
+    }
+
 }
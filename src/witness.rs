@@ -0,0 +1,257 @@
+use ark_ff::PrimeField;
+use ark_relations::r1cs::ConstraintSystemRef;
+use ark_serialize::{Read, SerializationError, Write};
+use ark_std::{
+    string::{String, ToString},
+    vec::Vec,
+};
+
+/// The magic bytes opening a binary assignment file.
+pub const ASSIGNMENT_MAGIC: [u8; 4] = *b"PZKA";
+
+/// The version of the binary assignment format written by this crate.
+pub const ASSIGNMENT_VERSION: u8 = 1;
+
+/// A full assignment of a circuit, split as in a `ConstraintSystem`:
+/// `instance` starts with the constant `one`, followed by the public inputs,
+/// and `witness` holds the private witness values.
+///
+/// # Binary format
+///
+/// All integers are little-endian.
+///
+/// | bytes                | content                                         |
+/// |----------------------|-------------------------------------------------|
+/// | 4                    | magic `PZKA`                                    |
+/// | 1                    | format version, currently `1`                   |
+/// | 4                    | `n`, the byte length of one field element       |
+/// | 8                    | number of instance values                       |
+/// | 8                    | number of witness values                        |
+/// | `n` per value        | instance values, then witness values            |
+///
+/// Field elements use their uncompressed `CanonicalSerialize` encoding, which
+/// is the little-endian canonical integer representative.
+///
+/// # JSON format
+///
+/// An object with the keys `"instance"` and `"witness"`, each an array of
+/// field elements written as canonical decimal strings:
+///
+/// ```json
+/// {"instance": ["1", "3"], "witness": ["5", "0"]}
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Assignment<F: PrimeField> {
+    /// The instance assignment, including the leading constant `one`.
+    pub instance: Vec<F>,
+    /// The witness assignment.
+    pub witness: Vec<F>,
+}
+
+impl<F: PrimeField> Assignment<F> {
+    /// Copies the assignment out of a synthesized constraint system.
+    pub fn from_constraint_system(cs: &ConstraintSystemRef<F>) -> Option<Self> {
+        let cs = cs.borrow()?;
+        Some(Self {
+            instance: cs.instance_assignment.clone(),
+            witness: cs.witness_assignment.clone(),
+        })
+    }
+
+    /// The instance and witness concatenated, as indexed by constraint matrices.
+    pub fn full_assignment(&self) -> Vec<F> {
+        [self.instance.as_slice(), self.witness.as_slice()].concat()
+    }
+
+    /// Writes the assignment in the binary format.
+    pub fn write_binary<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        writer.write_all(&ASSIGNMENT_MAGIC)?;
+        writer.write_all(&[ASSIGNMENT_VERSION])?;
+        writer.write_all(&(F::zero().uncompressed_size() as u32).to_le_bytes())?;
+        writer.write_all(&(self.instance.len() as u64).to_le_bytes())?;
+        writer.write_all(&(self.witness.len() as u64).to_le_bytes())?;
+        for value in self.instance.iter().chain(&self.witness) {
+            value.serialize_uncompressed(&mut writer)?;
+        }
+        Ok(())
+    }
+
+    /// Reads an assignment in the binary format.
+    pub fn read_binary<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        let mut version = [0u8; 1];
+        reader.read_exact(&mut version)?;
+        if magic != ASSIGNMENT_MAGIC || version[0] != ASSIGNMENT_VERSION {
+            return Err(SerializationError::InvalidData);
+        }
+
+        let mut element_size = [0u8; 4];
+        reader.read_exact(&mut element_size)?;
+        if u32::from_le_bytes(element_size) as usize != F::zero().uncompressed_size() {
+            return Err(SerializationError::InvalidData);
+        }
+
+        let mut read_len = || -> Result<usize, SerializationError> {
+            let mut len = [0u8; 8];
+            reader.read_exact(&mut len)?;
+            usize::try_from(u64::from_le_bytes(len)).map_err(|_| SerializationError::InvalidData)
+        };
+        let num_instance = read_len()?;
+        let num_witness = read_len()?;
+
+        let mut read_values = |len: usize| {
+            (0..len)
+                .map(|_| F::deserialize_uncompressed(&mut reader))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let instance = read_values(num_instance)?;
+        let witness = read_values(num_witness)?;
+
+        Ok(Self { instance, witness })
+    }
+
+    /// Writes the assignment in the JSON format.
+    pub fn to_json(&self) -> String {
+        let values = |values: &[F]| {
+            values
+                .iter()
+                .map(|value| format!("\"{}\"", value.into_bigint()))
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        format!(
+            "{{\"instance\":[{}],\"witness\":[{}]}}",
+            values(&self.instance),
+            values(&self.witness)
+        )
+    }
+
+    /// Reads an assignment in the JSON format. Values must be canonical
+    /// decimal representatives, given either as strings or as bare numbers.
+    pub fn from_json(json: &str) -> Result<Self, SerializationError> {
+        let mut parser = JsonParser {
+            input: json.as_bytes(),
+            pos: 0,
+        };
+        let mut instance = None;
+        let mut witness = None;
+
+        parser.expect(b'{')?;
+        if !parser.eat(b'}') {
+            loop {
+                let key = parser.string()?;
+                parser.expect(b':')?;
+                let values = parser.values()?;
+                match key.as_str() {
+                    "instance" if instance.is_none() => instance = Some(values),
+                    "witness" if witness.is_none() => witness = Some(values),
+                    _ => return Err(SerializationError::InvalidData),
+                }
+                if parser.eat(b'}') {
+                    break;
+                }
+                parser.expect(b',')?;
+            }
+        }
+        parser.end()?;
+
+        Ok(Self {
+            instance: instance.ok_or(SerializationError::InvalidData)?,
+            witness: witness.ok_or(SerializationError::InvalidData)?,
+        })
+    }
+}
+
+/// A parser for the small JSON subset used by [`Assignment::from_json`].
+struct JsonParser<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> JsonParser<'a> {
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.input.get(self.pos) {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        self.skip_whitespace();
+        if self.input.get(self.pos) == Some(&byte) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), SerializationError> {
+        if self.eat(byte) {
+            Ok(())
+        } else {
+            Err(SerializationError::InvalidData)
+        }
+    }
+
+    fn end(&mut self) -> Result<(), SerializationError> {
+        self.skip_whitespace();
+        if self.pos == self.input.len() {
+            Ok(())
+        } else {
+            Err(SerializationError::InvalidData)
+        }
+    }
+
+    fn take_while(&mut self, f: impl Fn(u8) -> bool) -> &'a [u8] {
+        let start = self.pos;
+        while self.input.get(self.pos).is_some_and(|b| f(*b)) {
+            self.pos += 1;
+        }
+        &self.input[start..self.pos]
+    }
+
+    fn string(&mut self) -> Result<String, SerializationError> {
+        self.expect(b'"')?;
+        let contents = self.take_while(|b| b != b'"' && b != b'\\');
+        let contents = core::str::from_utf8(contents)
+            .map_err(|_| SerializationError::InvalidData)?
+            .to_string();
+        // Escapes never occur in keys or decimal strings.
+        if !self.eat(b'"') {
+            return Err(SerializationError::InvalidData);
+        }
+        Ok(contents)
+    }
+
+    fn value<F: PrimeField>(&mut self) -> Result<F, SerializationError> {
+        self.skip_whitespace();
+        let digits = if self.input.get(self.pos) == Some(&b'"') {
+            self.string()?
+        } else {
+            let digits = self.take_while(|b| b.is_ascii_digit());
+            String::from_utf8(digits.to_vec()).map_err(|_| SerializationError::InvalidData)?
+        };
+        let value = F::from_str(&digits).map_err(|_| SerializationError::InvalidData)?;
+        // `from_str` reduces modulo the field size; only accept canonical values.
+        if value.into_bigint().to_string() != digits {
+            return Err(SerializationError::InvalidData);
+        }
+        Ok(value)
+    }
+
+    fn values<F: PrimeField>(&mut self) -> Result<Vec<F>, SerializationError> {
+        self.expect(b'[')?;
+        let mut values = Vec::new();
+        if self.eat(b']') {
+            return Ok(values);
+        }
+        loop {
+            values.push(self.value()?);
+            if self.eat(b']') {
+                return Ok(values);
+            }
+            self.expect(b',')?;
+        }
+    }
+}
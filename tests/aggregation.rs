@@ -0,0 +1,165 @@
+#![warn(unused)]
+#![deny(
+    trivial_casts,
+    trivial_numeric_casts,
+    variant_size_differences,
+    stable_features,
+    non_shorthand_field_patterns,
+    renamed_and_removed_lints,
+    unsafe_code
+)]
+
+use ark_bls12_377::{Bls12_377, Fr};
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::Field;
+use ark_groth16::{
+    aggregation::{AggregateProof, AggregationError, AggregationKey},
+    data_structures::{CircuitSpecificSetupPolymorphicSNARK, PolymorphicSNARK},
+    Groth16,
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::rand::{RngCore, SeedableRng};
+use ark_std::test_rng;
+
+use common::SquareChain;
+mod common;
+
+#[test]
+fn test_aggregate_proofs() {
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
+
+    let num_squarings = 6;
+    let (pk, vk) = Groth16::<Bls12_377>::setup(
+        SquareChain::<Fr> {
+            x: None,
+            num_squarings,
+        },
+        &mut rng,
+    )
+    .unwrap();
+    let pvk = Groth16::<Bls12_377>::process_vk(&vk).unwrap();
+    let rnd = Groth16::<Bls12_377>::rndgen(&pk, &mut rng).unwrap();
+    let key = AggregationKey::<Bls12_377>::setup(8, &mut rng).unwrap();
+    let avk = key.verifying_key();
+
+    let statements: Vec<Vec<Fr>> = (2..6u64)
+        .map(|x| vec![(0..num_squarings).fold(Fr::from(x), |y, _| y.square())])
+        .collect();
+    let proofs: Vec<_> = (2..6u64)
+        .map(|x| {
+            let circuit = SquareChain {
+                x: Some(Fr::from(x)),
+                num_squarings,
+            };
+            Groth16::<Bls12_377>::prove(&pk, circuit, &rnd).unwrap()
+        })
+        .collect();
+
+    let aggregate =
+        Groth16::<Bls12_377>::aggregate_proofs(&key, &vk, &rnd, &statements, &proofs).unwrap();
+    assert_eq!(aggregate.rounds.len(), 2);
+    assert!(Groth16::<Bls12_377>::verify_aggregate_proof(
+        &avk,
+        &pvk,
+        &rnd,
+        &statements,
+        &aggregate
+    )
+    .unwrap());
+
+    let mut bytes = Vec::new();
+    aggregate.serialize_compressed(&mut bytes).unwrap();
+    let deserialized = AggregateProof::<Bls12_377>::deserialize_compressed(&*bytes).unwrap();
+    assert_eq!(deserialized, aggregate);
+
+    // the statements are bound in order
+    let mut swapped = statements.clone();
+    swapped.swap(0, 1);
+    assert!(
+        !Groth16::<Bls12_377>::verify_aggregate_proof(&avk, &pvk, &rnd, &swapped, &aggregate)
+            .unwrap()
+    );
+
+    // the proofs only verify under their randomizer
+    let other_rnd = Groth16::<Bls12_377>::rndgen(&pk, &mut rng).unwrap();
+    assert!(!Groth16::<Bls12_377>::verify_aggregate_proof(
+        &avk,
+        &pvk,
+        &other_rnd,
+        &statements,
+        &aggregate
+    )
+    .unwrap());
+
+    let mut tampered = aggregate.clone();
+    tampered.z_c = (aggregate.z_c.into_group() + vk.alpha_g1).into_affine();
+    assert!(!Groth16::<Bls12_377>::verify_aggregate_proof(
+        &avk,
+        &pvk,
+        &rnd,
+        &statements,
+        &tampered
+    )
+    .unwrap());
+
+    // an invalid proof spoils the aggregate
+    let mut invalid = proofs.clone();
+    invalid[3].c = (invalid[3].c.into_group() + vk.alpha_g1).into_affine();
+    let aggregate =
+        Groth16::<Bls12_377>::aggregate_proofs(&key, &vk, &rnd, &statements, &invalid).unwrap();
+    assert!(!Groth16::<Bls12_377>::verify_aggregate_proof(
+        &avk,
+        &pvk,
+        &rnd,
+        &statements,
+        &aggregate
+    )
+    .unwrap());
+}
+
+#[test]
+fn test_aggregate_duplicates() {
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
+
+    let num_squarings = 4;
+    let (pk, vk) = Groth16::<Bls12_377>::setup(
+        SquareChain::<Fr> {
+            x: None,
+            num_squarings,
+        },
+        &mut rng,
+    )
+    .unwrap();
+    let rnd = Groth16::<Bls12_377>::rndgen(&pk, &mut rng).unwrap();
+    let key = AggregationKey::<Bls12_377>::setup(4, &mut rng).unwrap();
+
+    let x = Fr::from(3u64);
+    let y = (0..num_squarings).fold(x, |y, _| y.square());
+    let circuit = SquareChain {
+        x: Some(x),
+        num_squarings,
+    };
+    let proof = Groth16::<Bls12_377>::prove(&pk, circuit, &rnd).unwrap();
+
+    // an aggregate proof does not attest that the proofs are distinct
+    let pvk = Groth16::<Bls12_377>::process_vk(&vk).unwrap();
+    let avk = key.verifying_key();
+    let proofs = vec![proof.clone(), proof.clone()];
+    let statements = [vec![y], vec![y]];
+    let aggregate =
+        Groth16::<Bls12_377>::aggregate_proofs(&key, &vk, &rnd, &statements, &proofs).unwrap();
+    assert!(Groth16::<Bls12_377>::verify_aggregate_proof(
+        &avk,
+        &pvk,
+        &rnd,
+        &statements,
+        &aggregate
+    )
+    .unwrap());
+
+    let proofs = vec![proof.clone(), proof.clone(), proof];
+    assert_eq!(
+        Groth16::<Bls12_377>::aggregate_proofs(&key, &vk, &rnd, &vec![vec![y]; 3], &proofs),
+        Err(AggregationError::InvalidProofCount(3))
+    );
+}
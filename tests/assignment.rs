@@ -0,0 +1,221 @@
+#![warn(unused)]
+#![deny(
+    trivial_casts,
+    trivial_numeric_casts,
+    variant_size_differences,
+    stable_features,
+    non_shorthand_field_patterns,
+    renamed_and_removed_lints,
+    unsafe_code
+)]
+
+use ark_bls12_377::{Bls12_377, Fr};
+use ark_ff::One;
+use ark_groth16::{data_structures::PolymorphicSNARK, witness::Assignment, Groth16, ProofCache};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem, SynthesisError, SynthesisMode};
+use ark_std::rand::{RngCore, SeedableRng};
+use ark_std::test_rng;
+
+use common::SquareChain;
+mod common;
+
+fn synthesize(circuit: SquareChain<Fr>) -> Assignment<Fr> {
+    let cs = ConstraintSystem::new_ref();
+    cs.set_mode(SynthesisMode::Prove {
+        construct_matrices: false,
+    });
+    circuit.generate_constraints(cs.clone()).unwrap();
+    Assignment::from_constraint_system(&cs).unwrap()
+}
+
+#[test]
+fn test_assignment_round_trip() {
+    let assignment = synthesize(SquareChain {
+        x: Some(Fr::from(3u64)),
+        num_squarings: 4,
+    });
+
+    let mut bytes = Vec::new();
+    assignment.write_binary(&mut bytes).unwrap();
+    assert_eq!(
+        Assignment::<Fr>::read_binary(&bytes[..]).unwrap(),
+        assignment
+    );
+
+    let json = assignment.to_json();
+    assert_eq!(Assignment::<Fr>::from_json(&json).unwrap(), assignment);
+
+    let pretty = "{\n  \"witness\": [\"0\", 5],\n  \"instance\": [\"1\"]\n}";
+    let parsed = Assignment::<Fr>::from_json(pretty).unwrap();
+    assert_eq!(parsed.instance, vec![Fr::one()]);
+    assert_eq!(parsed.witness, vec![Fr::from(0u64), Fr::from(5u64)]);
+
+    // non-canonical values and truncated input are rejected
+    assert!(Assignment::<Fr>::from_json("{\"instance\":[\"01\"],\"witness\":[]}").is_err());
+    assert!(Assignment::<Fr>::from_json("{\"instance\":[\"1\"]}").is_err());
+    assert!(Assignment::<Fr>::read_binary(&bytes[..bytes.len() - 1]).is_err());
+}
+
+#[test]
+fn test_prove_from_assignment() {
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
+
+    let circuit = SquareChain::<Fr> {
+        x: None,
+        num_squarings: 8,
+    };
+    let index =
+        Groth16::<Bls12_377>::generate_random_index_with_reduction(circuit, &mut rng).unwrap();
+    let pvk = Groth16::<Bls12_377>::process_vk(&index.pk.vk).unwrap();
+    let prepared = Groth16::<Bls12_377>::create_prepared_randomizer(&index.pk, &mut rng).unwrap();
+    let rnd = prepared.rnd.clone();
+
+    let circuit = SquareChain {
+        x: Some(Fr::from(7u64)),
+        num_squarings: 8,
+    };
+    // the assignment travels through the JSON format, as from an external service
+    let json = synthesize(circuit).to_json();
+    let assignment = Assignment::<Fr>::from_json(&json).unwrap();
+
+    let proof = Groth16::<Bls12_377>::prove_from_assignment(
+        &index,
+        &assignment.instance,
+        &assignment.witness,
+        &prepared,
+    )
+    .unwrap();
+    assert_eq!(
+        proof,
+        Groth16::<Bls12_377>::prove(&index.pk, circuit, &rnd).unwrap()
+    );
+    assert!(Groth16::<Bls12_377>::verify_with_processed_vk(
+        &pvk,
+        &assignment.instance[1..],
+        &proof,
+        &rnd
+    )
+    .unwrap());
+
+    // a tampered witness is caught before proving
+    let mut witness = assignment.witness.clone();
+    witness[1] += Fr::one();
+    assert!(matches!(
+        Groth16::<Bls12_377>::prove_from_assignment(
+            &index,
+            &assignment.instance,
+            &witness,
+            &prepared
+        ),
+        Err(SynthesisError::Unsatisfiable)
+    ));
+
+    // so is a missing witness value
+    assert!(matches!(
+        Groth16::<Bls12_377>::prove_from_assignment(
+            &index,
+            &assignment.instance,
+            &assignment.witness[1..],
+            &prepared
+        ),
+        Err(SynthesisError::Unsatisfiable)
+    ));
+}
+
+#[test]
+fn test_reprove_with_diff() {
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
+
+    let num_squarings = 8;
+    let circuit = SquareChain::<Fr> {
+        x: None,
+        num_squarings,
+    };
+    let index =
+        Groth16::<Bls12_377>::generate_random_index_with_reduction(circuit, &mut rng).unwrap();
+    let pvk = Groth16::<Bls12_377>::process_vk(&index.pk.vk).unwrap();
+    let prepared = Groth16::<Bls12_377>::create_prepared_randomizer(&index.pk, &mut rng).unwrap();
+
+    let old = synthesize(SquareChain {
+        x: Some(Fr::from(3u64)),
+        num_squarings,
+    });
+    let (proof, cache) = Groth16::<Bls12_377>::create_proof_with_cache(
+        &index,
+        &old.instance,
+        &old.witness,
+        &prepared,
+    )
+    .unwrap();
+    assert_eq!(
+        proof,
+        Groth16::<Bls12_377>::prove_from_assignment(&index, &old.instance, &old.witness, &prepared)
+            .unwrap()
+    );
+
+    // the diff lists only the variables whose value changed
+    let new = synthesize(SquareChain {
+        x: Some(Fr::from(5u64)),
+        num_squarings,
+    });
+    let diff = new
+        .full_assignment()
+        .into_iter()
+        .zip(old.full_assignment())
+        .enumerate()
+        .filter(|(_, (new, old))| new != old)
+        .map(|(i, (new, _))| (i, new))
+        .collect::<Vec<_>>();
+    let (proof, cache) =
+        Groth16::<Bls12_377>::reprove_with_diff(&index, &prepared, &cache, &diff).unwrap();
+
+    // the cache holds the witness, so it is redacted and only exported explicitly
+    assert_eq!(format!("{:?}", cache), "ProofCache(<redacted>)");
+    let mut bytes = Vec::new();
+    cache.export(&mut bytes).unwrap();
+    assert_eq!(ProofCache::<Bls12_377>::import(&bytes[..]).unwrap(), cache);
+    assert_eq!(cache.assignment, new.full_assignment());
+    assert_eq!(
+        (proof.clone(), cache.clone()),
+        Groth16::<Bls12_377>::create_proof_with_cache(
+            &index,
+            &new.instance,
+            &new.witness,
+            &prepared
+        )
+        .unwrap()
+    );
+    assert!(Groth16::<Bls12_377>::verify_with_processed_vk(
+        &pvk,
+        &new.instance[1..],
+        &proof,
+        &prepared.rnd
+    )
+    .unwrap());
+
+    // the constant `one`, missing variables and unsatisfying diffs are rejected
+    for diff in [
+        vec![(0, Fr::from(2u64))],
+        vec![(cache.assignment.len(), Fr::one())],
+        vec![(diff[0].0, Fr::one())],
+    ] {
+        assert!(matches!(
+            Groth16::<Bls12_377>::reprove_with_diff(&index, &prepared, &cache, &diff),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+    }
+
+    // a key lacking the bases its densities promise is rejected, not indexed
+    let diff = old
+        .full_assignment()
+        .into_iter()
+        .enumerate()
+        .skip(1)
+        .collect::<Vec<_>>();
+    let mut malformed = index;
+    malformed.pk.a_query.clear();
+    assert!(matches!(
+        Groth16::<Bls12_377>::reprove_with_diff(&malformed, &prepared, &cache, &diff),
+        Err(SynthesisError::MalformedVerifyingKey)
+    ));
+}
@@ -0,0 +1,209 @@
+#![warn(unused)]
+#![deny(
+    trivial_casts,
+    trivial_numeric_casts,
+    variant_size_differences,
+    stable_features,
+    non_shorthand_field_patterns,
+    renamed_and_removed_lints,
+    unsafe_code
+)]
+
+use ark_bls12_377::{Bls12_377, Fr, G1Affine, G2Affine};
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::{Field, One, UniformRand};
+use ark_groth16::{
+    data_structures::{CircuitSpecificSetupPolymorphicSNARK, PolymorphicSNARK},
+    CommittedProof, Groth16,
+};
+use ark_relations::r1cs::SynthesisError;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::rand::{RngCore, SeedableRng};
+use ark_std::test_rng;
+
+use common::SquareChain;
+mod common;
+
+#[test]
+fn test_commit_and_prove() {
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
+
+    // commit to `x` and `x^4`
+    let num_squarings = 8;
+    let pk = Groth16::<Bls12_377>::generate_random_parameters_with_commitments(
+        SquareChain::<Fr> {
+            x: None,
+            num_squarings,
+        },
+        &[0, 2],
+        &mut rng,
+    )
+    .unwrap();
+    let vk = pk.vk.clone();
+    assert_eq!(vk.commitment_bases_g1.len(), 2);
+    assert!(pk.l_query[0].is_zero() && pk.l_query[2].is_zero());
+    let pvk = Groth16::<Bls12_377>::process_vk(&vk).unwrap();
+
+    let prove = |x: u64, rng: &mut ark_std::rand::rngs::StdRng| {
+        let rnd = Groth16::<Bls12_377>::rndgen(&pk, rng).unwrap();
+        let prepared = Groth16::<Bls12_377>::prepare_randomizer(&pk, &rnd).unwrap();
+        let circuit = SquareChain {
+            x: Some(Fr::from(x)),
+            num_squarings,
+        };
+        (
+            Groth16::<Bls12_377>::create_committed_proof(circuit, &pk, &prepared).unwrap(),
+            rnd,
+        )
+    };
+    let x = Fr::from(3u64);
+    let y = (0..num_squarings).fold(x, |y, _| y.square());
+    let (proof, rnd) = prove(3, &mut rng);
+    assert!(Groth16::<Bls12_377>::verify_committed_proof(&pvk, &proof, &rnd, &[y]).unwrap());
+    let derived = Groth16::<Bls12_377>::commitment_blinding(&rnd, &[x, x.square().square()]);
+    assert!(Groth16::<Bls12_377>::verify_commitment(
+        &vk,
+        &proof,
+        &[x, x.square().square()],
+        derived
+    ));
+    assert!(!Groth16::<Bls12_377>::verify_commitment(
+        &vk,
+        &proof,
+        &[x, x.square().square()],
+        Fr::from(0u64)
+    ));
+    assert!(!Groth16::<Bls12_377>::verify_commitment(
+        &vk,
+        &proof,
+        &[x, x.square()],
+        derived
+    ));
+
+    // the commitment travels next to the plain proof, which serializes as before
+    let mut bytes = Vec::new();
+    proof.serialize_compressed(&mut bytes).unwrap();
+    assert_eq!(
+        CommittedProof::<Bls12_377>::deserialize_compressed(&bytes[..]).unwrap(),
+        proof
+    );
+
+    // a key committing to witnesses neither makes nor accepts plain proofs
+    let circuit = SquareChain {
+        x: Some(x),
+        num_squarings,
+    };
+    assert!(matches!(
+        Groth16::<Bls12_377>::prove(&pk, circuit, &rnd),
+        Err(SynthesisError::MalformedVerifyingKey)
+    ));
+    assert!(
+        !Groth16::<Bls12_377>::verify_with_processed_vk(&pvk, &[y], &proof.proof, &rnd).unwrap()
+    );
+
+    // a blinded commitment hides the witnesses until it is opened
+    let blinding = Fr::rand(&mut rng);
+    let prepared = Groth16::<Bls12_377>::prepare_randomizer(&pk, &rnd).unwrap();
+    let circuit = SquareChain {
+        x: Some(x),
+        num_squarings,
+    };
+    let blinded = Groth16::<Bls12_377>::create_committed_proof_with_blinding(
+        circuit, &pk, &prepared, blinding,
+    )
+    .unwrap();
+    assert_ne!(blinded.d, proof.d);
+    assert!(Groth16::<Bls12_377>::verify_committed_proof(&pvk, &blinded, &rnd, &[y]).unwrap());
+    assert!(Groth16::<Bls12_377>::verify_commitment(
+        &vk,
+        &blinded,
+        &[x, x.square().square()],
+        blinding
+    ));
+
+    // moving part of a public input into `D` breaks the opening proof
+    let mut shifted = proof.clone();
+    shifted.d = (proof.d.into_group() - vk.gamma_abc_g1[1]).into_affine();
+    assert!(
+        !Groth16::<Bls12_377>::verify_committed_proof(&pvk, &shifted, &rnd, &[y + Fr::one()])
+            .unwrap()
+    );
+    let mut truncated = proof.clone();
+    truncated.opening.responses.pop();
+    assert!(!Groth16::<Bls12_377>::verify_committed_proof(&pvk, &truncated, &rnd, &[y]).unwrap());
+
+    // by default, the same committed witnesses are hidden across randomizers
+    let (again, _) = prove(3, &mut rng);
+    assert_ne!(again.d, proof.d);
+    let proofs = vec![proof.clone(), again];
+    assert!(Groth16::<Bls12_377>::compare_proofs_by_commitment(&proofs).unwrap());
+
+    // unblinded, they are detected across randomizers
+    let unblinded = |x: u64, rng: &mut ark_std::rand::rngs::StdRng| {
+        let rnd = Groth16::<Bls12_377>::rndgen(&pk, rng).unwrap();
+        let circuit = SquareChain {
+            x: Some(Fr::from(x)),
+            num_squarings,
+        };
+        let prepared = Groth16::<Bls12_377>::prepare_randomizer(&pk, &rnd).unwrap();
+        let proof = Groth16::<Bls12_377>::create_committed_proof_with_blinding(
+            circuit,
+            &pk,
+            &prepared,
+            Fr::from(0u64),
+        )
+        .unwrap();
+        let y = (0..num_squarings).fold(Fr::from(x), |y, _| y.square());
+        assert!(Groth16::<Bls12_377>::verify_committed_proof(&pvk, &proof, &rnd, &[y]).unwrap());
+        proof
+    };
+    let proofs = vec![unblinded(3, &mut rng), unblinded(3, &mut rng)];
+    let elements = proofs.iter().map(|proof| proof.proof.clone()).collect();
+    assert!(Groth16::<Bls12_377>::compare_all_proofs(&elements).unwrap());
+    assert!(!Groth16::<Bls12_377>::compare_proofs_by_commitment(&proofs).unwrap());
+    let proofs = vec![unblinded(3, &mut rng), unblinded(5, &mut rng)];
+    assert!(Groth16::<Bls12_377>::compare_proofs_by_commitment(&proofs).unwrap());
+}
+
+#[test]
+fn test_no_commitment() {
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
+
+    let num_squarings = 4;
+    let (pk, vk) = Groth16::<Bls12_377>::setup(
+        SquareChain::<Fr> {
+            x: None,
+            num_squarings,
+        },
+        &mut rng,
+    )
+    .unwrap();
+    let pvk = Groth16::<Bls12_377>::process_vk(&vk).unwrap();
+    let rnd = Groth16::<Bls12_377>::rndgen(&pk, &mut rng).unwrap();
+
+    let x = Fr::from(2u64);
+    let y = (0..num_squarings).fold(x, |y, _| y.square());
+    let circuit = SquareChain {
+        x: Some(x),
+        num_squarings,
+    };
+    let proof = Groth16::<Bls12_377>::prove(&pk, circuit, &rnd).unwrap();
+    assert!(Groth16::<Bls12_377>::verify_with_processed_vk(&pvk, &[y], &proof, &rnd).unwrap());
+
+    // a plain proof is just `A`, `B` and `C`
+    assert_eq!(
+        proof.compressed_size(),
+        2 * G1Affine::zero().compressed_size() + G2Affine::zero().compressed_size()
+    );
+
+    // without committed witnesses, there is nothing to commit to
+    let prepared = Groth16::<Bls12_377>::prepare_randomizer(&pk, &rnd).unwrap();
+    let circuit = SquareChain {
+        x: Some(x),
+        num_squarings,
+    };
+    assert!(matches!(
+        Groth16::<Bls12_377>::create_committed_proof(circuit, &pk, &prepared),
+        Err(SynthesisError::MalformedVerifyingKey)
+    ));
+}
@@ -0,0 +1,36 @@
+use ark_ff::Field;
+use ark_relations::{
+    lc,
+    r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError},
+};
+
+/// Proves knowledge of a square root `x` of the public `y`, through a chain
+/// of `x^(2^i)` witnesses.
+#[derive(Clone, Copy)]
+pub struct SquareChain<F: Field> {
+    pub x: Option<F>,
+    pub num_squarings: usize,
+}
+
+impl<F: Field> ConstraintSynthesizer<F> for SquareChain<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let x = self.x;
+        let y = x.map(|x| (0..self.num_squarings).fold(x, |acc, _| acc.square()));
+
+        let y_var = cs.new_input_variable(|| y.ok_or(SynthesisError::AssignmentMissing))?;
+        let mut value = x;
+        let mut var = cs.new_witness_variable(|| value.ok_or(SynthesisError::AssignmentMissing))?;
+        for i in 0..self.num_squarings {
+            let square = value.map(|v| v.square());
+            let square_var = if i + 1 == self.num_squarings {
+                y_var
+            } else {
+                cs.new_witness_variable(|| square.ok_or(SynthesisError::AssignmentMissing))?
+            };
+            cs.enforce_constraint(lc!() + var, lc!() + var, lc!() + square_var)?;
+            value = square;
+            var = square_var;
+        }
+        Ok(())
+    }
+}
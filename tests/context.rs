@@ -0,0 +1,153 @@
+#![warn(unused)]
+#![deny(
+    trivial_casts,
+    trivial_numeric_casts,
+    variant_size_differences,
+    stable_features,
+    non_shorthand_field_patterns,
+    renamed_and_removed_lints,
+    unsafe_code
+)]
+
+use ark_bls12_377::{Bls12_377, Fr};
+use ark_ff::Field;
+use ark_groth16::{
+    circuits::ContextBound,
+    data_structures::{CircuitSpecificSetupPolymorphicSNARK, PolymorphicSNARK},
+    gm17::GM17,
+    prepare_verifying_key, Groth16,
+};
+use ark_std::rand::{RngCore, SeedableRng};
+use ark_std::test_rng;
+
+use common::SquareChain;
+mod common;
+
+/// An unambiguous encoding of the context of a submission.
+fn context(prover_id: &[u8], nonce: u64, expiry: u64) -> Vec<u8> {
+    let mut context = (prover_id.len() as u64).to_le_bytes().to_vec();
+    context.extend_from_slice(prover_id);
+    context.extend_from_slice(&nonce.to_le_bytes());
+    context.extend_from_slice(&expiry.to_le_bytes());
+    context
+}
+
+#[test]
+fn test_context_binding() {
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
+
+    let circuit = |x, context| {
+        ContextBound::new(
+            SquareChain::<Fr> {
+                x,
+                num_squarings: 3,
+            },
+            context,
+        )
+    };
+    let pk = Groth16::<Bls12_377>::generate_random_parameters_with_reduction(
+        circuit(None, Vec::new()),
+        &mut rng,
+    )
+    .unwrap();
+    let pvk = prepare_verifying_key(&pk.vk);
+    let rnd = Groth16::<Bls12_377>::create_randomizer(&pk, &mut rng).unwrap();
+
+    let x = Fr::from(2u64);
+    let y = x.square().square().square();
+    let alice = context(b"alice", 7, 1000);
+    let proof = Groth16::<Bls12_377>::create_proof_with_prepared_randomizer(
+        circuit(Some(x), alice.clone()),
+        &pk,
+        &Groth16::<Bls12_377>::prepare_randomizer(&pk, &rnd).unwrap(),
+    )
+    .unwrap();
+    assert!(
+        <Groth16<Bls12_377> as PolymorphicSNARK<Fr>>::verify_with_context(
+            &pvk,
+            &[y],
+            &proof,
+            &rnd,
+            &alice
+        )
+        .unwrap()
+    );
+
+    // the proof stolen by another prover, or replayed to another nonce, fails
+    for stolen in [context(b"bob", 7, 1000), context(b"alice", 8, 1000)] {
+        assert!(
+            !<Groth16<Bls12_377> as PolymorphicSNARK<Fr>>::verify_with_context(
+                &pvk,
+                &[y],
+                &proof,
+                &rnd,
+                &stolen
+            )
+            .unwrap()
+        );
+    }
+
+    // the same witness under another context is a distinct proof, which
+    // shares `B` under the same randomizer, but nothing under another one
+    let bob = context(b"bob", 7, 1000);
+    let prove_bob = |rnd| {
+        let proof = Groth16::<Bls12_377>::create_proof_with_prepared_randomizer(
+            circuit(Some(x), bob.clone()),
+            &pk,
+            &Groth16::<Bls12_377>::prepare_randomizer(&pk, rnd).unwrap(),
+        )
+        .unwrap();
+        assert!(
+            <Groth16<Bls12_377> as PolymorphicSNARK<Fr>>::verify_with_context(
+                &pvk,
+                &[y],
+                &proof,
+                rnd,
+                &bob
+            )
+            .unwrap()
+        );
+        proof
+    };
+    let reused = prove_bob(&rnd);
+    assert_ne!(reused.a, proof.a);
+    assert_eq!(reused.b, proof.b);
+    assert!(!Groth16::<Bls12_377>::compare_all_proofs(&vec![proof.clone(), reused]).unwrap());
+    let other_rnd = Groth16::<Bls12_377>::create_randomizer(&pk, &mut rng).unwrap();
+    let reused = prove_bob(&other_rnd);
+    assert!(Groth16::<Bls12_377>::compare_all_proofs(&vec![proof, reused]).unwrap());
+}
+
+fn check_context_binding<S: CircuitSpecificSetupPolymorphicSNARK<Fr>>() {
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
+
+    let circuit = |x, context| {
+        ContextBound::new(
+            SquareChain::<Fr> {
+                x,
+                num_squarings: 3,
+            },
+            context,
+        )
+    };
+    let (pk, vk) = S::setup(circuit(None, Vec::new()), &mut rng).unwrap();
+    let pvk = S::process_vk(&vk).unwrap();
+    let rnd = S::rndgen(&pk, &mut rng).unwrap();
+
+    let x = Fr::from(2u64);
+    let y = x.square().square().square();
+    let alice = context(b"alice", 7, 1000);
+    let proof = S::prove(&pk, circuit(Some(x), alice.clone()), &rnd).unwrap();
+    assert!(S::verify_with_context(&pvk, &[y], &proof, &rnd, &alice).unwrap());
+    assert!(!S::verify_with_context(&pvk, &[y], &proof, &rnd, &context(b"bob", 7, 1000)).unwrap());
+}
+
+#[test]
+fn test_context_binding_groth16() {
+    check_context_binding::<Groth16<Bls12_377>>();
+}
+
+#[test]
+fn test_context_binding_gm17() {
+    check_context_binding::<GM17<Bls12_377>>();
+}
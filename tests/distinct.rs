@@ -0,0 +1,95 @@
+#![warn(unused)]
+#![deny(
+    trivial_casts,
+    trivial_numeric_casts,
+    variant_size_differences,
+    stable_features,
+    non_shorthand_field_patterns,
+    renamed_and_removed_lints,
+    unsafe_code
+)]
+
+use ark_bls12_377::{Bls12_377, Fr};
+use ark_ff::Field;
+use ark_groth16::{
+    circuits::DistinctWitnesses,
+    data_structures::{CircuitSpecificSetupPolymorphicSNARK, PolymorphicSNARK},
+    Groth16,
+};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem, SynthesisError};
+use ark_std::rand::{RngCore, SeedableRng};
+use ark_std::test_rng;
+
+use common::SquareChain;
+mod common;
+
+const NUM_SQUARINGS: usize = 2;
+
+fn chain(x: Option<Fr>) -> SquareChain<Fr> {
+    SquareChain {
+        x,
+        num_squarings: NUM_SQUARINGS,
+    }
+}
+
+/// Distinct fourth roots of `y = x^4`.
+fn roots(x: Fr) -> [Fr; 3] {
+    let i = (-Fr::from(1u64)).sqrt().unwrap();
+    [x, -x, i * x]
+}
+
+#[test]
+fn test_distinct_witnesses() {
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
+
+    let x = Fr::from(3u64);
+    let y = x.square().square();
+    let [x0, x1, x2] = roots(x);
+
+    let (pk, vk) = Groth16::<Bls12_377>::setup(
+        DistinctWitnesses::new([chain(None), chain(None), chain(None)], vec![0]),
+        &mut rng,
+    )
+    .unwrap();
+    let pvk = Groth16::<Bls12_377>::process_vk(&vk).unwrap();
+    let rnd = Groth16::<Bls12_377>::rndgen(&pk, &mut rng).unwrap();
+
+    let circuit =
+        DistinctWitnesses::new([chain(Some(x0)), chain(Some(x1)), chain(Some(x2))], vec![0]);
+    let proof = Groth16::<Bls12_377>::prove(&pk, circuit, &rnd).unwrap();
+    assert!(Groth16::<Bls12_377>::verify_with_processed_vk(&pvk, &[y], &proof, &rnd).unwrap());
+    assert!(!Groth16::<Bls12_377>::verify_with_processed_vk(
+        &pvk,
+        &[y + Fr::from(1u64)],
+        &proof,
+        &rnd
+    )
+    .unwrap());
+}
+
+#[test]
+fn test_equal_witnesses() {
+    let x = Fr::from(3u64);
+
+    // a repeated solution cannot satisfy the inequality constraints
+    let cs = ConstraintSystem::new_ref();
+    let circuit = DistinctWitnesses::new([chain(Some(x)), chain(Some(x))], vec![0]);
+    assert_eq!(
+        circuit.generate_constraints(cs),
+        Err(SynthesisError::Unsatisfiable)
+    );
+
+    // nor can solutions to different public inputs be combined
+    let cs = ConstraintSystem::new_ref();
+    let circuit = DistinctWitnesses::new([chain(Some(x)), chain(Some(x + x))], vec![0]);
+    assert_eq!(
+        circuit.generate_constraints(cs),
+        Err(SynthesisError::Unsatisfiable)
+    );
+
+    // while two distinct roots do
+    let cs = ConstraintSystem::new_ref();
+    let circuit = DistinctWitnesses::new([chain(Some(x)), chain(Some(-x))], vec![0]);
+    circuit.generate_constraints(cs.clone()).unwrap();
+    assert!(cs.is_satisfied().unwrap());
+}
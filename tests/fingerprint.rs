@@ -0,0 +1,91 @@
+#![warn(unused)]
+#![deny(
+    trivial_casts,
+    trivial_numeric_casts,
+    variant_size_differences,
+    stable_features,
+    non_shorthand_field_patterns,
+    renamed_and_removed_lints,
+    unsafe_code
+)]
+
+use ark_bls12_377::{Bls12_377, Fr};
+use ark_ec::CurveGroup;
+use ark_ff::{Field, UniformRand};
+use ark_groth16::{
+    fingerprint::FingerprintIndex, prepare_verifying_key, Groth16, Randomizer, SecretRandomizer,
+};
+use ark_std::rand::{RngCore, SeedableRng};
+use ark_std::test_rng;
+
+use common::SquareChain;
+mod common;
+
+#[test]
+fn test_witness_fingerprint() {
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
+
+    let pk = Groth16::<Bls12_377>::generate_random_parameters_with_reduction(
+        SquareChain::<Fr> {
+            x: None,
+            num_squarings: 3,
+        },
+        &mut rng,
+    )
+    .unwrap();
+    let prove = |x: u64, rnd: &SecretRandomizer<Bls12_377>| {
+        Groth16::<Bls12_377>::create_proof_with_prepared_randomizer(
+            SquareChain {
+                x: Some(Fr::from(x)),
+                num_squarings: 3,
+            },
+            &pk,
+            &Groth16::<Bls12_377>::prepare_randomizer(&pk, rnd.randomizer()).unwrap(),
+        )
+        .unwrap()
+    };
+
+    // a fresh randomizer per round changes the proofs, but not the fingerprints
+    let rounds: Vec<_> = (0..3)
+        .map(|_| {
+            let rnd = Groth16::<Bls12_377>::create_randomizer(&pk, &mut rng).unwrap();
+            SecretRandomizer::try_from(rnd).unwrap()
+        })
+        .collect();
+    let (first, second) = (prove(2, &rounds[0]), prove(2, &rounds[1]));
+    assert_ne!(first, second);
+    assert_eq!(
+        Groth16::<Bls12_377>::witness_fingerprint(&pk, &rounds[0], &first),
+        Groth16::<Bls12_377>::witness_fingerprint(&pk, &rounds[1], &second)
+    );
+
+    let mut index = FingerprintIndex::new();
+    assert_eq!(index.insert(&pk, &rounds[0], &first, 0), None);
+    assert_eq!(
+        index.insert(&pk, &rounds[0], &prove(3, &rounds[0]), 0),
+        None
+    );
+    assert_eq!(index.insert(&pk, &rounds[1], &second, 1), Some(0));
+    assert_eq!(
+        index.insert(&pk, &rounds[2], &prove(3, &rounds[2]), 2),
+        Some(0)
+    );
+    assert_eq!(index.lookup(&pk, &rounds[2], &prove(4, &rounds[2])), None);
+    assert_eq!(index.len(), 2);
+
+    // rescaling `A` and `B` yields another valid proof, which evades the index
+    let t = Fr::rand(&mut rng);
+    let mut rescaled = first.clone();
+    rescaled.a = (rescaled.a * t).into_affine();
+    rescaled.b = (rescaled.b * t.inverse().unwrap()).into_affine();
+    let pvk = prepare_verifying_key(&pk.vk);
+    let y = Fr::from(2u64).square().square().square();
+    assert!(
+        Groth16::<Bls12_377>::verify_proof(&pvk, &rescaled, rounds[0].randomizer(), &[y]).unwrap()
+    );
+    assert_eq!(index.lookup(&pk, &rounds[0], &rescaled), None);
+
+    // the public part of a randomizer cannot fingerprint
+    let public = Randomizer::from(rounds[0].public());
+    assert!(SecretRandomizer::try_from(public).is_err());
+}
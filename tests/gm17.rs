@@ -0,0 +1,106 @@
+#![warn(unused)]
+#![deny(
+    trivial_casts,
+    trivial_numeric_casts,
+    variant_size_differences,
+    stable_features,
+    non_shorthand_field_patterns,
+    renamed_and_removed_lints,
+    unsafe_code
+)]
+
+use ark_bls12_377::{Bls12_377, Fr};
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::{Field, UniformRand};
+use ark_groth16::{
+    data_structures::{CircuitSpecificSetupPolymorphicSNARK, PolymorphicSNARK},
+    gm17::{Proof, GM17},
+};
+use ark_std::rand::{RngCore, SeedableRng};
+use ark_std::test_rng;
+
+use common::SquareChain;
+mod common;
+
+#[test]
+fn test_gm17() {
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
+
+    let num_squarings = 10;
+    let (pk, vk) = GM17::<Bls12_377>::setup(
+        SquareChain::<Fr> {
+            x: None,
+            num_squarings,
+        },
+        &mut rng,
+    )
+    .unwrap();
+    let pvk = GM17::<Bls12_377>::process_vk(&vk).unwrap();
+    let rnd = GM17::<Bls12_377>::rndgen(&pk, &mut rng).unwrap();
+
+    let prove = |x: u64| {
+        let circuit = SquareChain {
+            x: Some(Fr::from(x)),
+            num_squarings,
+        };
+        GM17::<Bls12_377>::prove(&pk, circuit, &rnd).unwrap()
+    };
+    let y = (0..num_squarings).fold(Fr::from(3u64), |y, _| y.square());
+    let proof = prove(3);
+    assert!(GM17::<Bls12_377>::verify_with_processed_vk(&pvk, &[y], &proof, &rnd).unwrap());
+    assert!(!GM17::<Bls12_377>::verify_with_processed_vk(
+        &pvk,
+        &[y + Fr::from(1u64)],
+        &proof,
+        &rnd
+    )
+    .unwrap());
+
+    // proofs are deterministic per randomizer, and only verify under it
+    assert_eq!(proof, prove(3));
+    assert!(!GM17::<Bls12_377>::compare_all_proofs(&vec![proof.clone(), prove(3)]).unwrap());
+    assert!(GM17::<Bls12_377>::compare_all_proofs(&vec![proof.clone(), prove(5)]).unwrap());
+    let other_rnd = GM17::<Bls12_377>::rndgen(&pk, &mut rng).unwrap();
+    assert!(!GM17::<Bls12_377>::verify_with_processed_vk(&pvk, &[y], &proof, &other_rnd).unwrap());
+}
+
+#[test]
+fn test_gm17_malleability() {
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
+
+    let num_squarings = 4;
+    let (pk, vk) = GM17::<Bls12_377>::setup(
+        SquareChain::<Fr> {
+            x: None,
+            num_squarings,
+        },
+        &mut rng,
+    )
+    .unwrap();
+    let pvk = GM17::<Bls12_377>::process_vk(&vk).unwrap();
+    let rnd = GM17::<Bls12_377>::rndgen(&pk, &mut rng).unwrap();
+
+    let x = Fr::from(2u64);
+    let y = (0..num_squarings).fold(x, |y, _| y.square());
+    let circuit = SquareChain {
+        x: Some(x),
+        num_squarings,
+    };
+    let proof = GM17::<Bls12_377>::prove(&pk, circuit, &rnd).unwrap();
+
+    // the rerandomization that mauls Groth16 proofs breaks `e(A, gamma) = e(gamma, B)`
+    let r1 = Fr::rand(&mut rng);
+    let mauled = Proof::<Bls12_377> {
+        a: (proof.a * r1.inverse().unwrap()).into_affine(),
+        b: (proof.b * r1).into_affine(),
+        c: proof.c,
+    };
+    assert!(!GM17::<Bls12_377>::verify_with_processed_vk(&pvk, &[y], &mauled, &rnd).unwrap());
+
+    // neither can `C` absorb a shift of `A`
+    let shifted = Proof::<Bls12_377> {
+        a: (proof.a.into_group() + vk.g_gamma_g1).into_affine(),
+        ..proof
+    };
+    assert!(!GM17::<Bls12_377>::verify_with_processed_vk(&pvk, &[y], &shifted, &rnd).unwrap());
+}
@@ -0,0 +1,94 @@
+#![warn(unused)]
+#![deny(
+    trivial_casts,
+    trivial_numeric_casts,
+    variant_size_differences,
+    stable_features,
+    non_shorthand_field_patterns,
+    renamed_and_removed_lints,
+    unsafe_code
+)]
+
+use ark_bls12_377::{Bls12_377, Fr};
+use ark_groth16::{
+    issuance::{IssuanceError, IssuedRandomizer, IssuerSecretKey},
+    Groth16,
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::rand::{RngCore, SeedableRng};
+use ark_std::test_rng;
+
+use common::SquareChain;
+mod common;
+
+#[test]
+fn test_issue_randomizer() {
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
+
+    let setup = |rng: &mut _| {
+        Groth16::<Bls12_377>::generate_random_parameters_with_reduction(
+            SquareChain::<Fr> {
+                x: None,
+                num_squarings: 3,
+            },
+            rng,
+        )
+        .unwrap()
+    };
+    let pk = setup(&mut rng);
+    let issuer_key = IssuerSecretKey::<Bls12_377>::generate(&mut rng);
+    let issuer_pk = issuer_key.public_key();
+    let rnd = Groth16::<Bls12_377>::create_randomizer(&pk, &mut rng).unwrap();
+    let issued =
+        Groth16::<Bls12_377>::issue_randomizer(&issuer_key, &pk.vk, &rnd, 100, 200).unwrap();
+
+    let mut bytes = Vec::new();
+    issued.serialize_compressed(&mut bytes).unwrap();
+    let issued = IssuedRandomizer::<Bls12_377>::deserialize_compressed(&bytes[..]).unwrap();
+    for now in [100, 150, 200] {
+        Groth16::<Bls12_377>::verify_issued_randomizer(&issuer_pk, &pk.vk, &rnd, &issued, now)
+            .unwrap();
+    }
+
+    let verify = |issuer_pk, vk, rnd, issued, now| {
+        Groth16::<Bls12_377>::verify_issued_randomizer(issuer_pk, vk, rnd, issued, now)
+    };
+    assert_eq!(
+        verify(&issuer_pk, &pk.vk, &rnd, &issued, 99),
+        Err(IssuanceError::NotYetValid)
+    );
+    assert_eq!(
+        verify(&issuer_pk, &pk.vk, &rnd, &issued, 201),
+        Err(IssuanceError::Expired)
+    );
+
+    let other_pk = setup(&mut rng);
+    assert_eq!(
+        verify(&issuer_pk, &other_pk.vk, &rnd, &issued, 150),
+        Err(IssuanceError::VerifyingKeyMismatch)
+    );
+
+    let other_rnd = Groth16::<Bls12_377>::create_randomizer(&pk, &mut rng).unwrap();
+    assert_eq!(
+        verify(&issuer_pk, &pk.vk, &other_rnd, &issued, 150),
+        Err(IssuanceError::RandomizerMismatch)
+    );
+
+    // another issuer, or a tampered window, invalidates the signature
+    let other_issuer_pk = IssuerSecretKey::<Bls12_377>::generate(&mut rng).public_key();
+    assert_eq!(
+        verify(&other_issuer_pk, &pk.vk, &rnd, &issued, 150),
+        Err(IssuanceError::InvalidSignature)
+    );
+    let mut extended = issued.clone();
+    extended.not_after = 300;
+    assert_eq!(
+        verify(&issuer_pk, &pk.vk, &rnd, &extended, 250),
+        Err(IssuanceError::InvalidSignature)
+    );
+
+    assert_eq!(
+        Groth16::<Bls12_377>::issue_randomizer(&issuer_key, &pk.vk, &rnd, 200, 100),
+        Err(IssuanceError::InvalidValidityWindow)
+    );
+}
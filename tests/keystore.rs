@@ -0,0 +1,228 @@
+#![warn(unused)]
+#![deny(
+    trivial_casts,
+    trivial_numeric_casts,
+    variant_size_differences,
+    stable_features,
+    non_shorthand_field_patterns,
+    renamed_and_removed_lints,
+    unsafe_code
+)]
+
+use ark_bls12_377::{Bls12_377, Fr};
+use ark_groth16::{
+    keystore::{KdfParams, Keystore, KeystoreError, SecretKind, KEYSTORE_MAGIC},
+    Groth16,
+};
+use ark_std::rand::{RngCore, SeedableRng};
+use ark_std::test_rng;
+
+use common::SquareChain;
+mod common;
+
+/// Cheap parameters, to keep the tests fast.
+const PARAMS: KdfParams = KdfParams {
+    log_n: 4,
+    r: 8,
+    p: 1,
+};
+
+fn keystore(rng: &mut impl RngCore) -> Keystore<Bls12_377> {
+    let (pk, trapdoor) = Groth16::<Bls12_377>::generate_random_parameters_with_trapdoor(
+        SquareChain::<Fr> {
+            x: None,
+            num_squarings: 3,
+        },
+        rng,
+    )
+    .unwrap();
+
+    let mut store = Keystore::new();
+    for label in ["round-1", "round-2"] {
+        let rnd = Groth16::<Bls12_377>::create_randomizer(&pk, rng).unwrap();
+        store.insert_randomizer(label, rnd).unwrap();
+    }
+    store.insert_trapdoor("setup", trapdoor).unwrap();
+    store
+}
+
+#[test]
+fn test_keystore_round_trip() {
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
+    let mut store = keystore(&mut rng);
+
+    let rnd = store.randomizer("round-1").unwrap().clone();
+    assert!(matches!(
+        store.insert_randomizer("round-1", rnd),
+        Err(KeystoreError::InvalidLabel(_))
+    ));
+    assert!(store.randomizer("setup").is_none());
+    assert!(store.trapdoor("setup").is_some());
+
+    let mut file = Vec::new();
+    store.save(&mut file, b"hunter2", PARAMS, &mut rng).unwrap();
+    assert_eq!(file[..4], KEYSTORE_MAGIC);
+    assert_eq!(
+        Keystore::<Bls12_377>::load(&file[..], b"hunter2").unwrap(),
+        store
+    );
+    assert_eq!(
+        Keystore::<Bls12_377>::list(&file[..]).unwrap(),
+        vec![
+            ("round-1".to_string(), SecretKind::Randomizer),
+            ("round-2".to_string(), SecretKind::Randomizer),
+            ("setup".to_string(), SecretKind::Trapdoor),
+        ]
+    );
+
+    // saving again picks a fresh salt and nonce
+    let mut other = Vec::new();
+    store
+        .save(&mut other, b"hunter2", PARAMS, &mut rng)
+        .unwrap();
+    assert_ne!(file, other);
+
+    let mut rotated = Vec::new();
+    Keystore::<Bls12_377>::rotate_password(
+        &file[..],
+        b"hunter2",
+        &mut rotated,
+        b"correct horse",
+        PARAMS,
+        &mut rng,
+    )
+    .unwrap();
+    assert!(matches!(
+        Keystore::<Bls12_377>::load(&rotated[..], b"hunter2"),
+        Err(KeystoreError::WrongPassword)
+    ));
+    assert_eq!(
+        Keystore::<Bls12_377>::load(&rotated[..], b"correct horse").unwrap(),
+        store
+    );
+
+    assert!(store.remove("round-2"));
+    assert!(!store.remove("round-2"));
+    assert_eq!(
+        store.labels(),
+        vec![
+            ("round-1", SecretKind::Randomizer),
+            ("setup", SecretKind::Trapdoor)
+        ]
+    );
+}
+
+#[test]
+fn test_keystore_errors() {
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
+    let store = keystore(&mut rng);
+    let mut file = Vec::new();
+    store.save(&mut file, b"hunter2", PARAMS, &mut rng).unwrap();
+
+    assert!(matches!(
+        Keystore::<Bls12_377>::load(&file[..], b"hunter3"),
+        Err(KeystoreError::WrongPassword)
+    ));
+
+    // every byte past the key derivation parameters is authenticated
+    for i in 14..file.len() {
+        let mut tampered = file.clone();
+        tampered[i] ^= 1;
+        assert!(Keystore::<Bls12_377>::load(&tampered[..], b"hunter2").is_err());
+    }
+    let mut tampered = file.clone();
+    let last = tampered.len() - 40;
+    tampered[last] ^= 1;
+    assert!(matches!(
+        Keystore::<Bls12_377>::load(&tampered[..], b"hunter2"),
+        Err(KeystoreError::IntegrityCheckFailed)
+    ));
+
+    let mut tampered = file.clone();
+    tampered[4] = 1;
+    assert!(matches!(
+        Keystore::<Bls12_377>::load(&tampered[..], b"hunter2"),
+        Err(KeystoreError::UnsupportedVersion(1))
+    ));
+    assert!(matches!(
+        Keystore::<Bls12_377>::load(&file[1..], b"hunter2"),
+        Err(KeystoreError::NotAKeystore)
+    ));
+    assert!(Keystore::<Bls12_377>::load(&file[..file.len() - 1], b"hunter2").is_err());
+
+    // parameters taking too much memory are rejected before deriving a key
+    let mut tampered = file.clone();
+    tampered[5] = 24;
+    tampered[6..10].copy_from_slice(&(1u32 << 20).to_le_bytes());
+    assert!(matches!(
+        Keystore::<Bls12_377>::load(&tampered[..], b"hunter2"),
+        Err(KeystoreError::InvalidParameters)
+    ));
+    assert!(matches!(
+        Keystore::<Bls12_377>::load_with_memory_limit(&file[..], b"hunter2", 1 << 10),
+        Err(KeystoreError::InvalidParameters)
+    ));
+    assert!(Keystore::<Bls12_377>::load_with_memory_limit(
+        &file[..],
+        b"hunter2",
+        PARAMS.memory() as u64
+    )
+    .is_ok());
+
+    assert!(matches!(
+        store.save(
+            Vec::new(),
+            b"hunter2",
+            KdfParams { log_n: 0, ..PARAMS },
+            &mut rng
+        ),
+        Err(KeystoreError::InvalidParameters)
+    ));
+}
+
+#[test]
+fn test_kdf_known_answers() {
+    // The test vectors of RFC 7914, section 12, but the last one, which takes
+    // 1 GiB of memory.
+    let vectors: [(&[u8], &[u8], KdfParams, &str); 3] = [
+        (
+            b"",
+            b"",
+            KdfParams {
+                log_n: 4,
+                r: 1,
+                p: 1,
+            },
+            "77d6576238657b203b19ca42c18a0497f16b4844e3074ae8dfdffa3fede21442\
+             fcd0069ded0948f8326a753a0fc81f17e8d3e0fb2e0d3628cf35e20c38d18906",
+        ),
+        (
+            b"password",
+            b"NaCl",
+            KdfParams {
+                log_n: 10,
+                r: 8,
+                p: 16,
+            },
+            "fdbabe1c9d3472007856e7190d01e9fe7c6ad7cbc8237830e77376634b373162\
+             2eaf30d92e22a3886ff109279d9830dac727afb94a83ee6d8360cbdfa2cc0640",
+        ),
+        (
+            b"pleaseletmein",
+            b"SodiumChloride",
+            KdfParams {
+                log_n: 14,
+                r: 8,
+                p: 1,
+            },
+            "7023bdcb3afd7348461c06cd81fd38ebfda8fbba904f8e3ea9b543f6545da1f2\
+             d5432955613f0fcf62d49705242a9af9e61e85dc0d651e40dfcf017b45575887",
+        ),
+    ];
+    for (password, salt, params, expected) in vectors {
+        let mut key = [0u8; 64];
+        params.derive_key(password, salt, &mut key).unwrap();
+        let hex: String = key.iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(hex, expected);
+    }
+}
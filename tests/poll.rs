@@ -0,0 +1,126 @@
+#![cfg(feature = "r1cs")]
+#![warn(unused)]
+#![deny(
+    trivial_casts,
+    trivial_numeric_casts,
+    variant_size_differences,
+    stable_features,
+    non_shorthand_field_patterns,
+    renamed_and_removed_lints,
+    unsafe_code
+)]
+
+use ark_bls12_377::{Bls12_377, Fr};
+use ark_ec::CurveGroup;
+use ark_ff::{Field, UniformRand};
+use ark_groth16::{
+    poll::{
+        member_leaf, member_tree, poseidon_config, Ballot, MembershipCircuit, Poll, PollInfo, Tally,
+    },
+    Groth16,
+};
+use ark_relations::r1cs::SynthesisError;
+use ark_std::rand::{RngCore, SeedableRng};
+use ark_std::test_rng;
+
+#[test]
+fn test_anonymous_poll() {
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
+
+    let params = poseidon_config::<Fr>();
+    let secrets: Vec<Fr> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+    let leaves: Vec<Fr> = secrets.iter().map(|s| member_leaf(&params, *s)).collect();
+    let tree = member_tree(&params, &leaves).unwrap();
+    let pk = Groth16::<Bls12_377>::generate_random_parameters_with_reduction(
+        MembershipCircuit::blank(params.clone(), tree.height()),
+        &mut rng,
+    )
+    .unwrap();
+
+    let poll = Poll::new(pk.clone(), params.clone(), tree.root(), 1, 2, &mut rng).unwrap();
+    assert_eq!(
+        poll.info(),
+        &PollInfo {
+            root: tree.root(),
+            poll_id: 1,
+            num_choices: 2,
+        }
+    );
+
+    // members cast their ballots from what the operator publishes
+    let (info, rnd) = (poll.info().clone(), poll.randomizer().clone());
+    let vote = |member: usize, choice| {
+        Ballot::cast(
+            &pk,
+            &rnd,
+            &params,
+            &info,
+            secrets[member],
+            tree.generate_proof(member).unwrap(),
+            choice,
+        )
+        .unwrap()
+    };
+    let ballots = vec![
+        vote(0, 1),
+        vote(1, 0),
+        vote(2, 1),
+        // member 0 votes again, for the same and the other choice
+        vote(0, 1),
+        vote(0, 0),
+    ];
+    assert_eq!(
+        ballots[1],
+        poll.vote(secrets[1], tree.generate_proof(1).unwrap(), 0)
+            .unwrap()
+    );
+    assert_ne!(ballots[0].proof, ballots[4].proof);
+    assert_eq!(ballots[0].nullifier, ballots[4].nullifier);
+    assert_ne!(ballots[0].nullifier, ballots[1].nullifier);
+
+    // a rescaled ballot verifies, but keeps the nullifier of its member
+    let mut rescaled = ballots[1].clone();
+    let t = Fr::rand(&mut rng);
+    rescaled.proof.a = (rescaled.proof.a * t).into_affine();
+    rescaled.proof.b = (rescaled.proof.b * t.inverse().unwrap()).into_affine();
+
+    // an outsider cannot vote, nor a ballot claim a choice out of range or
+    // another nullifier
+    assert_eq!(
+        Ballot::cast(
+            &pk,
+            &rnd,
+            &params,
+            &info,
+            Fr::rand(&mut rng),
+            tree.generate_proof(0).unwrap(),
+            0
+        ),
+        Err(SynthesisError::Unsatisfiable)
+    );
+    let mut out_of_range = vote(1, 0);
+    out_of_range.choice = 2;
+    let mut forged = vote(1, 0);
+    forged.nullifier += Fr::from(1u64);
+    let mut invalid = ballots.clone();
+    invalid.extend([rescaled, out_of_range, forged]);
+    assert_eq!(
+        poll.tally(&invalid).unwrap(),
+        Tally {
+            counts: vec![1, 2],
+            invalid: 2,
+            duplicates: 3,
+        }
+    );
+
+    // the nullifiers of a member differ between polls
+    let other_poll = Poll::new(pk, params, tree.root(), 2, 2, &mut rng).unwrap();
+    let other_ballot = other_poll
+        .vote(secrets[0], tree.generate_proof(0).unwrap(), 1)
+        .unwrap();
+    assert_ne!(ballots[0].nullifier, other_ballot.nullifier);
+    assert_eq!(
+        other_poll.tally(&[other_ballot]).unwrap().counts,
+        vec![0, 1]
+    );
+}
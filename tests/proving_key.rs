@@ -0,0 +1,162 @@
+#![warn(unused)]
+#![deny(
+    trivial_casts,
+    trivial_numeric_casts,
+    variant_size_differences,
+    stable_features,
+    non_shorthand_field_patterns,
+    renamed_and_removed_lints,
+    unsafe_code
+)]
+
+use ark_bls12_377::{Bls12_377, Fr};
+use ark_groth16::{
+    data_structures::{CircuitSpecificSetupPolymorphicSNARK, PolymorphicSNARK},
+    Groth16, ProvingKey, QueryDensity,
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::rand::{RngCore, SeedableRng};
+use ark_std::test_rng;
+
+use common::SquareChain;
+mod common;
+
+#[test]
+fn test_query_density() {
+    let density = (0..130).map(|i| i % 3 == 0).collect::<QueryDensity>();
+    assert_eq!(density.len(), 130);
+    assert_eq!(density.count_ones(), 44);
+    assert!(density.get(129) && !density.get(128) && !density.get(130));
+    assert_eq!(density.filter(126, &[10, 11, 12, 13]), vec![10, 13]);
+
+    let mut bytes = Vec::new();
+    density.serialize_compressed(&mut bytes).unwrap();
+    assert_eq!(
+        QueryDensity::deserialize_compressed(&bytes[..]).unwrap(),
+        density
+    );
+
+    // a bit set past the end of the bitmap is rejected
+    let last = bytes.len() - 1;
+    bytes[last] |= 0x80;
+    assert!(QueryDensity::deserialize_compressed(&bytes[..]).is_err());
+}
+
+#[test]
+fn test_sparse_proving_key() {
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
+
+    let num_squarings = 16;
+    let (pk, vk) = Groth16::<Bls12_377>::setup(
+        SquareChain::<Fr> {
+            x: None,
+            num_squarings,
+        },
+        &mut rng,
+    )
+    .unwrap();
+
+    // `one`, the public `y` and the `num_squarings` witnesses
+    let num_variables = num_squarings + 2;
+    assert_eq!(pk.a_query_density.len(), num_variables);
+    assert_eq!(pk.b_query_density.len(), num_variables);
+    assert_eq!(pk.a_query.len(), pk.a_query_density.count_ones());
+    assert_eq!(pk.b_g1_query.len(), pk.b_query_density.count_ones());
+    assert_eq!(pk.b_g2_query.len(), pk.b_query_density.count_ones());
+
+    // neither `one` nor `y` appear on the B side of any constraint
+    assert!(!pk.b_query_density.get(0) && !pk.b_query_density.get(1));
+    assert_eq!(pk.b_g2_query.len(), num_squarings);
+
+    let pvk = Groth16::<Bls12_377>::process_vk(&vk).unwrap();
+    let rnd = Groth16::<Bls12_377>::rndgen(&pk, &mut rng).unwrap();
+    let circuit = SquareChain {
+        x: Some(Fr::from(5u64)),
+        num_squarings,
+    };
+    let y = (0..num_squarings).fold(Fr::from(5u64), |y, _| y * y);
+    let proof = Groth16::<Bls12_377>::prove(&pk, circuit, &rnd).unwrap();
+    assert!(Groth16::<Bls12_377>::verify_with_processed_vk(&pvk, &[y], &proof, &rnd).unwrap());
+}
+
+#[test]
+fn test_proving_key_validation() {
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
+
+    let (pk, _) = Groth16::<Bls12_377>::setup(
+        SquareChain::<Fr> {
+            x: None,
+            num_squarings: 4,
+        },
+        &mut rng,
+    )
+    .unwrap();
+    let mut bytes = Vec::new();
+    pk.serialize_compressed(&mut bytes).unwrap();
+    assert_eq!(
+        ProvingKey::<Bls12_377>::deserialize_compressed(&bytes[..]).unwrap(),
+        pk
+    );
+
+    // a query shorter than its density is rejected
+    let mut truncated = pk.clone();
+    truncated.a_query.pop();
+    let mut bytes = Vec::new();
+    truncated.serialize_compressed(&mut bytes).unwrap();
+    assert!(ProvingKey::<Bls12_377>::deserialize_compressed(&bytes[..]).is_err());
+
+    // so is a `B` query in `G2` that disagrees with the one in `G1`
+    let mut truncated = pk.clone();
+    truncated.b_g2_query.pop();
+    let mut bytes = Vec::new();
+    truncated.serialize_compressed(&mut bytes).unwrap();
+    assert!(ProvingKey::<Bls12_377>::deserialize_compressed(&bytes[..]).is_err());
+
+    // and an `L` query that does not cover the witnesses
+    let mut truncated = pk;
+    truncated.l_query.pop();
+    let mut bytes = Vec::new();
+    truncated.serialize_compressed(&mut bytes).unwrap();
+    assert!(ProvingKey::<Bls12_377>::deserialize_compressed(&bytes[..]).is_err());
+}
+
+#[test]
+fn test_prepared_randomizer() {
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
+
+    let num_squarings = 8;
+    let (pk, vk) = Groth16::<Bls12_377>::setup(
+        SquareChain::<Fr> {
+            x: None,
+            num_squarings,
+        },
+        &mut rng,
+    )
+    .unwrap();
+    let pvk = Groth16::<Bls12_377>::process_vk(&vk).unwrap();
+    let prepared = Groth16::<Bls12_377>::create_prepared_randomizer(&pk, &mut rng).unwrap();
+    assert_eq!(
+        Groth16::<Bls12_377>::prepare_randomizer(&pk, &prepared.rnd).unwrap(),
+        prepared
+    );
+
+    // every witness proven under the prepared randomizer verifies against it
+    for x in 1..4u64 {
+        let circuit = SquareChain {
+            x: Some(Fr::from(x)),
+            num_squarings,
+        };
+        let y = (0..num_squarings).fold(Fr::from(x), |y, _| y * y);
+        let proof =
+            Groth16::<Bls12_377>::create_proof_with_prepared_randomizer(circuit, &pk, &prepared)
+                .unwrap();
+        assert_eq!(
+            proof,
+            Groth16::<Bls12_377>::prove(&pk, circuit, &prepared.rnd).unwrap()
+        );
+        assert!(
+            Groth16::<Bls12_377>::verify_with_processed_vk(&pvk, &[y], &proof, &prepared.rnd)
+                .unwrap()
+        );
+    }
+}
@@ -0,0 +1,132 @@
+#![warn(unused)]
+#![deny(
+    trivial_casts,
+    trivial_numeric_casts,
+    variant_size_differences,
+    stable_features,
+    non_shorthand_field_patterns,
+    renamed_and_removed_lints,
+    unsafe_code
+)]
+
+use ark_bls12_377::{Bls12_377, Fr};
+use ark_ff::{Field, UniformRand};
+use ark_groth16::{
+    data_structures::{CircuitSpecificSetupPolymorphicSNARK, PolymorphicSNARK},
+    r1cs_to_qap::{CircomReduction, LibsnarkReduction, R1CSToQAP, Radix2Reduction},
+    Groth16,
+};
+use ark_poly::{EvaluationDomain, GeneralEvaluationDomain, Radix2EvaluationDomain};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem, SynthesisMode};
+use ark_std::rand::{RngCore, SeedableRng};
+use ark_std::test_rng;
+
+use common::SquareChain;
+mod common;
+
+/// Computes the QAP witness of `QAP` for `circuit` and evaluates it against
+/// the `h_query` exponents of `QAP` at `t`, i.e. `h(t)t(t)/delta`.
+fn evaluate_h<QAP: R1CSToQAP>(circuit: SquareChain<Fr>, t: Fr, delta_inverse: Fr) -> Fr {
+    let cs = ConstraintSystem::new_ref();
+    cs.set_mode(SynthesisMode::Prove {
+        construct_matrices: true,
+    });
+    circuit.generate_constraints(cs.clone()).unwrap();
+    cs.finalize();
+
+    let domain =
+        GeneralEvaluationDomain::<Fr>::new(cs.num_constraints() + cs.num_instance_variables())
+            .unwrap();
+    let h = QAP::witness_map::<Fr, GeneralEvaluationDomain<Fr>>(cs).unwrap();
+    let scalars = QAP::h_query_scalars::<Fr, GeneralEvaluationDomain<Fr>>(
+        domain.size() - 1,
+        t,
+        domain.evaluate_vanishing_polynomial(t),
+        delta_inverse,
+    )
+    .unwrap();
+    scalars.iter().zip(&h).map(|(s, h)| *s * h).sum()
+}
+
+#[test]
+fn test_reductions_agree() {
+    let mut rng = test_rng();
+    let circuit = SquareChain {
+        x: Some(Fr::from(3u64)),
+        num_squarings: 10,
+    };
+    let t = Fr::rand(&mut rng);
+    let delta_inverse = Fr::rand(&mut rng).inverse().unwrap();
+
+    let expected = evaluate_h::<LibsnarkReduction>(circuit, t, delta_inverse);
+    assert_eq!(
+        evaluate_h::<Radix2Reduction>(circuit, t, delta_inverse),
+        expected
+    );
+    assert_eq!(
+        evaluate_h::<CircomReduction>(circuit, t, delta_inverse),
+        expected
+    );
+}
+
+#[test]
+fn test_circom_reduction() {
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
+    type Circom = Groth16<Bls12_377, CircomReduction>;
+
+    let num_squarings = 10;
+    let (pk, vk) = Circom::setup(
+        SquareChain::<Fr> {
+            x: None,
+            num_squarings,
+        },
+        &mut rng,
+    )
+    .unwrap();
+    // one base per element of the domain
+    assert_eq!(pk.h_query.len(), 16);
+
+    let pvk = Circom::process_vk(&vk).unwrap();
+    let rnd = Circom::rndgen(&pk, &mut rng).unwrap();
+    let circuit = SquareChain {
+        x: Some(Fr::from(3u64)),
+        num_squarings,
+    };
+    let y = (0..num_squarings).fold(Fr::from(3u64), |y, _| y * y);
+    let proof = Circom::prove(&pk, circuit, &rnd).unwrap();
+    assert!(Circom::verify_with_processed_vk(&pvk, &[y], &proof, &rnd).unwrap());
+    assert!(!Circom::verify_with_processed_vk(&pvk, &[y + Fr::from(1u64)], &proof, &rnd).unwrap());
+}
+
+#[test]
+fn test_radix2_reduction() {
+    type Radix2 = Groth16<Bls12_377, Radix2Reduction, Radix2EvaluationDomain<Fr>>;
+
+    let seed = test_rng().next_u64();
+    let num_squarings = 10;
+    let setup_circuit = SquareChain::<Fr> {
+        x: None,
+        num_squarings,
+    };
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(seed);
+    let (pk, vk) = Radix2::setup(setup_circuit, &mut rng).unwrap();
+    let rnd = Radix2::rndgen(&pk, &mut rng).unwrap();
+
+    // the same randomness yields the same key and proof as the libsnark reduction
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(seed);
+    let (libsnark_pk, _) = Groth16::<Bls12_377>::setup(setup_circuit, &mut rng).unwrap();
+    assert_eq!(pk, libsnark_pk);
+
+    let circuit = SquareChain {
+        x: Some(Fr::from(5u64)),
+        num_squarings,
+    };
+    let y = (0..num_squarings).fold(Fr::from(5u64), |y, _| y * y);
+    let proof = Radix2::prove(&pk, circuit, &rnd).unwrap();
+    assert_eq!(
+        proof,
+        Groth16::<Bls12_377>::prove(&pk, circuit, &rnd).unwrap()
+    );
+    let pvk = Radix2::process_vk(&vk).unwrap();
+    assert!(Radix2::verify_with_processed_vk(&pvk, &[y], &proof, &rnd).unwrap());
+}
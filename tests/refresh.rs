@@ -0,0 +1,89 @@
+#![warn(unused)]
+#![deny(
+    trivial_casts,
+    trivial_numeric_casts,
+    variant_size_differences,
+    stable_features,
+    non_shorthand_field_patterns,
+    renamed_and_removed_lints,
+    unsafe_code
+)]
+
+use ark_bls12_377::{Bls12_377, Fr};
+use ark_ec::CurveGroup;
+use ark_ff::Field;
+use ark_groth16::{prepare_verifying_key, Groth16, ProvingKey, Randomizer};
+use ark_std::rand::{RngCore, SeedableRng};
+use ark_std::test_rng;
+
+use common::SquareChain;
+mod common;
+
+#[test]
+fn test_refresh_keys() {
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
+
+    let pk = Groth16::<Bls12_377>::generate_random_parameters_with_reduction(
+        SquareChain::<Fr> {
+            x: None,
+            num_squarings: 3,
+        },
+        &mut rng,
+    )
+    .unwrap();
+    let x = Fr::from(2u64);
+    let y = x.square().square().square();
+    let prove_and_verify =
+        |pk: &ProvingKey<Bls12_377>, vk_pk: &ProvingKey<Bls12_377>, rnd: &Randomizer<Bls12_377>| {
+            let proof = Groth16::<Bls12_377>::create_proof_with_prepared_randomizer(
+                SquareChain {
+                    x: Some(x),
+                    num_squarings: 3,
+                },
+                pk,
+                &Groth16::<Bls12_377>::prepare_randomizer(pk, rnd).unwrap(),
+            )
+            .unwrap();
+            let pvk = prepare_verifying_key(&vk_pk.vk);
+            Groth16::<Bls12_377>::verify_proof(&pvk, &proof, rnd, &[y]).unwrap()
+        };
+
+    let old_rnd = Groth16::<Bls12_377>::create_randomizer(&pk, &mut rng).unwrap();
+    assert!(prove_and_verify(&pk, &pk, &old_rnd));
+
+    let (new_pk, proof) = Groth16::<Bls12_377>::refresh_keys(&pk, &mut rng);
+    assert_ne!(new_pk.delta_g1, pk.delta_g1);
+    assert!(Groth16::<Bls12_377>::verify_key_refresh(
+        &pk, &new_pk, &proof
+    ));
+
+    // the refreshed key proves under its own randomizers, and retires the
+    // randomizers of the previous key
+    let new_rnd = Groth16::<Bls12_377>::create_randomizer(&new_pk, &mut rng).unwrap();
+    assert!(prove_and_verify(&new_pk, &new_pk, &new_rnd));
+    assert!(!prove_and_verify(&new_pk, &new_pk, &old_rnd));
+    assert!(!prove_and_verify(&pk, &new_pk, &old_rnd));
+
+    // an inconsistent update, or the proof of another one, is rejected
+    let mut tampered = new_pk.clone();
+    tampered.h_query[0] = (tampered.h_query[0] + pk.delta_g1).into_affine();
+    assert!(!Groth16::<Bls12_377>::verify_key_refresh(
+        &pk, &tampered, &proof
+    ));
+    let mut tampered = new_pk.clone();
+    tampered.vk.delta_g2 = pk.vk.delta_g2;
+    assert!(!Groth16::<Bls12_377>::verify_key_refresh(
+        &pk, &tampered, &proof
+    ));
+    let (other_pk, other_proof) = Groth16::<Bls12_377>::refresh_keys(&pk, &mut rng);
+    assert!(Groth16::<Bls12_377>::verify_key_refresh(
+        &pk,
+        &other_pk,
+        &other_proof
+    ));
+    assert!(!Groth16::<Bls12_377>::verify_key_refresh(
+        &pk,
+        &new_pk,
+        &other_proof
+    ));
+}
@@ -0,0 +1,127 @@
+#![warn(unused)]
+#![deny(
+    trivial_casts,
+    trivial_numeric_casts,
+    variant_size_differences,
+    stable_features,
+    non_shorthand_field_patterns,
+    renamed_and_removed_lints,
+    unsafe_code
+)]
+
+use ark_bls12_377::{Bls12_377, Fr};
+use ark_ff::{Field, UniformRand, Zero};
+use ark_groth16::{
+    prepare_verifying_key,
+    secret::{MasterSecret, SecretScalar},
+    Groth16, Randomizer, Trapdoor,
+};
+use ark_std::rand::{RngCore, SeedableRng};
+use ark_std::test_rng;
+use zeroize::Zeroize;
+
+use common::SquareChain;
+mod common;
+
+#[test]
+fn test_secret_scalar() {
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
+
+    let value = Fr::rand(&mut rng);
+    let mut secret = SecretScalar::new(value);
+    assert_eq!(*secret.expose(), value);
+    assert_eq!(format!("{:?}", secret), "SecretScalar(<redacted>)");
+
+    let mut bytes = Vec::new();
+    secret.export(&mut bytes).unwrap();
+    assert_eq!(SecretScalar::<Fr>::import(&bytes[..]).unwrap(), secret);
+
+    secret.zeroize();
+    assert!(secret.expose().is_zero());
+}
+
+#[test]
+fn test_export_secrets() {
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
+
+    let (pk, trapdoor) = Groth16::<Bls12_377>::generate_random_parameters_with_trapdoor(
+        SquareChain::<Fr> {
+            x: None,
+            num_squarings: 3,
+        },
+        &mut rng,
+    )
+    .unwrap();
+    let rnd = Groth16::<Bls12_377>::create_randomizer(&pk, &mut rng).unwrap();
+
+    // the secrets are redacted from debug output
+    let debug = format!("{:?} {:?}", rnd, trapdoor);
+    for secret in [rnd.r.expose(), rnd.s.expose(), trapdoor.alpha.expose()] {
+        assert!(!debug.contains(&secret.to_string()));
+    }
+
+    let mut bytes = Vec::new();
+    rnd.export(&mut bytes).unwrap();
+    assert_eq!(Randomizer::<Bls12_377>::import(&bytes[..]).unwrap(), rnd);
+    assert!(Randomizer::<Bls12_377>::import(&bytes[..bytes.len() - 1]).is_err());
+
+    let mut bytes = Vec::new();
+    trapdoor.export(&mut bytes).unwrap();
+    assert_eq!(Trapdoor::<Bls12_377>::import(&bytes[..]).unwrap(), trapdoor);
+}
+
+#[test]
+fn test_derive_randomizer() {
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
+
+    let setup = |num_squarings, rng: &mut _| {
+        Groth16::<Bls12_377>::generate_random_parameters_with_reduction(
+            SquareChain::<Fr> {
+                x: None,
+                num_squarings,
+            },
+            rng,
+        )
+        .unwrap()
+    };
+    let pk = setup(3, &mut rng);
+    let other_pk = setup(4, &mut rng);
+
+    let master = MasterSecret::generate(&mut rng);
+    assert_eq!(format!("{:?}", master), "MasterSecret(<redacted>)");
+    let mut bytes = Vec::new();
+    master.export(&mut bytes).unwrap();
+    assert_eq!(MasterSecret::import(&bytes[..]).unwrap(), master);
+
+    // derivation is deterministic, and separated by context, circuit and epoch
+    let epoch = master.derive_child(b"epoch-1");
+    let rnd = Randomizer::derive(&pk, &epoch, b"round-1").unwrap();
+    assert_eq!(rnd, Randomizer::derive(&pk, &epoch, b"round-1").unwrap());
+    assert_ne!(rnd, Randomizer::derive(&pk, &epoch, b"round-2").unwrap());
+    assert_ne!(
+        rnd.r,
+        Randomizer::derive(&other_pk, &epoch, b"round-1").unwrap().r
+    );
+    assert_ne!(epoch, master.derive_child(b"epoch-2"));
+    assert_ne!(
+        rnd,
+        Randomizer::derive(&pk, &master.derive_child(b"epoch-2"), b"round-1").unwrap()
+    );
+    assert_ne!(rnd.r, rnd.s);
+
+    // derived randomizers prove like sampled ones
+    let x = Fr::from(2u64);
+    let circuit = SquareChain {
+        x: Some(x),
+        num_squarings: 3,
+    };
+    let y = x.square().square().square();
+    let proof = Groth16::<Bls12_377>::create_proof_with_prepared_randomizer(
+        circuit,
+        &pk,
+        &Groth16::<Bls12_377>::prepare_randomizer(&pk, &rnd).unwrap(),
+    )
+    .unwrap();
+    let pvk = prepare_verifying_key(&pk.vk);
+    assert!(Groth16::<Bls12_377>::verify_proof(&pvk, &proof, &rnd, &[y]).unwrap());
+}
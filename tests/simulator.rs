@@ -0,0 +1,130 @@
+#![warn(unused)]
+#![deny(
+    trivial_casts,
+    trivial_numeric_casts,
+    variant_size_differences,
+    stable_features,
+    non_shorthand_field_patterns,
+    renamed_and_removed_lints,
+    unsafe_code
+)]
+
+use ark_bls12_377::{Bls12_377, Fr};
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::{UniformRand, Zero};
+use ark_groth16::{prepare_verifying_key, Groth16};
+use ark_std::rand::{RngCore, SeedableRng};
+use ark_std::test_rng;
+
+use common::SquareChain;
+mod common;
+
+#[test]
+fn test_simulate_proof() {
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
+
+    let num_squarings = 5;
+    let (pk, trapdoor) = Groth16::<Bls12_377>::generate_random_parameters_with_trapdoor(
+        SquareChain::<Fr> {
+            x: None,
+            num_squarings,
+        },
+        &mut rng,
+    )
+    .unwrap();
+    let vk = &pk.vk;
+    assert_eq!(
+        vk.gamma_g2 * trapdoor.beta.expose(),
+        vk.beta_g2 * trapdoor.gamma.expose()
+    );
+    assert_eq!(
+        vk.delta_g2 * trapdoor.beta.expose(),
+        vk.beta_g2 * trapdoor.delta.expose()
+    );
+
+    let pvk = prepare_verifying_key(vk);
+    let rnd = Groth16::<Bls12_377>::create_randomizer(&pk, &mut rng).unwrap();
+
+    // any statement, true or not, has a simulated proof
+    let y = Fr::rand(&mut rng);
+    let proof = Groth16::<Bls12_377>::simulate_proof(&trapdoor, vk, &[y], &rnd).unwrap();
+    assert!(Groth16::<Bls12_377>::verify_proof(&pvk, &proof, &rnd, &[y]).unwrap());
+    assert!(
+        !Groth16::<Bls12_377>::verify_proof(&pvk, &proof, &rnd, &[y + Fr::from(1u64)]).unwrap()
+    );
+
+    // like honest proofs, simulated proofs are deterministic under a
+    // randomizer, and bound to it
+    assert_eq!(
+        proof,
+        Groth16::<Bls12_377>::simulate_proof(&trapdoor, vk, &[y], &rnd).unwrap()
+    );
+    let other_rnd = Groth16::<Bls12_377>::create_randomizer(&pk, &mut rng).unwrap();
+    assert!(!Groth16::<Bls12_377>::verify_proof(&pvk, &proof, &other_rnd, &[y]).unwrap());
+    let other = Groth16::<Bls12_377>::simulate_proof(&trapdoor, vk, &[y], &other_rnd).unwrap();
+    assert_ne!(proof.a, other.a);
+    assert_ne!(proof.b, other.b);
+    assert!(Groth16::<Bls12_377>::verify_proof(&pvk, &other, &other_rnd, &[y]).unwrap());
+
+    // the same instance under another trapdoor has unrelated elements
+    let (other_pk, other_trapdoor) =
+        Groth16::<Bls12_377>::generate_random_parameters_with_trapdoor(
+            SquareChain::<Fr> {
+                x: None,
+                num_squarings,
+            },
+            &mut rng,
+        )
+        .unwrap();
+    let unrelated =
+        Groth16::<Bls12_377>::simulate_proof(&other_trapdoor, &other_pk.vk, &[y], &rnd).unwrap();
+    assert_ne!(proof.a, unrelated.a);
+
+    assert!(Groth16::<Bls12_377>::simulate_proof(&trapdoor, vk, &[], &rnd).is_err());
+    assert!(Groth16::<Bls12_377>::simulate_committed_proof(&trapdoor, vk, &[y], &rnd).is_err());
+}
+
+#[test]
+fn test_simulate_committed_proof() {
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
+
+    let num_squarings = 5;
+    let (pk, trapdoor) =
+        Groth16::<Bls12_377>::generate_random_parameters_with_commitments_and_trapdoor(
+            SquareChain::<Fr> {
+                x: None,
+                num_squarings,
+            },
+            &[0, 2],
+            &mut rng,
+        )
+        .unwrap();
+    let vk = &pk.vk;
+    let pvk = prepare_verifying_key(vk);
+    let rnd = Groth16::<Bls12_377>::create_randomizer(&pk, &mut rng).unwrap();
+
+    // a blinded commitment and its opening are simulated along with the proof
+    let y = Fr::rand(&mut rng);
+    let proof = Groth16::<Bls12_377>::simulate_committed_proof(&trapdoor, vk, &[y], &rnd).unwrap();
+    assert!(!proof.opening.blinding_response.is_zero());
+    assert!(Groth16::<Bls12_377>::verify_commitment_opening(vk, &proof));
+    assert!(Groth16::<Bls12_377>::verify_committed_proof(&pvk, &proof, &rnd, &[y]).unwrap());
+    assert!(!Groth16::<Bls12_377>::verify_committed_proof(
+        &pvk,
+        &proof,
+        &rnd,
+        &[y + Fr::from(1u64)]
+    )
+    .unwrap());
+    assert_eq!(
+        proof,
+        Groth16::<Bls12_377>::simulate_committed_proof(&trapdoor, vk, &[y], &rnd).unwrap()
+    );
+
+    // the blinding `eta_gamma_inv_g1` of `D` is accounted for in `C`
+    let mut unblinded = proof.clone();
+    unblinded.d = (proof.d.into_group() - vk.eta_gamma_inv_g1).into_affine();
+    assert!(!Groth16::<Bls12_377>::verify_committed_proof(&pvk, &unblinded, &rnd, &[y]).unwrap());
+
+    assert!(Groth16::<Bls12_377>::simulate_proof(&trapdoor, vk, &[y], &rnd).is_err());
+}
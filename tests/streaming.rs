@@ -0,0 +1,163 @@
+#![warn(unused)]
+#![deny(
+    trivial_casts,
+    trivial_numeric_casts,
+    variant_size_differences,
+    stable_features,
+    non_shorthand_field_patterns,
+    renamed_and_removed_lints,
+    unsafe_code
+)]
+
+use ark_bls12_377::{Bls12_377, Fr, G1Affine, G1Projective};
+use ark_groth16::{
+    data_structures::{CircuitSpecificSetupPolymorphicSNARK, PolymorphicSNARK},
+    streaming::{ProverConfig, ProvingKeyFile, ProvingKeySource, Query, SetupConfig},
+    Groth16,
+};
+use ark_serialize::Validate;
+use ark_std::rand::{rngs::StdRng, RngCore, SeedableRng};
+use ark_std::test_rng;
+use std::fs::File;
+use std::io::{Seek, SeekFrom, Write};
+
+use common::SquareChain;
+mod common;
+
+#[test]
+fn test_prove_with_budget() {
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
+
+    let num_squarings = 20;
+    let (pk, vk) = Groth16::<Bls12_377>::setup(
+        SquareChain::<Fr> {
+            x: None,
+            num_squarings,
+        },
+        &mut rng,
+    )
+    .unwrap();
+    let pvk = Groth16::<Bls12_377>::process_vk(&vk).unwrap();
+    let rnd = Groth16::<Bls12_377>::rndgen(&pk, &mut rng).unwrap();
+
+    let circuit = SquareChain {
+        x: Some(Fr::from(3u64)),
+        num_squarings,
+    };
+    let y = (0..num_squarings).fold(Fr::from(3u64), |y, _| y * y);
+    let expected = Groth16::<Bls12_377>::prove(&pk, circuit, &rnd).unwrap();
+    let prepared = Groth16::<Bls12_377>::prepare_randomizer(&pk, &rnd).unwrap();
+
+    // chunks of a handful of bases from the in-memory key
+    let config = ProverConfig::with_memory_budget(1000);
+    assert!(config.chunk_len::<G1Affine>() < 8);
+    let proof =
+        Groth16::<Bls12_377>::create_proof_with_budget(circuit, &pk, &config, &prepared).unwrap();
+    assert_eq!(proof, expected);
+
+    // the same key read from a file, one base at a time
+    let path = std::env::temp_dir().join(format!("pzkp-{}.key", rng.next_u64()));
+    ProvingKeyFile::write(&pk, File::create(&path).unwrap()).unwrap();
+    let pk_file = ProvingKeyFile::<Bls12_377>::open(&path, Validate::Yes).unwrap();
+    assert_eq!(pk_file.query_len(Query::H), pk.h_query.len());
+    assert_eq!(pk_file.load().unwrap(), pk);
+
+    let config = ProverConfig::with_memory_budget(0);
+    let proof =
+        Groth16::<Bls12_377>::create_proof_with_budget(circuit, &pk_file, &config, &prepared)
+            .unwrap();
+    assert_eq!(proof, expected);
+    assert!(Groth16::<Bls12_377>::verify_with_processed_vk(&pvk, &[y], &proof, &rnd).unwrap());
+
+    // a truncated file is rejected when opened
+    let len = std::fs::metadata(&path).unwrap().len();
+    File::options()
+        .write(true)
+        .open(&path)
+        .unwrap()
+        .set_len(len - 1)
+        .unwrap();
+    assert!(ProvingKeyFile::<Bls12_377>::open(&path, Validate::Yes).is_err());
+
+    // so is a parameters length overflowing the offsets of the queries
+    ProvingKeyFile::write(&pk, File::create(&path).unwrap()).unwrap();
+    let mut file = File::options().write(true).open(&path).unwrap();
+    file.seek(SeekFrom::Start(5)).unwrap();
+    file.write_all(&u64::MAX.to_le_bytes()).unwrap();
+    drop(file);
+    assert!(ProvingKeyFile::<Bls12_377>::open(&path, Validate::Yes).is_err());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_key_file_setup_resumes() {
+    let seed = test_rng().next_u64();
+    let circuit = SquareChain::<Fr> {
+        x: None,
+        num_squarings: 20,
+    };
+    let path = std::env::temp_dir().join(format!("pzkp-setup-{}.key", seed));
+    let mut checkpoint_path = path.clone().into_os_string();
+    checkpoint_path.push(".checkpoint");
+
+    // stop the setup after a few chunks of a handful of bases, as if it crashed
+    let config = SetupConfig::with_memory_budget(1000);
+    assert!(config.chunk_len::<G1Projective>() < 8);
+    let mut chunks = 0;
+    let vk = Groth16::<Bls12_377>::generate_key_file_with_progress(
+        circuit,
+        &path,
+        &config,
+        &mut StdRng::seed_from_u64(seed),
+        |progress| {
+            chunks += 1;
+            assert!(progress.bases_written <= progress.num_bases);
+            chunks < 3
+        },
+    )
+    .unwrap();
+    assert!(vk.is_none());
+    assert!(std::fs::metadata(&checkpoint_path).is_ok());
+    let key_bytes = std::fs::read(&path).unwrap();
+    let checkpoint_bytes = std::fs::read(&checkpoint_path).unwrap();
+    let resume = || {
+        Groth16::<Bls12_377>::generate_key_file_with_reduction(
+            circuit,
+            &path,
+            &config,
+            &mut StdRng::seed_from_u64(seed + 1),
+        )
+    };
+
+    // resuming without the key file, or with a truncated one, fails
+    std::fs::remove_file(&path).unwrap();
+    assert!(resume().is_err());
+    assert!(std::fs::metadata(&path).is_err());
+    std::fs::write(&path, &key_bytes[..key_bytes.len() - 1]).unwrap();
+    assert!(resume().is_err());
+    std::fs::write(&path, &key_bytes).unwrap();
+
+    // so does a checkpoint past the end of its query or of the last query
+    let len = checkpoint_bytes.len();
+    for (position, value) in [(len - 8, u64::MAX), (len - 16, 6)] {
+        let mut corrupted = checkpoint_bytes.clone();
+        corrupted[position..position + 8].copy_from_slice(&value.to_le_bytes());
+        std::fs::write(&checkpoint_path, corrupted).unwrap();
+        assert!(resume().is_err());
+    }
+    std::fs::write(&checkpoint_path, &checkpoint_bytes).unwrap();
+
+    // resuming ignores the new randomness and finishes the same key
+    let vk = resume().unwrap();
+    assert!(std::fs::metadata(&checkpoint_path).is_err());
+
+    let pk = Groth16::<Bls12_377>::generate_random_parameters_with_reduction(
+        circuit,
+        &mut StdRng::seed_from_u64(seed),
+    )
+    .unwrap();
+    assert_eq!(vk, pk.vk);
+    let pk_file = ProvingKeyFile::<Bls12_377>::open(&path, Validate::Yes).unwrap();
+    assert_eq!(pk_file.load().unwrap(), pk);
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,199 @@
+#![warn(unused)]
+#![deny(
+    trivial_casts,
+    trivial_numeric_casts,
+    variant_size_differences,
+    stable_features,
+    non_shorthand_field_patterns,
+    renamed_and_removed_lints,
+    unsafe_code
+)]
+
+use ark_bls12_377::{Bls12_377, Fr};
+use ark_ec::CurveGroup;
+use ark_ff::{Field, UniformRand};
+use ark_groth16::{
+    submission::{Contest, ContestPhase, Reveal, SubmissionCommitment, SubmissionError},
+    Groth16, Proof,
+};
+use ark_relations::{
+    lc,
+    r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError},
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::rand::{RngCore, SeedableRng};
+use ark_std::test_rng;
+
+/// Proves knowledge of a fourth root `x` of the public `y`, exposing `x^3` as
+/// the nullifier of the solution.
+#[derive(Clone, Copy)]
+struct FourthRoot {
+    x: Option<Fr>,
+}
+
+impl FourthRoot {
+    fn nullifier(x: Fr) -> Fr {
+        x.square() * x
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for FourthRoot {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        let missing = || SynthesisError::AssignmentMissing;
+        let y =
+            cs.new_input_variable(|| self.x.map(|x| x.square().square()).ok_or_else(missing))?;
+        let nullifier =
+            cs.new_input_variable(|| self.x.map(Self::nullifier).ok_or_else(missing))?;
+        let x = cs.new_witness_variable(|| self.x.ok_or_else(missing))?;
+        let x2 = cs.new_witness_variable(|| self.x.map(|x| x.square()).ok_or_else(missing))?;
+        cs.enforce_constraint(lc!() + x, lc!() + x, lc!() + x2)?;
+        cs.enforce_constraint(lc!() + x2, lc!() + x2, lc!() + y)?;
+        cs.enforce_constraint(lc!() + x2, lc!() + x, lc!() + nullifier)
+    }
+}
+
+#[test]
+fn test_commit_reveal_contest() {
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
+
+    let pk = Groth16::<Bls12_377>::generate_random_parameters_with_reduction(
+        FourthRoot { x: None },
+        &mut rng,
+    )
+    .unwrap();
+    let rnd = Groth16::<Bls12_377>::create_randomizer(&pk, &mut rng).unwrap();
+    let prepared = Groth16::<Bls12_377>::prepare_randomizer(&pk, &rnd).unwrap();
+    let prove = |x: Fr| -> (Proof<Bls12_377>, Fr) {
+        let proof = Groth16::<Bls12_377>::create_proof_with_prepared_randomizer(
+            FourthRoot { x: Some(x) },
+            &pk,
+            &prepared,
+        )
+        .unwrap();
+        (proof, FourthRoot::nullifier(x))
+    };
+    let submit = |(proof, nullifier): (Proof<Bls12_377>, Fr),
+                  prover_id: &[u8],
+                  rng: &mut ark_std::rand::rngs::StdRng| {
+        Reveal::new(proof, nullifier, prover_id.to_vec(), rng)
+    };
+
+    // x and -x are the distinct solutions of x^4 = 16
+    let (x, minus_x) = (Fr::from(2u64), -Fr::from(2u64));
+    let y = x.square().square();
+    let mut contest = Contest::new(&pk.vk, &rnd, vec![y]);
+
+    let alice = submit(prove(x), b"alice", &mut rng);
+    let bob = submit(prove(minus_x), b"bob", &mut rng);
+    let carol = submit(prove(x), b"carol", &mut rng);
+    let dave = submit(prove(x), b"dave", &mut rng);
+    assert_ne!(alice.commitment(), carol.commitment());
+
+    // bob also submits a rescaled copy of his proof, which verifies but has
+    // the nullifier of his solution
+    let (mut proof, nullifier) = prove(minus_x);
+    let t = Fr::rand(&mut rng);
+    proof.a = (proof.a * t).into_affine();
+    proof.b = (proof.b * t.inverse().unwrap()).into_affine();
+    let bob_again = submit((proof, nullifier), b"bob's sybil", &mut rng);
+
+    // carol finds the solution of alice independently, but commits later
+    contest.commit(carol.commitment(), 20).unwrap();
+    contest.commit(bob_again.commitment(), 16).unwrap();
+    contest.commit(alice.commitment(), 10).unwrap();
+    contest.commit(bob.commitment(), 15).unwrap();
+    assert_eq!(
+        contest.commit(alice.commitment(), 30),
+        Err(SubmissionError::DuplicateCommitment)
+    );
+    assert_eq!(
+        contest.reveal(alice.clone()),
+        Err(SubmissionError::WrongPhase(ContestPhase::Commit))
+    );
+    contest.close_commitments().unwrap();
+
+    // dave copies the proof of alice, but committed nothing in time
+    assert_eq!(
+        contest.commit(dave.commitment(), 40),
+        Err(SubmissionError::WrongPhase(ContestPhase::Reveal))
+    );
+    assert_eq!(
+        contest.reveal(dave),
+        Err(SubmissionError::UnknownCommitment)
+    );
+
+    // the messages and the state survive serialization
+    let mut bytes = Vec::new();
+    alice.serialize_compressed(&mut bytes).unwrap();
+    let alice = Reveal::<Bls12_377>::deserialize_compressed(&bytes[..]).unwrap();
+    let mut bytes = Vec::new();
+    bob.commitment().serialize_compressed(&mut bytes).unwrap();
+    assert_eq!(
+        SubmissionCommitment::deserialize_compressed(&bytes[..]).unwrap(),
+        bob.commitment()
+    );
+
+    contest.reveal(carol).unwrap();
+    contest.reveal(alice.clone()).unwrap();
+    contest.reveal(bob).unwrap();
+    contest.reveal(bob_again).unwrap();
+    assert_eq!(contest.reveal(alice), Err(SubmissionError::AlreadyRevealed));
+
+    let mut bytes = Vec::new();
+    contest.serialize_compressed(&mut bytes).unwrap();
+    let mut contest = Contest::<Bls12_377>::deserialize_compressed(&bytes[..]).unwrap();
+
+    let awards = contest.close().unwrap();
+    assert_eq!(contest.phase(), ContestPhase::Closed);
+    let winners: Vec<_> = awards
+        .iter()
+        .map(|award| (award.prover_id.as_slice(), award.timestamp))
+        .collect();
+    assert_eq!(winners, [(&b"alice"[..], 10), (&b"bob"[..], 15)]);
+    assert_eq!(contest.awards(), awards);
+}
+
+#[test]
+fn test_reveal_invalid_proof() {
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
+
+    let pk = Groth16::<Bls12_377>::generate_random_parameters_with_reduction(
+        FourthRoot { x: None },
+        &mut rng,
+    )
+    .unwrap();
+    let rnd = Groth16::<Bls12_377>::create_randomizer(&pk, &mut rng).unwrap();
+    let other_rnd = Groth16::<Bls12_377>::create_randomizer(&pk, &mut rng).unwrap();
+    let x = Fr::from(3u64);
+    let y = x.square().square();
+    let prove = |rnd| {
+        Groth16::<Bls12_377>::create_proof_with_prepared_randomizer(
+            FourthRoot { x: Some(x) },
+            &pk,
+            &Groth16::<Bls12_377>::prepare_randomizer(&pk, rnd).unwrap(),
+        )
+        .unwrap()
+    };
+    let mut contest = Contest::new(&pk.vk, &rnd, vec![y]);
+
+    // a proof under another randomizer does not verify in the contest
+    let reveal = Reveal::new(
+        prove(&other_rnd),
+        FourthRoot::nullifier(x),
+        b"mallory".to_vec(),
+        &mut rng,
+    );
+    // nor does a proof revealed with another nullifier
+    let forged = Reveal::new(
+        prove(&rnd),
+        FourthRoot::nullifier(x) + Fr::from(1u64),
+        b"mallory".to_vec(),
+        &mut rng,
+    );
+    contest.commit(reveal.commitment(), 1).unwrap();
+    contest.commit(forged.commitment(), 2).unwrap();
+    contest.close_commitments().unwrap();
+    assert_eq!(contest.reveal(reveal), Err(SubmissionError::InvalidProof));
+    assert_eq!(contest.reveal(forged), Err(SubmissionError::InvalidProof));
+    assert!(contest.close().unwrap().is_empty());
+}
@@ -14,13 +14,14 @@ use ark_ec::CurveGroup;
 use ark_ff::{Field, UniformRand};
 use ark_groth16::{
     data_structures::{CircuitSpecificSetupPolymorphicSNARK, PolymorphicSNARK},
-    Proof,
+    Proof, ProverIndex,
 };
 use ark_r1cs_std::{
     prelude::{AllocVar, AllocationMode, Boolean, EqGadget},
     uint8::UInt8,
 };
 use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, Namespace, SynthesisError};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_std::rand::{RngCore, SeedableRng};
 use ark_std::test_rng;
 
@@ -189,8 +190,16 @@ fn flatten_input(sudoku: &[[u8; 9]; 9]) -> Vec<Fr> {
 #[test]
 fn test_sudoku() {
     // We're going to use the Groth16 proving system.
-    use ark_groth16::Groth16;
+    prove_sudoku::<ark_groth16::Groth16<Bls12_377>>();
+}
+
+#[test]
+fn test_sudoku_gm17() {
+    // The same, with the non-malleable GM17 proving system.
+    prove_sudoku::<ark_groth16::gm17::GM17<Bls12_377>>();
+}
 
+fn prove_sudoku<S: CircuitSpecificSetupPolymorphicSNARK<Fr>>() {
     // This may not be cryptographically safe, use
     // `OsRng` (for example) in production software.
     let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
@@ -201,12 +210,12 @@ fn test_sudoku() {
             sudoku: None,
             solution: None,
         };
-        Groth16::<Bls12_377>::setup(c, &mut rng).unwrap()
+        S::setup(c, &mut rng).unwrap()
     };
-    let pvk = Groth16::<Bls12_377>::process_vk(&vk).unwrap();
+    let pvk = S::process_vk(&vk).unwrap();
 
     // rndgen
-    let rnd = Groth16::<Bls12_377>::rndgen(&pk, &mut rng).unwrap();
+    let rnd = S::rndgen(&pk, &mut rng).unwrap();
 
     let sudoku = [
         [4, 5, 2, 6, 7, 8, 3, 1, 9],
@@ -251,16 +260,16 @@ fn test_sudoku() {
             sudoku: Some(sudoku),
             solution: Some(solution.clone()),
         };
-        let proof = Groth16::<Bls12_377>::prove(&pk, puzzle, &rnd).unwrap();
+        let proof = S::prove(&pk, puzzle, &rnd).unwrap();
         proofs.push(proof);
     }
 
     // verify
     let flat = flatten_input(&sudoku);
     for (_, proof) in proofs.iter().enumerate() {
-        assert!(Groth16::<Bls12_377>::verify_with_processed_vk(&pvk, &flat, &proof, &rnd).unwrap());
+        assert!(S::verify_with_processed_vk(&pvk, &flat, &proof, &rnd).unwrap());
     }
-    assert!(Groth16::<Bls12_377>::compare_all_proofs(&proofs).unwrap());
+    assert!(S::compare_all_proofs(&proofs).unwrap());
 }
 
 #[test]
@@ -416,3 +425,182 @@ fn test_malleability_attack() {
     }
     assert!(Groth16::<Bls12_377>::compare_all_proofs(&proofs).unwrap());
 }
+
+#[test]
+fn test_sudoku_prove_many() {
+    // We're going to use the Groth16 proving system.
+    prove_many_sudoku::<ark_groth16::Groth16<Bls12_377>>();
+}
+
+#[test]
+fn test_sudoku_prove_many_gm17() {
+    // The same, with the non-malleable GM17 proving system.
+    prove_many_sudoku::<ark_groth16::gm17::GM17<Bls12_377>>();
+}
+
+fn prove_many_sudoku<S: CircuitSpecificSetupPolymorphicSNARK<Fr>>()
+where
+    S::Proof: PartialEq + std::fmt::Debug,
+{
+    // This may not be cryptographically safe, use
+    // `OsRng` (for example) in production software.
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
+
+    // setup
+    let (pk, vk) = {
+        let c = Puzzle::<9> {
+            sudoku: None,
+            solution: None,
+        };
+        S::setup(c, &mut rng).unwrap()
+    };
+    let pvk = S::process_vk(&vk).unwrap();
+
+    // rndgen
+    let rnd = S::rndgen(&pk, &mut rng).unwrap();
+
+    let sudoku = [
+        [4, 5, 2, 6, 7, 8, 3, 1, 9],
+        [8, 7, 3, 1, 0, 9, 4, 0, 6],
+        [1, 9, 6, 3, 4, 0, 8, 7, 0],
+        [6, 1, 5, 4, 9, 7, 2, 8, 3],
+        [2, 3, 8, 5, 1, 6, 7, 9, 4],
+        [9, 4, 7, 2, 8, 3, 5, 6, 1],
+        [5, 2, 1, 7, 6, 4, 9, 3, 8],
+        [3, 8, 4, 9, 0, 1, 6, 0, 7],
+        [7, 6, 9, 8, 3, 0, 1, 4, 0],
+    ];
+    let solutions = [
+        [
+            [4, 5, 2, 6, 7, 8, 3, 1, 9],
+            [8, 7, 3, 1, 5, 9, 4, 2, 6],
+            [1, 9, 6, 3, 4, 2, 8, 7, 5],
+            [6, 1, 5, 4, 9, 7, 2, 8, 3],
+            [2, 3, 8, 5, 1, 6, 7, 9, 4],
+            [9, 4, 7, 2, 8, 3, 5, 6, 1],
+            [5, 2, 1, 7, 6, 4, 9, 3, 8],
+            [3, 8, 4, 9, 2, 1, 6, 5, 7],
+            [7, 6, 9, 8, 3, 5, 1, 4, 2],
+        ],
+        [
+            [4, 5, 2, 6, 7, 8, 3, 1, 9],
+            [8, 7, 3, 1, 2, 9, 4, 5, 6],
+            [1, 9, 6, 3, 4, 5, 8, 7, 2],
+            [6, 1, 5, 4, 9, 7, 2, 8, 3],
+            [2, 3, 8, 5, 1, 6, 7, 9, 4],
+            [9, 4, 7, 2, 8, 3, 5, 6, 1],
+            [5, 2, 1, 7, 6, 4, 9, 3, 8],
+            [3, 8, 4, 9, 5, 1, 6, 2, 7],
+            [7, 6, 9, 8, 3, 2, 1, 4, 5],
+        ],
+    ];
+
+    // prove all solutions at once
+    let puzzles = solutions
+        .iter()
+        .map(|solution| Puzzle::<9> {
+            sudoku: Some(sudoku),
+            solution: Some(*solution),
+        })
+        .collect::<Vec<_>>();
+    let proofs = S::prove_many(&pk, puzzles, &rnd).unwrap();
+    assert_eq!(proofs.len(), solutions.len());
+
+    // proofs are deterministic per randomizer, so they match one-by-one proving
+    for (proof, solution) in proofs.iter().zip(solutions) {
+        let puzzle = Puzzle::<9> {
+            sudoku: Some(sudoku),
+            solution: Some(solution),
+        };
+        assert_eq!(*proof, S::prove(&pk, puzzle, &rnd).unwrap());
+    }
+
+    // verify
+    let flat = flatten_input(&sudoku);
+    for proof in proofs.iter() {
+        assert!(S::verify_with_processed_vk(&pvk, &flat, proof, &rnd).unwrap());
+    }
+    assert!(S::compare_all_proofs(&proofs).unwrap());
+}
+
+#[test]
+fn test_sudoku_prover_index() {
+    // We're going to use the Groth16 proving system.
+    use ark_groth16::Groth16;
+
+    // This may not be cryptographically safe, use
+    // `OsRng` (for example) in production software.
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
+
+    // setup, keeping the constraint matrices around
+    let index = {
+        let c = Puzzle::<9> {
+            sudoku: None,
+            solution: None,
+        };
+        Groth16::<Bls12_377>::generate_random_index_with_reduction(c, &mut rng).unwrap()
+    };
+    let pvk = Groth16::<Bls12_377>::process_vk(&index.pk.vk).unwrap();
+
+    // the index survives a serialization round trip
+    let mut bytes = Vec::new();
+    index.serialize_uncompressed(&mut bytes).unwrap();
+    let index = ProverIndex::<Bls12_377>::deserialize_uncompressed(&bytes[..]).unwrap();
+
+    // rndgen
+    let rnd = Groth16::<Bls12_377>::rndgen(&index.pk, &mut rng).unwrap();
+
+    let sudoku = [
+        [4, 5, 2, 6, 7, 8, 3, 1, 9],
+        [8, 7, 3, 1, 0, 9, 4, 0, 6],
+        [1, 9, 6, 3, 4, 0, 8, 7, 0],
+        [6, 1, 5, 4, 9, 7, 2, 8, 3],
+        [2, 3, 8, 5, 1, 6, 7, 9, 4],
+        [9, 4, 7, 2, 8, 3, 5, 6, 1],
+        [5, 2, 1, 7, 6, 4, 9, 3, 8],
+        [3, 8, 4, 9, 0, 1, 6, 0, 7],
+        [7, 6, 9, 8, 3, 0, 1, 4, 0],
+    ];
+    let solution = [
+        [4, 5, 2, 6, 7, 8, 3, 1, 9],
+        [8, 7, 3, 1, 5, 9, 4, 2, 6],
+        [1, 9, 6, 3, 4, 2, 8, 7, 5],
+        [6, 1, 5, 4, 9, 7, 2, 8, 3],
+        [2, 3, 8, 5, 1, 6, 7, 9, 4],
+        [9, 4, 7, 2, 8, 3, 5, 6, 1],
+        [5, 2, 1, 7, 6, 4, 9, 3, 8],
+        [3, 8, 4, 9, 2, 1, 6, 5, 7],
+        [7, 6, 9, 8, 3, 5, 1, 4, 2],
+    ];
+
+    // prove without constructing constraints
+    let puzzle = Puzzle::<9> {
+        sudoku: Some(sudoku),
+        solution: Some(solution),
+    };
+    let prepared = Groth16::<Bls12_377>::prepare_randomizer(&index.pk, &rnd).unwrap();
+    let proof = Groth16::<Bls12_377>::create_proof_with_index(puzzle, &index, &prepared).unwrap();
+
+    // the proof matches the one from full constraint synthesis
+    let puzzle = Puzzle::<9> {
+        sudoku: Some(sudoku),
+        solution: Some(solution),
+    };
+    assert_eq!(proof, Groth16::<Bls12_377>::prove(&index.pk, puzzle, &rnd).unwrap());
+
+    // verify
+    let flat = flatten_input(&sudoku);
+    assert!(Groth16::<Bls12_377>::verify_with_processed_vk(&pvk, &flat, &proof, &rnd).unwrap());
+
+    // a wrong solution is rejected instead of proven
+    let mut wrong = solution;
+    wrong[1][4] = 2;
+    let puzzle = Puzzle::<9> {
+        sudoku: Some(sudoku),
+        solution: Some(wrong),
+    };
+    assert!(matches!(
+        Groth16::<Bls12_377>::create_proof_with_index(puzzle, &index, &prepared),
+        Err(SynthesisError::Unsatisfiable)
+    ));
+}
@@ -0,0 +1,200 @@
+#![warn(unused)]
+#![deny(
+    trivial_casts,
+    trivial_numeric_casts,
+    variant_size_differences,
+    stable_features,
+    non_shorthand_field_patterns,
+    renamed_and_removed_lints,
+    unsafe_code
+)]
+
+use ark_bls12_377::{Bls12_377, Fr};
+use ark_ec::CurveGroup;
+use ark_ff::{Field, One};
+use ark_groth16::{
+    prepare_verifying_key,
+    threshold::{
+        ProductMessage, ProofRequest, ProofShare, ShareMessage, ThresholdError, ThresholdParty,
+    },
+    Groth16, Randomizer,
+};
+use ark_relations::r1cs::SynthesisError;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::rand::{RngCore, SeedableRng};
+use ark_std::test_rng;
+
+use common::SquareChain;
+mod common;
+
+#[test]
+fn test_threshold_randomizer() {
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
+
+    let pk = Groth16::<Bls12_377>::generate_random_parameters_with_reduction(
+        SquareChain::<Fr> {
+            x: None,
+            num_squarings: 3,
+        },
+        &mut rng,
+    )
+    .unwrap();
+    let (rnd, parties) =
+        Groth16::<Bls12_377>::create_threshold_randomizer(&pk, 3, &mut rng).unwrap();
+
+    // the public part alone cannot be proven under
+    let verifier_rnd = Randomizer::from(rnd.clone());
+    assert_eq!(
+        Groth16::<Bls12_377>::prepare_randomizer(&pk, &verifier_rnd).unwrap_err(),
+        SynthesisError::UnexpectedIdentity
+    );
+
+    // a prover holding the witness proves jointly with all the parties,
+    // which keep their shares
+    let x = Fr::from(2u64);
+    let circuit = SquareChain {
+        x: Some(x),
+        num_squarings: 3,
+    };
+    let prover = Groth16::<Bls12_377>::create_threshold_prover(circuit, &pk, &rnd).unwrap();
+    let mut bytes = Vec::new();
+    prover.request().serialize_compressed(&mut bytes).unwrap();
+    let request = ProofRequest::<Bls12_377>::deserialize_compressed(&bytes[..]).unwrap();
+    let mut replies = Vec::new();
+    for party in parties.iter() {
+        let mut bytes = Vec::new();
+        party
+            .prove_share(&request)
+            .unwrap()
+            .serialize_compressed(&mut bytes)
+            .unwrap();
+        replies.push(ProofShare::<Bls12_377>::deserialize_compressed(&bytes[..]).unwrap());
+    }
+    let shares = parties[0].shares();
+    let proof = prover.finish(shares, &replies).unwrap();
+    let pvk = prepare_verifying_key(&pk.vk);
+    let y = x.square().square().square();
+    assert!(Groth16::<Bls12_377>::verify_proof(&pvk, &proof, &verifier_rnd, &[y]).unwrap());
+    assert!(
+        !Groth16::<Bls12_377>::verify_proof(&pvk, &proof, &verifier_rnd, &[y + Fr::one()]).unwrap()
+    );
+
+    // every contribution of a party is checked against its shares
+    let mut tampered = replies.clone();
+    tampered[1].c_share = (tampered[1].c_share + pk.delta_g1).into_affine();
+    assert_eq!(
+        prover.finish(shares, &tampered).unwrap_err(),
+        ThresholdError::InvalidShare(1)
+    );
+    let mut tampered = replies.clone();
+    tampered[2].r_delta_g1 = (tampered[2].r_delta_g1 + pk.delta_g1).into_affine();
+    assert_eq!(
+        prover.finish(shares, &tampered).unwrap_err(),
+        ThresholdError::InvalidShare(2)
+    );
+    let mut tampered = replies.clone();
+    tampered[0].s_delta_g2 = (tampered[0].s_delta_g2 + pk.vk.delta_g2).into_affine();
+    assert_eq!(
+        prover.finish(shares, &tampered).unwrap_err(),
+        ThresholdError::InvalidShare(0)
+    );
+    assert_eq!(
+        prover.finish(shares, &replies[..2]).unwrap_err(),
+        ThresholdError::MissingMessages
+    );
+
+    // the shares of another randomizer are rejected
+    let (_, others) = Groth16::<Bls12_377>::create_threshold_randomizer(&pk, 3, &mut rng).unwrap();
+    assert_eq!(
+        prover.finish(others[0].shares(), &replies).unwrap_err(),
+        ThresholdError::RandomizerMismatch
+    );
+}
+
+#[test]
+fn test_threshold_rounds() {
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
+
+    let pk = Groth16::<Bls12_377>::generate_random_parameters_with_reduction(
+        SquareChain::<Fr> {
+            x: None,
+            num_squarings: 3,
+        },
+        &mut rng,
+    )
+    .unwrap();
+    assert_eq!(
+        Groth16::<Bls12_377>::create_threshold_randomizer(&pk, 1, &mut rng).unwrap_err(),
+        ThresholdError::InvalidParties
+    );
+
+    let mut parties: Vec<_> = (0..2)
+        .map(|i| ThresholdParty::<Bls12_377>::new(&pk, i, 2, &mut rng).unwrap())
+        .collect();
+    let commitments: Vec<_> = parties.iter().map(ThresholdParty::commit).collect();
+    assert_eq!(
+        parties[0].multiply(&[]).unwrap_err(),
+        ThresholdError::UnexpectedRound
+    );
+    assert_eq!(
+        parties[0].reveal(&commitments[..1]).unwrap_err(),
+        ThresholdError::MissingMessages
+    );
+    let request = Groth16::<Bls12_377>::create_threshold_prover(
+        SquareChain {
+            x: Some(Fr::from(2u64)),
+            num_squarings: 3,
+        },
+        &pk,
+        &Randomizer::default().public(),
+    )
+    .unwrap()
+    .request();
+    assert_eq!(
+        parties[0].prove_share(&request).unwrap_err(),
+        ThresholdError::UnexpectedRound
+    );
+
+    // the messages of each round survive serialization
+    let mut shares = Vec::new();
+    for party in parties.iter_mut() {
+        let mut bytes = Vec::new();
+        party
+            .reveal(&commitments)
+            .unwrap()
+            .serialize_compressed(&mut bytes)
+            .unwrap();
+        shares.push(ShareMessage::<Bls12_377>::deserialize_compressed(&bytes[..]).unwrap());
+    }
+
+    // a share that does not open its commitment, e.g. chosen after seeing the
+    // others, is rejected
+    let mut tampered = shares.clone();
+    tampered[1].s_delta_g1 = (tampered[1].s_delta_g1 + pk.delta_g1).into_affine();
+    assert_eq!(
+        parties[0].clone().multiply(&tampered).unwrap_err(),
+        ThresholdError::CommitmentMismatch(1)
+    );
+
+    let mut products = Vec::new();
+    for party in parties.iter_mut() {
+        let mut bytes = Vec::new();
+        party
+            .multiply(&shares)
+            .unwrap()
+            .serialize_compressed(&mut bytes)
+            .unwrap();
+        products.push(ProductMessage::<Bls12_377>::deserialize_compressed(&bytes[..]).unwrap());
+    }
+
+    let mut tampered = products.clone();
+    tampered[0].p_share = (tampered[0].p_share + pk.delta_g1).into_affine();
+    assert_eq!(
+        parties[1].finish(&tampered).unwrap_err(),
+        ThresholdError::InvalidShare(0)
+    );
+
+    // every party computes the same randomizer
+    let rnd = parties[0].finish(&products).unwrap();
+    assert_eq!(rnd, parties[1].finish(&products).unwrap());
+}
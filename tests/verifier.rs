@@ -0,0 +1,113 @@
+#![warn(unused)]
+#![deny(
+    trivial_casts,
+    trivial_numeric_casts,
+    variant_size_differences,
+    stable_features,
+    non_shorthand_field_patterns,
+    renamed_and_removed_lints,
+    unsafe_code
+)]
+
+use ark_bls12_377::{Bls12_377, Fr, G1Affine, G1Projective};
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::UniformRand;
+use ark_groth16::{
+    data_structures::{CircuitSpecificSetupPolymorphicSNARK, PolymorphicSNARK},
+    prepare_verifying_key, prepare_verifying_key_with_memory_budget,
+    prepare_verifying_key_with_tables, GammaAbcTables, Groth16, VerifyingKey,
+};
+use ark_std::rand::{rngs::StdRng, RngCore, SeedableRng};
+use ark_std::test_rng;
+
+use common::SquareChain;
+mod common;
+
+#[test]
+fn test_prepare_inputs_with_tables() {
+    let mut rng = StdRng::seed_from_u64(test_rng().next_u64());
+
+    let num_inputs = 81;
+    let vk = VerifyingKey::<Bls12_377> {
+        gamma_abc_g1: (0..=num_inputs)
+            .map(|_| G1Projective::rand(&mut rng).into_affine())
+            .collect(),
+        ..VerifyingKey::default()
+    };
+    let inputs = (0..num_inputs)
+        .map(|_| Fr::rand(&mut rng))
+        .collect::<Vec<_>>();
+    let expected = vk.gamma_abc_g1[0].into_group()
+        + vk.gamma_abc_g1[1..]
+            .iter()
+            .zip(&inputs)
+            .map(|(base, input)| *base * input)
+            .sum::<G1Projective>();
+
+    let pvk = prepare_verifying_key(&vk);
+    assert!(pvk.gamma_abc_g1_tables.is_none());
+    assert_eq!(
+        Groth16::<Bls12_377>::prepare_inputs(&pvk, &inputs).unwrap(),
+        expected
+    );
+
+    let pvk = prepare_verifying_key_with_tables(&vk, 5);
+    assert_eq!(
+        Groth16::<Bls12_377>::prepare_inputs(&pvk, &inputs).unwrap(),
+        expected
+    );
+    assert!(Groth16::<Bls12_377>::prepare_inputs(&pvk, &inputs[1..]).is_err());
+
+    // malformed tables, e.g. from a deserialized key, are rejected
+    let malform = |f: &dyn Fn(&mut GammaAbcTables<Bls12_377>)| {
+        let mut pvk = pvk.clone();
+        f(pvk.gamma_abc_g1_tables.as_mut().unwrap());
+        Groth16::<Bls12_377>::prepare_inputs(&pvk, &inputs).is_err()
+    };
+    assert!(malform(&|tables| tables.window = 64));
+    assert!(malform(&|tables| tables.window = 6));
+    assert!(malform(&|tables| {
+        tables.tables[3].pop();
+    }));
+    assert!(malform(&|tables| {
+        tables.tables[3][2].pop();
+    }));
+
+    // tables are only built when they fit in the budget
+    let size = GammaAbcTables::<Bls12_377>::size_in_bytes(num_inputs, 8);
+    assert!(size > num_inputs * 256 * std::mem::size_of::<G1Affine>());
+    let pvk = prepare_verifying_key_with_memory_budget(&vk, size);
+    assert_eq!(pvk.gamma_abc_g1_tables.as_ref().unwrap().window, 8);
+    assert_eq!(
+        Groth16::<Bls12_377>::prepare_inputs(&pvk, &inputs).unwrap(),
+        expected
+    );
+    let pvk = prepare_verifying_key_with_memory_budget(&vk, size / 100);
+    assert!(pvk.gamma_abc_g1_tables.is_none());
+}
+
+#[test]
+fn test_verify_with_tables() {
+    let mut rng = StdRng::seed_from_u64(test_rng().next_u64());
+
+    let num_squarings = 4;
+    let (pk, vk) = Groth16::<Bls12_377>::setup(
+        SquareChain::<Fr> {
+            x: None,
+            num_squarings,
+        },
+        &mut rng,
+    )
+    .unwrap();
+    let rnd = Groth16::<Bls12_377>::rndgen(&pk, &mut rng).unwrap();
+    let circuit = SquareChain {
+        x: Some(Fr::from(2u64)),
+        num_squarings,
+    };
+    let y = (0..num_squarings).fold(Fr::from(2u64), |y, _| y * y);
+    let proof = Groth16::<Bls12_377>::prove(&pk, circuit, &rnd).unwrap();
+
+    let pvk = prepare_verifying_key_with_tables(&vk, 4);
+    assert!(Groth16::<Bls12_377>::verify_with_processed_vk(&pvk, &[y], &proof, &rnd).unwrap());
+    assert!(!Groth16::<Bls12_377>::verify_with_processed_vk(&pvk, &[y + y], &proof, &rnd).unwrap());
+}